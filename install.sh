@@ -79,6 +79,18 @@ if command -v update-desktop-database &> /dev/null; then
     update-desktop-database "$HOME/.local/share/applications"
 fi
 
+# Register as the handler of stremio:// links, magnet links and torrent files
+# are only claimed when no other application handles them
+if command -v xdg-mime &> /dev/null; then
+    echo "🔗 Registering MIME associations..."
+    xdg-mime default "$(basename "$DESKTOP_FILE")" x-scheme-handler/stremio
+    for mime in x-scheme-handler/magnet application/x-bittorrent; do
+        if [ -z "$(xdg-mime query default "$mime")" ]; then
+            xdg-mime default "$(basename "$DESKTOP_FILE")" "$mime"
+        fi
+    done
+fi
+
 if command -v gtk-update-icon-cache &> /dev/null; then
     gtk-update-icon-cache -f -t "$ICON_DIR" || true
 fi
@@ -94,6 +106,7 @@ echo "   ✓ MPV player with custom configuration"
 echo "   ✓ Discord Rich Presence (enabled by default)"
 echo "   ✓ Timeline thumbnails (Thumbfast)"
 echo "   ✓ Anime4K AI upscaling shaders (Ctrl+1-6)"
+echo "   ✓ Opens stremio:// and magnet links, torrent and media files"
 echo ""
 echo "💡 Shader controls:"
 echo "   Press Ctrl+1-6 during playback to switch Anime4K modes"
@@ -1,7 +1,12 @@
 mod adapters;
+mod monitor;
+mod pip;
+mod state;
+#[cfg(test)]
+mod tests;
 mod utils;
 
-use std::{ffi::CString, path::PathBuf};
+use std::{env, ffi::CString, num::NonZeroU32, path::PathBuf, process::Command};
 
 use ashpd::{
     WindowIdentifier,
@@ -15,32 +20,42 @@ use ashpd::{
 };
 use crossbeam_channel::{Receiver, Sender, unbounded};
 use glutin::{
+    config::Config,
     context::{ContextApi, Version},
     display::GetGlDisplay,
     prelude::GlDisplay,
+    surface::GlSurface,
 };
-use tracing::error;
+use pip::{PipResponse, PipWindow};
+use state::WindowState;
+use tracing::{debug, error, warn};
 use url::Url;
 use winit::{
     application::ApplicationHandler,
-    dpi::PhysicalSize,
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
     event::{ElementState, KeyEvent, Touch, WindowEvent},
     event_loop::ActiveEventLoop,
     keyboard::ModifiersState,
-    platform::wayland::WindowAttributesExtWayland,
+    monitor::MonitorHandle,
+    platform::wayland::{ActiveEventLoopExtWayland, WindowAttributesExtWayland},
     raw_window_handle::{HasDisplayHandle, HasWindowHandle},
     window::{
-        CursorIcon, Fullscreen, Icon as WindowIcon, UserAttentionType, Window, WindowAttributes,
+        CursorIcon, CustomCursor, Fullscreen, Icon as WindowIcon, UserAttentionType, Window,
+        WindowAttributes, WindowId,
     },
 };
 
 use crate::{
-    constants::{APP_ID, APP_NAME, WINDOW_SIZE},
+    config::WindowConfig,
+    constants::{APP_ID, APP_NAME},
+    gpu,
     shared::{
         self,
-        types::{Cursor, MouseState, UserEvent, WindowSize},
+        types::{Cursor, CustomCursorImage, MouseState, UserEvent, WindowSize},
     },
 };
+pub use monitor::MonitorInfo;
+pub use pip::PipAction;
 
 const CONTEXT_API: ContextApi = ContextApi::OpenGl(Some(Version::new(3, 3)));
 
@@ -67,29 +82,54 @@ pub enum AppEvent {
     FileHover((PathBuf, MouseState)),
     FileDrop(MouseState),
     FileCancel,
+    PictureInPicture(bool),
+    PipAction(PipAction),
+    PipResized,
+    /// The main window moved to another monitor, or its scale or refresh rate changed
+    Monitor(MonitorInfo),
+    ScaleFactor(f64),
 }
 
 pub struct App {
     window: Option<Window>,
+    gl_config: Option<Config>,
+    /// The main window is hidden but kept while the picture-in-picture window uses its context
+    hidden: bool,
+    pip: Option<PipWindow>,
     sender: Sender<AppEvent>,
     receiver: Receiver<AppEvent>,
-    maximized: bool,
+    config: WindowConfig,
+    state: WindowState,
+    monitor: Option<MonitorInfo>,
     modifiers_state: ModifiersState,
     mouse_state: MouseState,
+    /// Cursor image of the web UI, created on the next iteration of the event loop
+    custom_cursor: Option<CustomCursorImage>,
     inhibit_request: Option<Request<()>>,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: WindowConfig) -> Self {
         let (sender, receiver) = unbounded::<AppEvent>();
 
+        let state = match config.restore {
+            true => WindowState::load(&config.state_file),
+            false => WindowState::default(),
+        };
+
         Self {
             window: None,
+            gl_config: None,
+            hidden: false,
+            pip: None,
             sender,
             receiver,
-            maximized: false,
+            config,
+            state,
+            monitor: None,
             modifiers_state: ModifiersState::empty(),
             mouse_state: MouseState::default(),
+            custom_cursor: None,
             inhibit_request: None,
         }
     }
@@ -102,21 +142,53 @@ impl App {
         // Load window icon
         let window_icon = Self::load_window_icon();
 
+        let size = self.state.size.map_or(
+            PhysicalSize::<u32>::from(self.config.size),
+            PhysicalSize::from,
+        );
+
         let mut window_attributes = WindowAttributes::default()
             .with_title(APP_NAME)
             .with_name(APP_ID, APP_ID)
             .with_decorations(true)
             .with_resizable(true)
-            .with_maximized(self.maximized)
-            .with_min_inner_size(PhysicalSize::new(900, 600))
-            .with_inner_size(PhysicalSize::<u32>::from(WINDOW_SIZE));
+            .with_maximized(self.state.maximized)
+            .with_min_inner_size(LogicalSize::new(900, 600))
+            .with_inner_size(size);
 
         // Set window icon if loaded successfully
         if let Some(icon) = window_icon {
             window_attributes = window_attributes.with_window_icon(Some(icon));
         }
 
+        let monitor = self
+            .state
+            .monitor
+            .as_deref()
+            .and_then(|name| monitor::find(event_loop.available_monitors(), name));
+
+        // The position is dropped when its monitor was unplugged or rearranged
+        if let Some(position) = self.state.position
+            && monitor
+                .as_ref()
+                .is_some_and(|monitor| monitor::contains(monitor, position))
+        {
+            window_attributes = window_attributes.with_position(PhysicalPosition::from(position));
+        }
+
+        if self.state.fullscreen {
+            let monitor = self
+                .fullscreen_monitor(event_loop.available_monitors())
+                .or(monitor);
+
+            window_attributes =
+                window_attributes.with_fullscreen(Some(Fullscreen::Borderless(monitor)));
+        }
+
         let (window, config) = utils::create_window(event_loop, window_attributes);
+        let window = window.expect("Failed to get window");
+        let window_size = WindowSize::from(window.inner_size());
+        let scale_factor = window.scale_factor();
         let surface = utils::create_surface(&config, &window);
         let context = utils::create_context(&config, CONTEXT_API);
 
@@ -125,33 +197,135 @@ impl App {
             context.display().get_proc_address(&name) as _
         });
 
-        self.window = window;
+        self.window = Some(window);
+        self.gl_config = Some(config);
+        self.hidden = false;
         self.sender.send(AppEvent::Visibility(true)).ok();
 
         shared::create_gl(surface, context);
         shared::with_gl(|_, _| {
+            gpu::read_renderer();
             let refresh_rate = self.get_refresh_rate();
-            shared::create_renderer(WINDOW_SIZE, refresh_rate);
+            shared::create_renderer((window_size.0, window_size.1), refresh_rate, scale_factor);
         });
 
+        self.update_monitor();
         self.sender.send(AppEvent::Ready).ok();
     }
 
     pub fn destroy_window(&mut self) {
+        self.save_state();
+
+        // The picture-in-picture window draws with the context of the main window
+        self.close_pip();
+
         shared::drop_renderer();
         shared::drop_gl();
 
         self.window.take();
+        self.gl_config.take();
+        self.hidden = false;
         self.sender.send(AppEvent::Visibility(false)).ok();
     }
 
+    /// Hides the main window, it's only kept when the picture-in-picture window needs it
+    fn hide_window(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = self.window.as_ref().filter(|_| self.pip.is_some()) else {
+            self.destroy_window();
+            return;
+        };
+
+        // Wayland windows can't be hidden, only minimized
+        match event_loop.is_wayland() {
+            true => window.set_minimized(true),
+            false => window.set_visible(false),
+        }
+
+        self.hidden = true;
+        self.sender.send(AppEvent::Visibility(false)).ok();
+    }
+
+    fn show_window(&mut self, event_loop: &ActiveEventLoop) {
+        match self.window.as_ref() {
+            Some(window) if self.hidden => {
+                window.set_visible(true);
+                window.set_minimized(false);
+                window.focus_window();
+
+                self.hidden = false;
+                self.sender.send(AppEvent::Visibility(true)).ok();
+            }
+            Some(_) => self.notify(),
+            None => self.create_window(event_loop),
+        }
+    }
+
+    fn open_pip(&mut self, event_loop: &ActiveEventLoop) {
+        if self.pip.is_some() {
+            return;
+        }
+
+        if self.window.is_none() {
+            self.create_window(event_loop);
+        }
+
+        if let Some(config) = self.gl_config.as_ref()
+            && let Some((pip, surface)) = PipWindow::new(event_loop, config)
+        {
+            shared::create_pip_gl(surface);
+
+            self.pip = Some(pip);
+            self.sender.send(AppEvent::PictureInPicture(true)).ok();
+        }
+    }
+
+    fn close_pip(&mut self) {
+        if self.pip.take().is_some() {
+            shared::drop_pip_gl();
+            self.sender.send(AppEvent::PictureInPicture(false)).ok();
+        }
+    }
+
+    fn pip_window_event(&mut self, event_loop: &ActiveEventLoop, event: WindowEvent) {
+        let Some(pip) = self.pip.as_mut() else {
+            return;
+        };
+
+        if let WindowEvent::Resized(size) = event
+            && let (Some(width), Some(height)) =
+                (NonZeroU32::new(size.width), NonZeroU32::new(size.height))
+        {
+            shared::with_pip_gl(|surface, context| surface.resize(context, width, height));
+            self.sender.send(AppEvent::PipResized).ok();
+        }
+
+        match pip.window_event(event) {
+            Some(PipResponse::Action(action)) => {
+                self.sender.send(AppEvent::PipAction(action)).ok();
+            }
+            // Closing the picture-in-picture window brings the main one back
+            Some(PipResponse::Close) => {
+                self.close_pip();
+                self.show_window(event_loop);
+            }
+            None => {}
+        }
+    }
+
     pub fn notify(&self) {
         if let Some(window) = self.window.as_ref() {
             window.request_user_attention(Some(UserAttentionType::Informational));
         }
     }
 
-    pub fn set_cursor(&self, cursor: Cursor) {
+    pub fn set_cursor(&mut self, cursor: Cursor) {
+        if let Cursor::Custom(image) = cursor {
+            self.custom_cursor = Some(image);
+            return;
+        }
+
+        self.custom_cursor = None;
+
         if let Some(window) = self.window.as_ref() {
             if let Ok(icon) = TryInto::<CursorIcon>::try_into(cursor) {
                 window.set_cursor(icon);
@@ -165,7 +339,9 @@ impl App {
     pub fn set_fullscreen(&self, state: bool) {
         if let Some(window) = self.window.as_ref() {
             let fullscreen = match state {
-                true => Some(Fullscreen::Borderless(None)),
+                true => Some(Fullscreen::Borderless(
+                    self.fullscreen_monitor(window.available_monitors()),
+                )),
                 false => None,
             };
 
@@ -173,16 +349,125 @@ impl App {
         }
     }
 
-    pub fn get_refresh_rate(&self) -> u32 {
+    pub fn set_fullscreen_monitor(&mut self, name: Option<String>) {
+        self.config.fullscreen_monitor = name;
+    }
+
+    pub fn set_restore(&mut self, restore: bool) {
+        self.config.restore = restore;
+    }
+
+    /// Configured fullscreen monitor, `None` keeps the monitor of the window
+    fn fullscreen_monitor(
+        &self,
+        monitors: impl Iterator<Item = MonitorHandle>,
+    ) -> Option<MonitorHandle> {
+        let name = self.config.fullscreen_monitor.as_deref()?;
+        let monitor = monitor::find(monitors, name);
+
+        if monitor.is_none() {
+            warn!(target: "window", "Monitor {name} not found, using the one of the window");
+        }
+
+        monitor
+    }
+
+    /// Monitor of the main window
+    pub fn monitor(&self) -> Option<&MonitorInfo> {
+        self.monitor.as_ref()
+    }
+
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        self.window
+            .as_ref()
+            .map(|window| {
+                window
+                    .available_monitors()
+                    .map(|monitor| MonitorInfo::from(&monitor))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reports the monitor of the main window when it changes
+    fn update_monitor(&mut self) {
+        let Some(monitor) = self
+            .window
+            .as_ref()
+            .and_then(Window::current_monitor)
+            .map(|monitor| MonitorInfo::from(&monitor))
+        else {
+            return;
+        };
+
+        if self.monitor.as_ref() != Some(&monitor) {
+            self.monitor = Some(monitor.clone());
+            self.sender.send(AppEvent::Monitor(monitor)).ok();
+        }
+    }
+
+    /// Keeps the geometry of the main window, unless it's maximized or fullscreen
+    fn update_geometry(&mut self) {
+        let Some(window) = self
+            .window
+            .as_ref()
+            .filter(|window| !window.is_maximized() && window.fullscreen().is_none())
+        else {
+            return;
+        };
+
+        let size = window.inner_size();
+        self.state.size = Some((size.width, size.height));
+
+        // Wayland doesn't tell the position of windows
+        if let Ok(position) = window.outer_position() {
+            self.state.position = Some((position.x, position.y));
+        }
+    }
+
+    fn save_state(&mut self) {
+        if !self.config.restore {
+            return;
+        }
+
         if let Some(window) = self.window.as_ref() {
-            for monitor in window.available_monitors() {
-                if let Some(m_hz) = monitor.refresh_rate_millihertz() {
-                    return m_hz / 1000;
-                }
-            }
+            self.state.maximized = window.is_maximized();
+            self.state.fullscreen = window.fullscreen().is_some();
+            self.state.monitor = window.current_monitor().and_then(|monitor| monitor.name());
         }
 
-        30
+        self.state.save(&self.config.state_file);
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        self.window.as_ref().map_or(1.0, Window::scale_factor)
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.window.is_some() && !self.hidden
+    }
+
+    /// Size of the picture-in-picture window, when it's open
+    pub fn pip_size(&self) -> Option<(i32, i32)> {
+        self.pip
+            .as_ref()
+            .map(|pip| (pip.size.width as i32, pip.size.height as i32))
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.window
+            .as_ref()
+            .is_some_and(|window| window.fullscreen().is_some())
+    }
+
+    pub fn get_refresh_rate(&self) -> u32 {
+        let monitor = self.window.as_ref().and_then(|window| {
+            window
+                .current_monitor()
+                .or_else(|| window.primary_monitor())
+        });
+
+        monitor::refresh_rate(monitor.as_ref())
     }
 
     pub async fn disable_idling(&mut self) {
@@ -197,7 +482,7 @@ impl App {
             self.inhibit_request = proxy
                 .inhibit(Some(&identifier), flags, reason)
                 .await
-                .map_err(|e| error!("Failed to prevent idling: {e}"))
+                .map_err(|e| error!(target: "window", "Failed to prevent idling: {e}"))
                 .ok();
         }
     }
@@ -207,7 +492,7 @@ impl App {
             request
                 .close()
                 .await
-                .map_err(|e| error!("Failed to allow idling: {e}"))
+                .map_err(|e| error!(target: "window", "Failed to allow idling: {e}"))
                 .ok();
         }
     }
@@ -221,7 +506,7 @@ impl App {
             request
                 .send_uri(&url)
                 .await
-                .map_err(|e| error!("Failed to open uri: {e}"))
+                .map_err(|e| error!(target: "window", "Failed to open uri: {e}"))
                 .ok();
         }
     }
@@ -233,7 +518,7 @@ impl App {
             request
                 .send()
                 .await
-                .map_err(|e| error!("Failed to set background mode: {e}"))
+                .map_err(|e| error!(target: "window", "Failed to set background mode: {e}"))
                 .ok();
         }
     }
@@ -255,8 +540,6 @@ impl App {
         use image::ImageReader;
         use std::io::Cursor;
 
-        println!("🔍 Loading window icon ({} bytes)...", WINDOW_ICON.len());
-
         // Load embedded PNG icon
         match ImageReader::new(Cursor::new(WINDOW_ICON)).with_guessed_format() {
             Ok(reader) => match reader.decode() {
@@ -265,22 +548,22 @@ impl App {
                     let (width, height) = rgba.dimensions();
                     match WindowIcon::from_rgba(rgba.into_raw(), width, height) {
                         Ok(icon) => {
-                            println!("✅ Window icon loaded: {}x{}", width, height);
+                            debug!(target: "window", "Window icon loaded: {width}x{height}");
                             Some(icon)
                         }
                         Err(e) => {
-                            eprintln!("❌ Failed to create window icon: {:?}", e);
+                            error!(target: "window", "Failed to create window icon: {e:?}");
                             None
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("❌ Failed to decode icon: {:?}", e);
+                    error!(target: "window", "Failed to decode icon: {e:?}");
                     None
                 }
             },
             Err(e) => {
-                eprintln!("❌ Failed to read icon: {:?}", e);
+                error!(target: "window", "Failed to read icon: {e:?}");
                 None
             }
         }
@@ -304,10 +587,19 @@ impl ApplicationHandler<UserEvent> for App {
 
     fn window_event(
         &mut self,
-        _event_loop: &ActiveEventLoop,
-        _window_id: winit::window::WindowId,
-        event: winit::event::WindowEvent,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
     ) {
+        if self
+            .pip
+            .as_ref()
+            .is_some_and(|pip| pip.window.id() == window_id)
+        {
+            self.pip_window_event(event_loop, event);
+            return;
+        }
+
         match event {
             WindowEvent::ModifiersChanged(modifiers) => {
                 self.modifiers_state = modifiers.state();
@@ -362,14 +654,21 @@ impl ApplicationHandler<UserEvent> for App {
                 self.sender.send(AppEvent::FileCancel).ok();
             }
             WindowEvent::Resized(size) => {
+                self.update_geometry();
                 self.sender.send(AppEvent::Resized(size.into())).ok();
             }
+            WindowEvent::Moved(_) => {
+                self.update_geometry();
+                self.update_monitor();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.sender.send(AppEvent::ScaleFactor(scale_factor)).ok();
+                self.update_monitor();
+            }
             WindowEvent::Focused(state) => {
                 self.sender.send(AppEvent::Focused(state)).ok();
 
                 if let Some(window) = self.window.as_ref() {
-                    self.maximized = window.is_maximized();
-
                     let minimized = window.is_minimized().unwrap_or(false);
                     self.sender.send(AppEvent::Minimized(minimized)).ok();
                 }
@@ -389,19 +688,80 @@ impl ApplicationHandler<UserEvent> for App {
 
     fn user_event(&mut self, event_loop: &ActiveEventLoop, event: UserEvent) {
         match event {
-            UserEvent::Raise => match self.window.is_some() {
-                true => self.notify(),
-                false => self.create_window(event_loop),
-            },
-            UserEvent::Show => {
-                self.create_window(event_loop);
+            UserEvent::Raise | UserEvent::Show => {
+                self.show_window(event_loop);
             }
             UserEvent::Hide => {
-                self.destroy_window();
+                self.hide_window(event_loop);
+            }
+            UserEvent::PictureInPicture(state) => match state.unwrap_or(self.pip.is_none()) {
+                true => self.open_pip(event_loop),
+                false => self.close_pip(),
+            },
+            UserEvent::SwitchProfile(profile) => {
+                // The profiles have their own sockets, the other one can start before this one quits
+                let args = profile_args(env::args().skip(1), &profile);
+                let started =
+                    env::current_exe().and_then(|exe| Command::new(exe).args(args).spawn());
+
+                match started {
+                    Ok(_) => {
+                        self.save_state();
+                        event_loop.exit();
+                    }
+                    Err(e) => error!(target: "window", "Failed to start profile {profile}: {e}"),
+                }
             }
             UserEvent::Quit => {
+                self.save_state();
                 event_loop.exit();
             }
+            UserEvent::Wake => {}
         }
     }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if let Some(image) = self.custom_cursor.take()
+            && let Some(window) = self.window.as_ref()
+        {
+            let (hotspot_x, hotspot_y) = image.hotspot;
+
+            match CustomCursor::from_rgba(
+                image.rgba,
+                image.width,
+                image.height,
+                hotspot_x,
+                hotspot_y,
+            ) {
+                Ok(source) => {
+                    window.set_cursor(event_loop.create_custom_cursor(source));
+                    window.set_cursor_visible(true);
+                }
+                Err(e) => warn!(target: "window", "Failed to create cursor: {e}"),
+            }
+        }
+    }
+}
+
+/// Arguments of this process with the profile replaced by another one, only the options
+/// of the session are kept: the input to open and the one-shot commands aren't run again
+fn profile_args(args: impl IntoIterator<Item = String>, profile: &str) -> Vec<String> {
+    let mut args = args.into_iter();
+    let mut profile_args = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-d" | "--dev" | "-n" | "--no-server" => profile_args.push(arg),
+            "-u" | "--url" | "--set" => {
+                if let Some(value) = args.next() {
+                    profile_args.extend([arg, value]);
+                }
+            }
+            _ if arg.starts_with("--url=") || arg.starts_with("--set=") => profile_args.push(arg),
+            _ => {}
+        }
+    }
+
+    profile_args.extend(["--profile".to_owned(), profile.to_owned()]);
+    profile_args
 }
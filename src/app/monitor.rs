@@ -0,0 +1,54 @@
+use serde::Serialize;
+use winit::monitor::MonitorHandle;
+
+// Used when no monitor reports its refresh rate
+const DEFAULT_REFRESH_RATE: u32 = 30;
+
+/// A monitor as reported to the web UI and to the rest of the shell
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub position: (i32, i32),
+    pub size: (u32, u32),
+    pub scale_factor: f64,
+    pub refresh_rate: u32,
+}
+
+impl From<&MonitorHandle> for MonitorInfo {
+    fn from(monitor: &MonitorHandle) -> Self {
+        let position = monitor.position();
+        let size = monitor.size();
+
+        Self {
+            name: monitor.name(),
+            position: (position.x, position.y),
+            size: (size.width, size.height),
+            scale_factor: monitor.scale_factor(),
+            refresh_rate: refresh_rate(Some(monitor)),
+        }
+    }
+}
+
+pub fn refresh_rate(monitor: Option<&MonitorHandle>) -> u32 {
+    monitor
+        .and_then(MonitorHandle::refresh_rate_millihertz)
+        .map_or(DEFAULT_REFRESH_RATE, |millihertz| millihertz / 1000)
+}
+
+pub fn find(
+    mut monitors: impl Iterator<Item = MonitorHandle>,
+    name: &str,
+) -> Option<MonitorHandle> {
+    monitors.find(|monitor| monitor.name().as_deref() == Some(name))
+}
+
+/// Whether a point of the desktop is on the monitor
+pub fn contains(monitor: &MonitorHandle, (x, y): (i32, i32)) -> bool {
+    let position = monitor.position();
+    let size = monitor.size();
+
+    x >= position.x
+        && y >= position.y
+        && x < position.x + size.width as i32
+        && y < position.y + size.height as i32
+}
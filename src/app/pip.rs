@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+use glutin::{
+    config::Config,
+    surface::{Surface, WindowSurface},
+};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{Key, NamedKey},
+    platform::wayland::WindowAttributesExtWayland,
+    window::{Window, WindowAttributes, WindowLevel},
+};
+
+use super::utils;
+use crate::constants::{APP_ID, APP_NAME};
+
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(480, 270);
+const MIN_SIZE: PhysicalSize<u32> = PhysicalSize::new(240, 135);
+// Two left clicks within this delay return to the main window
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(400);
+// The controls are shown again after this delay while the cursor moves over the window
+const CONTROLS_DELAY: Duration = Duration::from_secs(1);
+const SEEK_STEP: f64 = 5.0;
+const VOLUME_STEP: f64 = 5.0;
+
+/// Player actions triggered from the picture-in-picture window
+#[derive(Debug, Clone, Copy)]
+pub enum PipAction {
+    TogglePause,
+    Seek(f64),
+    Volume(f64),
+    /// Shows the progress bar of mpv's OSD, the overlay of the window
+    ShowControls,
+}
+
+/// What the main window should do after an event of the picture-in-picture window
+pub enum PipResponse {
+    Action(PipAction),
+    Close,
+}
+
+/// Small borderless always-on-top window rendering only the video
+pub struct PipWindow {
+    pub window: Window,
+    pub size: PhysicalSize<u32>,
+    last_click: Option<Instant>,
+    last_controls: Option<Instant>,
+}
+
+impl PipWindow {
+    /// Creates the window with the GL config of the main window so that both share its context
+    pub fn new(
+        event_loop: &ActiveEventLoop,
+        config: &Config,
+    ) -> Option<(Self, Surface<WindowSurface>)> {
+        let window_attributes = WindowAttributes::default()
+            .with_title(format!("{APP_NAME} - Picture in picture"))
+            .with_name(APP_ID, APP_ID)
+            .with_decorations(false)
+            .with_resizable(true)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+            .with_min_inner_size(MIN_SIZE)
+            .with_inner_size(SIZE);
+
+        let window = utils::create_compatible_window(event_loop, window_attributes, config)?;
+        let surface = utils::create_surface(config, &window);
+
+        let pip = Self {
+            size: window.inner_size(),
+            window,
+            last_click: None,
+            last_controls: None,
+        };
+
+        Some((pip, surface))
+    }
+
+    pub fn window_event(&mut self, event: WindowEvent) -> Option<PipResponse> {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.size = size;
+                None
+            }
+            WindowEvent::CloseRequested => Some(PipResponse::Close),
+            WindowEvent::CursorEntered { .. } | WindowEvent::CursorMoved { .. } => {
+                let shown = self
+                    .last_controls
+                    .is_some_and(|last| last.elapsed() < CONTROLS_DELAY);
+
+                (!shown).then(|| {
+                    self.last_controls = Some(Instant::now());
+                    PipResponse::Action(PipAction::ShowControls)
+                })
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => match button {
+                MouseButton::Left => {
+                    if self
+                        .last_click
+                        .take()
+                        .is_some_and(|last| last.elapsed() < DOUBLE_CLICK_DELAY)
+                    {
+                        return Some(PipResponse::Close);
+                    }
+
+                    self.last_click = Some(Instant::now());
+
+                    // Borderless windows are moved by dragging the video
+                    self.window.drag_window().ok();
+                    None
+                }
+                MouseButton::Right | MouseButton::Middle => {
+                    Some(PipResponse::Action(PipAction::TogglePause))
+                }
+                _ => None,
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                let y = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    MouseScrollDelta::PixelDelta(position) => position.y,
+                };
+
+                let step = y.signum() * VOLUME_STEP;
+                (y != 0.0).then_some(PipResponse::Action(PipAction::Volume(step)))
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key,
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => match logical_key {
+                Key::Named(NamedKey::Escape) => Some(PipResponse::Close),
+                Key::Named(NamedKey::Space) => Some(PipResponse::Action(PipAction::TogglePause)),
+                Key::Named(NamedKey::ArrowLeft) => {
+                    Some(PipResponse::Action(PipAction::Seek(-SEEK_STEP)))
+                }
+                Key::Named(NamedKey::ArrowRight) => {
+                    Some(PipResponse::Action(PipAction::Seek(SEEK_STEP)))
+                }
+                Key::Named(NamedKey::ArrowUp) => {
+                    Some(PipResponse::Action(PipAction::Volume(VOLUME_STEP)))
+                }
+                Key::Named(NamedKey::ArrowDown) => {
+                    Some(PipResponse::Action(PipAction::Volume(-VOLUME_STEP)))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
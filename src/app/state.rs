@@ -0,0 +1,47 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+/// Geometry and state of the main window, saved on close and restored on start
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct WindowState {
+    /// Position of the window on the desktop, unknown on Wayland
+    #[serde(default)]
+    pub position: Option<(i32, i32)>,
+    /// Size of the window when it's neither maximized nor fullscreen
+    #[serde(default)]
+    pub size: Option<(u32, u32)>,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+    #[serde(default)]
+    pub monitor: Option<String>,
+}
+
+impl WindowState {
+    pub fn load(file: &Path) -> Self {
+        fs::read_to_string(file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, file: &Path) {
+        if let Err(e) = self.write(file) {
+            error!(target: "window", "{e}");
+        }
+    }
+
+    fn write(&self, file: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let temp_file = file.with_extension("json.tmp");
+
+        fs::write(&temp_file, content).context("Failed to write window state")?;
+        fs::rename(&temp_file, file).context("Failed to replace window state")?;
+
+        Ok(())
+    }
+}
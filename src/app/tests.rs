@@ -0,0 +1,53 @@
+use super::profile_args;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn profile_replaced() {
+    assert_eq!(
+        profile_args(args(&["--no-server", "--profile", "kids", "--dev"]), "work"),
+        ["--no-server", "--dev", "--profile", "work"]
+    );
+    assert_eq!(
+        profile_args(
+            args(&["--profile=kids", "--set", "resume.auto_seek=true"]),
+            "work"
+        ),
+        ["--set", "resume.auto_seek=true", "--profile", "work"]
+    );
+}
+
+#[test]
+fn profile_added() {
+    assert_eq!(profile_args(args(&[]), "work"), ["--profile", "work"]);
+    assert_eq!(
+        profile_args(args(&["-d", "--url=https://host", "-n"]), "work"),
+        ["-d", "--url=https://host", "-n", "--profile", "work"]
+    );
+}
+
+#[test]
+fn one_shot_args_dropped() {
+    // The input was opened by this process, the new one starts on the web UI
+    assert_eq!(
+        profile_args(args(&["stremio:///detail/movie/tt1"]), "work"),
+        ["--profile", "work"]
+    );
+    assert_eq!(
+        profile_args(
+            args(&["--open", "/videos/a.mkv", "-d", "-o", "a.mkv"]),
+            "work"
+        ),
+        ["-d", "--profile", "work"]
+    );
+    assert_eq!(
+        profile_args(args(&["--diagnose", "--ctl", "pause", "true"]), "work"),
+        ["--profile", "work"]
+    );
+    assert_eq!(
+        profile_args(args(&["--url", "https://host", "--set", "a=1"]), "work"),
+        ["--url", "https://host", "--set", "a=1", "--profile", "work"]
+    );
+}
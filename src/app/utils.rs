@@ -6,6 +6,7 @@ use glutin::{
     surface::{Surface, WindowSurface},
 };
 use glutin_winit::{DisplayBuilder, GlWindow};
+use tracing::error;
 use winit::{
     event_loop::ActiveEventLoop,
     window::{Window, WindowAttributes},
@@ -35,10 +36,19 @@ pub fn create_window(
         .expect("Failed to build display")
 }
 
-pub fn create_surface(config: &Config, window: &Option<Window>) -> Surface<WindowSurface> {
+/// Creates a window whose surfaces can use the contexts of an existing config
+pub fn create_compatible_window(
+    event_loop: &ActiveEventLoop,
+    window_attributes: WindowAttributes,
+    config: &Config,
+) -> Option<Window> {
+    glutin_winit::finalize_window(event_loop, window_attributes, config)
+        .map_err(|e| error!(target: "window", "Failed to create window: {e}"))
+        .ok()
+}
+
+pub fn create_surface(config: &Config, window: &Window) -> Surface<WindowSurface> {
     let surface_attributes = window
-        .as_ref()
-        .expect("Failed to get window")
         .build_surface_attributes(Default::default())
         .expect("Failed to build surface attributes");
 
@@ -0,0 +1,573 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tracing::error;
+
+use super::{
+    layers::{self, Layers},
+    schema,
+};
+use crate::{constants::STARTUP_URL, gpu::GpuVendor, keymap::KeyBinding};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DiscordConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThumbfastConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_thumbfast_height")]
+    pub height: i64,
+}
+
+impl Default for ThumbfastConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            height: 80,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ResumeConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub auto_seek: bool,
+}
+
+impl Default for ResumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            auto_seek: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ShadersConfig {
+    #[serde(default)]
+    pub auto: bool,
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+/// How forced subtitles, which only translate the foreign parts of the dialogue, are picked
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ForcedSubtitles {
+    /// Only when the audio is in a preferred language
+    #[default]
+    Auto,
+    /// Before the full subtitles of a language
+    Always,
+    Never,
+}
+
+/// Subtitle style of a language, unset values keep the ones of mpv.conf
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SubtitleStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub border_size: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SubtitlesConfig {
+    /// Languages selected automatically, in order of preference
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub forced: ForcedSubtitles,
+    /// Prefers subtitles for the deaf and hard of hearing
+    #[serde(default)]
+    pub sdh: bool,
+    /// Styles by language, the `default` one applies to the other languages
+    #[serde(default)]
+    pub styles: HashMap<String, SubtitleStyle>,
+}
+
+/// Audio filter evening out the volume
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum AudioNormalization {
+    #[default]
+    Off,
+    /// Raises quiet passages and lowers loud ones (`dynaudnorm`)
+    Dynamic,
+    /// EBU R128 loudness normalization (`loudnorm`)
+    Loudness,
+}
+
+/// Settings of an audio output device, unset values keep the ones of mpv.conf
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AudioDeviceSettings {
+    #[serde(default)]
+    pub name: String,
+    /// Codecs passed through to the receiver (ex: `ac3`, `dts`, `truehd`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spdif: Option<Vec<String>>,
+    /// Channel layout, ex: `auto`, `stereo`, `5.1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channels: Option<String>,
+    #[serde(default)]
+    pub normalization: AudioNormalization,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AudioConfig {
+    /// Output device, mpv picks one when unset
+    #[serde(default)]
+    pub device: Option<String>,
+    /// Audio languages selected automatically, in order of preference
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub devices: Vec<AudioDeviceSettings>,
+}
+
+impl AudioConfig {
+    pub fn device_settings(&self, device: &str) -> AudioDeviceSettings {
+        self.devices
+            .iter()
+            .find(|settings| settings.name == device)
+            .cloned()
+            .unwrap_or_else(|| AudioDeviceSettings {
+                name: device.to_owned(),
+                ..Default::default()
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerSettings {
+    #[serde(default = "default_server_node")]
+    pub node: String,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            node: default_server_node(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WindowSettings {
+    #[serde(default = "default_window_width")]
+    pub width: i32,
+    #[serde(default = "default_window_height")]
+    pub height: i32,
+    /// Restores the geometry, monitor and state of the window from the last session
+    #[serde(default = "default_true")]
+    pub restore: bool,
+    /// Name of the monitor used for fullscreen (ex: `DP-1`), the one of the window when unset
+    #[serde(default)]
+    pub fullscreen_monitor: Option<String>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: default_window_width(),
+            height: default_window_height(),
+            restore: true,
+            fullscreen_monitor: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WebViewSettings {
+    #[serde(default = "default_webview_url")]
+    pub url: String,
+    #[serde(default = "default_webview_user_agent")]
+    pub user_agent: String,
+    #[serde(default = "default_webview_zoom_step")]
+    pub zoom_step: f64,
+    /// Directory or tar archive of a Stremio Web build, searched before the default locations
+    #[serde(default)]
+    pub bundle: Option<PathBuf>,
+    /// Only a bundle of this version is served when set
+    #[serde(default)]
+    pub bundle_version: Option<String>,
+    /// Loads the bundled web UI when the remote one fails to load
+    #[serde(default = "default_true")]
+    pub fallback: bool,
+}
+
+impl Default for WebViewSettings {
+    fn default() -> Self {
+        Self {
+            url: default_webview_url(),
+            user_agent: default_webview_user_agent(),
+            zoom_step: default_webview_zoom_step(),
+            bundle: None,
+            bundle_version: None,
+            fallback: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GpuConfig {
+    /// Picks the CEF switches from the detected GPU vendor, safe defaults are used otherwise
+    #[serde(default = "default_true")]
+    pub detect: bool,
+    /// Vendor the CEF switches are picked for instead of the detected one
+    #[serde(default)]
+    pub vendor: Option<GpuVendor>,
+    /// Extra CEF switches, appended to the detected ones
+    #[serde(default)]
+    pub switches: Vec<String>,
+    /// Switches removed from the detected ones, by value or by name (ex: `enable-features`)
+    #[serde(default)]
+    pub disabled_switches: Vec<String>,
+    /// mpv hardware decoding, mpv.conf decides when unset
+    #[serde(default)]
+    pub hwdec: Option<String>,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            vendor: None,
+            switches: vec![],
+            disabled_switches: vec![],
+            hwdec: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProfileConfig {
+    /// Uses the mpv config of the default profile, the profile's mpv.conf is loaded on top
+    #[serde(default = "default_true")]
+    pub shared_mpv_config: bool,
+}
+
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        Self {
+            shared_mpv_config: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LoggingSettings {
+    /// Level of every subsystem without its own level (error, warn, info, debug or trace)
+    #[serde(default = "default_logging_level")]
+    pub level: String,
+    /// Levels by subsystem, ex: `{"player": "debug", "mpv": "warn"}`
+    #[serde(default)]
+    pub targets: HashMap<String, String>,
+    /// Writes the logs to the logs directory of the data dir
+    #[serde(default = "default_true")]
+    pub file: bool,
+    /// Number of rotated log files kept next to the current one
+    #[serde(default = "default_logging_max_files")]
+    pub max_files: usize,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: default_logging_level(),
+            targets: HashMap::new(),
+            file: true,
+            max_files: default_logging_max_files(),
+        }
+    }
+}
+
+// Helper functions for serde defaults
+fn default_true() -> bool {
+    true
+}
+
+fn default_thumbfast_height() -> i64 {
+    80
+}
+
+fn default_server_node() -> String {
+    "node".to_owned()
+}
+
+fn default_window_width() -> i32 {
+    1700
+}
+
+fn default_window_height() -> i32 {
+    1050
+}
+
+fn default_webview_url() -> String {
+    STARTUP_URL.to_owned()
+}
+
+fn default_webview_user_agent() -> String {
+    // Custom user agent like community v5
+    "StremioShell/1.0.0".to_owned()
+}
+
+fn default_webview_zoom_step() -> f64 {
+    0.2
+}
+
+fn default_logging_level() -> String {
+    "info".to_owned()
+}
+
+fn default_logging_max_files() -> usize {
+    5
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub discord: DiscordConfig,
+    #[serde(default)]
+    pub thumbfast: ThumbfastConfig,
+    #[serde(default)]
+    pub resume: ResumeConfig,
+    #[serde(default)]
+    pub shaders: ShadersConfig,
+    #[serde(default)]
+    pub subtitles: SubtitlesConfig,
+    #[serde(default)]
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub window: WindowSettings,
+    #[serde(default)]
+    pub webview: WebViewSettings,
+    #[serde(default)]
+    pub gpu: GpuConfig,
+    #[serde(default)]
+    pub logging: LoggingSettings,
+    #[serde(default)]
+    pub profile: ProfileConfig,
+    #[serde(default)]
+    pub keymap: Vec<KeyBinding>,
+    #[serde(default)]
+    pub mpv: HashMap<String, Value>,
+    #[serde(skip)]
+    layers: Layers,
+    #[serde(skip)]
+    changes: Vec<String>,
+}
+
+impl AppConfig {
+    /// Loads the config from its layers, each one overriding the previous:
+    /// defaults, system file, user file, environment and command line
+    pub fn load(layers: Layers) -> Self {
+        let defaults = serde_json::to_value(Self::default()).unwrap_or_default();
+
+        let mut sources = vec![("defaults".to_owned(), defaults)];
+        sources.extend(layers.sources().into_iter().map(|(source, mut value)| {
+            // Invalid values are reported and ignored, the rest of the layer still applies
+            for error in schema::validate(&value).iter().rev() {
+                error!(target: "config", "{source}: {error}, ignoring it");
+                layers::remove_key(&mut value, &error.path);
+            }
+
+            (source, value)
+        }));
+
+        // Layers that still can't be deserialized are dropped, starting with the last one
+        for count in (1..=sources.len()).rev() {
+            let mut merged = Value::Null;
+            sources[..count]
+                .iter()
+                .for_each(|(_, value)| layers::merge(&mut merged, value));
+
+            match serde_json::from_value::<Self>(merged) {
+                Ok(mut config) => {
+                    config.layers = layers;
+                    return config;
+                }
+                Err(e) => {
+                    let (source, _) = &sources[count - 1];
+                    error!(target: "config", "{source}: {e}, ignoring all of its values");
+                }
+            }
+        }
+
+        Self {
+            layers,
+            ..Default::default()
+        }
+    }
+
+    /// Reads the config files again, the keys that changed are reported by `changes`
+    pub fn reload(&mut self) {
+        let mut layers = self.layers.clone();
+        layers.load_files();
+        self.update(layers);
+    }
+
+    fn update(&mut self, layers: Layers) {
+        let previous = serde_json::to_value(&*self).unwrap_or_default();
+
+        let mut changes = std::mem::take(&mut self.changes);
+        *self = Self::load(layers);
+
+        let current = serde_json::to_value(&*self).unwrap_or_default();
+        diff(&previous, &current, &mut vec![], &mut changes);
+        self.changes = changes;
+    }
+
+    /// Takes the dotted keys whose value changed since the last call
+    pub fn changes(&mut self) -> Vec<String> {
+        let mut changes = std::mem::take(&mut self.changes);
+        changes.sort();
+        changes.dedup();
+        changes
+    }
+
+    /// Files the config is read from
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.layers.files()
+    }
+
+    pub fn schema() -> Value {
+        schema::SCHEMA.clone()
+    }
+
+    /// Whether a dotted key can be changed from the web UI, the keys that run programs
+    /// are marked `readOnly` in the schema
+    pub fn writable(key: &str) -> bool {
+        schema::writable(&layers::key_path(key))
+    }
+
+    /// Returns the value of a dotted key, or the whole config without a key
+    pub fn get(&self, key: Option<&str>) -> Option<Value> {
+        let config = serde_json::to_value(self).ok()?;
+
+        match key {
+            Some(key) => layers::key_path(key)
+                .iter()
+                .try_fold(&config, |value, key| value.get(key))
+                .cloned(),
+            None => Some(config),
+        }
+    }
+
+    /// Changes a setting in the user file, valid changes apply even if the file can't be saved
+    pub fn set(&mut self, key: &str, value: Value) -> anyhow::Result<()> {
+        let mut layers = self.layers.clone();
+        layers.set(key, value);
+
+        let path = layers::key_path(key);
+        if let Some(error) = schema::validate(layers.user())
+            .into_iter()
+            .find(|error| error.path.starts_with(&path))
+        {
+            bail!("Invalid value for {key}: {}", error.message);
+        }
+
+        self.update(layers);
+        self.save(key)
+    }
+
+    /// Removes a setting from the user file, the value of the lower layers applies again
+    pub fn unset(&mut self, key: &str) -> anyhow::Result<()> {
+        let mut layers = self.layers.clone();
+        layers.unset(key);
+
+        self.update(layers);
+        self.save(key)
+    }
+
+    fn save(&self, key: &str) -> anyhow::Result<()> {
+        self.layers
+            .save()
+            .with_context(|| format!("Failed to save {key}"))
+    }
+
+    pub fn set_discord_enabled(&mut self, enabled: bool) {
+        if let Err(e) = self.set("discord.enabled", json!(enabled)) {
+            error!(target: "config", "{e:#}");
+        }
+    }
+
+    /// Saves the settings of a device, device names can't be used in dotted keys
+    pub fn set_audio_device_settings(
+        &mut self,
+        settings: AudioDeviceSettings,
+    ) -> anyhow::Result<()> {
+        let mut devices = self.audio.devices.clone();
+        match devices
+            .iter_mut()
+            .find(|device| device.name == settings.name)
+        {
+            Some(device) => *device = settings,
+            None => devices.push(settings),
+        }
+
+        self.set("audio.devices", json!(devices))
+    }
+
+    pub fn set_shader_preset(&mut self, preset: Option<String>) {
+        if let Err(e) = self.set("shaders.preset", json!(preset)) {
+            error!(target: "config", "{e:#}");
+        }
+    }
+}
+
+/// Collects the dotted keys of the leaves that differ, arrays are compared as a whole
+fn diff(previous: &Value, current: &Value, path: &mut Vec<String>, changes: &mut Vec<String>) {
+    match (previous, current) {
+        (Value::Object(previous), Value::Object(current)) => {
+            let mut keys = previous.keys().chain(current.keys()).collect::<Vec<_>>();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                path.push(key.to_owned());
+                diff(
+                    previous.get(key).unwrap_or(&Value::Null),
+                    current.get(key).unwrap_or(&Value::Null),
+                    path,
+                    changes,
+                );
+                path.pop();
+            }
+        }
+        (previous, current) if previous != current => changes.push(path.join(".")),
+        _ => {}
+    }
+}
@@ -0,0 +1,260 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use serde_json::{Map, Value};
+use tracing::{error, info};
+
+use super::migrations;
+use crate::constants::DATA_DIR;
+
+const APP_CONFIG_FILE: &str = "config.json";
+const SYSTEM_CONFIG_DIR: &str = "/etc";
+// Environment variables such as STREMIO_RESUME__AUTO_SEEK=true override resume.auto_seek
+const ENV_PREFIX: &str = "STREMIO_";
+const ENV_SEPARATOR: &str = "__";
+
+/// The raw values of every config source, merged into an `AppConfig` by its loader
+#[derive(Clone, Default)]
+pub struct Layers {
+    data_dir: PathBuf,
+    system_file: PathBuf,
+    user_file: PathBuf,
+    system: Value,
+    user: Value,
+    env: Value,
+    cli: Value,
+    /// Why the user file can't be used, it is never overwritten while this is set
+    user_error: Option<String>,
+}
+
+impl Layers {
+    pub fn new(data_dir: &Path, overrides: &[(String, Value)]) -> Self {
+        Self::with_sources(
+            data_dir,
+            &Path::new(SYSTEM_CONFIG_DIR).join(DATA_DIR),
+            env::vars(),
+            overrides,
+        )
+    }
+
+    /// Reads the files of `system_dir` and `data_dir`, and the overrides of the environment `vars`
+    pub fn with_sources(
+        data_dir: &Path,
+        system_dir: &Path,
+        vars: impl IntoIterator<Item = (String, String)>,
+        overrides: &[(String, Value)],
+    ) -> Self {
+        let mut cli = Value::Null;
+        overrides
+            .iter()
+            .for_each(|(key, value)| set_key(&mut cli, &key_path(key), value.clone()));
+
+        let mut layers = Self {
+            data_dir: data_dir.to_path_buf(),
+            system_file: system_dir.join(APP_CONFIG_FILE),
+            user_file: data_dir.join(APP_CONFIG_FILE),
+            env: env_layer(vars),
+            cli,
+            ..Default::default()
+        };
+
+        layers.load_files();
+        layers
+    }
+
+    /// Reads the system and user files, a broken user file keeps its previous values
+    pub fn load_files(&mut self) {
+        self.system = match read(&self.system_file) {
+            Ok(value) => value.unwrap_or_default(),
+            Err(e) => {
+                error!(target: "config", "{e}");
+                Value::Null
+            }
+        };
+
+        let exists = self.user_file.exists();
+
+        let mut user = match read(&self.user_file) {
+            Ok(value) => value.unwrap_or_else(|| Value::Object(Map::new())),
+            Err(e) => {
+                error!(target: "config", "{e}, it will not be modified until fixed");
+                self.user_error = Some(e);
+                return;
+            }
+        };
+
+        self.user_error = None;
+
+        match migrations::migrate(&mut user, &self.data_dir) {
+            Ok(false) => self.user = user,
+            Ok(true) => {
+                self.user = user;
+
+                if exists {
+                    let backup = self.user_file.with_extension("json.bak");
+                    if let Err(e) = fs::copy(&self.user_file, &backup) {
+                        error!(target: "config", "Failed to back up {}: {e}", backup.display());
+                        return;
+                    }
+
+                    info!(target: "config", "Migrated {}", self.user_file.display());
+                }
+
+                if let Err(e) = self.save() {
+                    error!(target: "config", "{e:#}");
+                }
+            }
+            Err(e) => {
+                error!(target: "config", "{}: {e}", self.user_file.display());
+                self.user = user;
+                self.user_error = Some(e);
+            }
+        }
+    }
+
+    /// Every source with its values, from the lowest to the highest precedence
+    pub fn sources(&self) -> Vec<(String, Value)> {
+        [
+            (self.system_file.display().to_string(), &self.system),
+            (self.user_file.display().to_string(), &self.user),
+            ("environment".to_owned(), &self.env),
+            ("command line".to_owned(), &self.cli),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(source, value)| (source, value.clone()))
+        .collect()
+    }
+
+    pub fn files(&self) -> Vec<PathBuf> {
+        vec![self.system_file.clone(), self.user_file.clone()]
+    }
+
+    pub fn user(&self) -> &Value {
+        &self.user
+    }
+
+    pub fn set(&mut self, key: &str, value: Value) {
+        set_key(&mut self.user, &key_path(key), value);
+    }
+
+    pub fn unset(&mut self, key: &str) {
+        remove_key(&mut self.user, &key_path(key));
+    }
+
+    /// Writes the user file atomically
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(e) = &self.user_error {
+            bail!("Not overwriting {}: {e}", self.user_file.display());
+        }
+
+        let content = serde_json::to_string_pretty(&self.user)?;
+
+        let temp_file = self.user_file.with_extension("json.tmp");
+        fs::write(&temp_file, content)
+            .and_then(|_| fs::rename(&temp_file, &self.user_file))
+            .with_context(|| format!("Failed to write {}", self.user_file.display()))
+    }
+}
+
+/// Parses a `KEY=VALUE` command line override, the value is JSON or a plain string
+pub fn parse_override(arg: &str) -> Result<(String, Value), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.to_owned(), parse_value(value)))
+        .ok_or(format!("Expected KEY=VALUE, got '{arg}'"))
+}
+
+fn parse_value(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or(Value::String(value.to_owned()))
+}
+
+fn env_layer(vars: impl IntoIterator<Item = (String, String)>) -> Value {
+    let mut layer = Value::Null;
+
+    vars.into_iter()
+        .filter_map(|(name, value)| {
+            name.strip_prefix(ENV_PREFIX)
+                .filter(|key| key.contains(ENV_SEPARATOR))
+                .map(|key| (key.to_lowercase().replace(ENV_SEPARATOR, "."), value))
+        })
+        .for_each(|(key, value)| set_key(&mut layer, &key_path(&key), parse_value(&value)));
+
+    layer
+}
+
+/// Reads a JSON file, a missing file is not an error
+fn read(path: &Path) -> Result<Option<Value>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))
+        .and_then(|content| {
+            serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+        })
+}
+
+pub fn key_path(key: &str) -> Vec<String> {
+    key.split('.').map(str::to_owned).collect()
+}
+
+/// Merges `layer` into `base`, objects are merged recursively and anything else is replaced
+pub fn merge(base: &mut Value, layer: &Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, layer) => *base = layer.clone(),
+    }
+}
+
+/// Sets a nested value, creating the objects on its path
+fn set_key(target: &mut Value, path: &[String], value: Value) {
+    let Some((key, rest)) = path.split_first() else {
+        *target = value;
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Some(object) = target.as_object_mut() {
+        set_key(object.entry(key).or_insert(Value::Null), rest, value);
+    }
+}
+
+/// Removes a nested value, array items are addressed by their index
+pub fn remove_key(target: &mut Value, path: &[String]) {
+    let Some((key, parents)) = path.split_last() else {
+        return;
+    };
+
+    let parent = parents.iter().try_fold(target, |value, key| match value {
+        Value::Object(object) => object.get_mut(key),
+        Value::Array(array) => key.parse::<usize>().ok().and_then(|i| array.get_mut(i)),
+        _ => None,
+    });
+
+    match parent {
+        Some(Value::Object(object)) => {
+            object.remove(key);
+        }
+        Some(Value::Array(array)) => {
+            if let Ok(index) = key.parse::<usize>()
+                && index < array.len()
+            {
+                array.remove(index);
+            }
+        }
+        _ => {}
+    }
+}
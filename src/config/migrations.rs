@@ -0,0 +1,63 @@
+use std::{fs, path::Path};
+
+use serde_json::{Value, json};
+use tracing::info;
+
+// Version written to the user file, bumped with every new migration
+pub const CONFIG_VERSION: u64 = 2;
+// Discord settings were stored in their own file before the unified config
+const LEGACY_DISCORD_FILE: &str = "discord.json";
+
+type Migration = fn(&mut Value, &Path);
+
+/// Migrations to apply to a config of version `index`
+const MIGRATIONS: &[Migration] = &[import_legacy_discord, remove_server_port];
+
+/// Brings the user config up to the current version, returns `true` if it was changed
+pub fn migrate(config: &mut Value, data_dir: &Path) -> Result<bool, String> {
+    let version = config.get("version").and_then(Value::as_u64).unwrap_or(0);
+
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "Written by a newer version (config version {version}, supported {CONFIG_VERSION})"
+        ));
+    }
+
+    if version == CONFIG_VERSION {
+        return Ok(false);
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(config, data_dir);
+    }
+
+    if let Some(object) = config.as_object_mut() {
+        object.insert("version".to_owned(), json!(CONFIG_VERSION));
+    }
+
+    Ok(true)
+}
+
+fn import_legacy_discord(config: &mut Value, data_dir: &Path) {
+    let enabled = fs::read_to_string(data_dir.join(LEGACY_DISCORD_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|legacy| legacy.get("enabled").and_then(Value::as_bool));
+
+    if let Some(enabled) = enabled
+        && config.pointer("/discord/enabled").is_none()
+        && let Some(object) = config.as_object_mut()
+    {
+        info!(target: "config", "Imported the Discord settings from {LEGACY_DISCORD_FILE}");
+        object.insert("discord".to_owned(), json!({ "enabled": enabled }));
+    }
+}
+
+/// The streaming server always listens on its default port, `server.port` was never passed to it
+fn remove_server_port(config: &mut Value, _data_dir: &Path) {
+    if let Some(server) = config.get_mut("server").and_then(Value::as_object_mut)
+        && server.remove("port").is_some()
+    {
+        info!(target: "config", "Removed server.port, the server always listens on 11470");
+    }
+}
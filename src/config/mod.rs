@@ -0,0 +1,304 @@
+mod app;
+mod layers;
+mod migrations;
+mod profile;
+mod schema;
+#[cfg(test)]
+mod tests;
+mod watcher;
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+pub use app::{
+    AppConfig, AudioConfig, AudioDeviceSettings, AudioNormalization, ForcedSubtitles, GpuConfig,
+    LoggingSettings, SubtitleStyle, SubtitlesConfig,
+};
+use app::{ResumeConfig, ServerSettings, ShadersConfig, WindowSettings};
+use layers::Layers;
+pub use layers::parse_override;
+pub use profile::{DEFAULT_PROFILE, Profile, parse_profile};
+use serde_json::Value;
+pub use watcher::{ConfigEvent, ConfigWatcher};
+
+use crate::constants::DATA_DIR;
+
+pub struct Config {
+    pub profile: Profile,
+    pub logging: LoggingConfig,
+    pub instance: InstanceConfig,
+    pub server: ServerConfig,
+    pub webview: WebViewConfig,
+    pub tray: TrayConfig,
+    pub window: WindowConfig,
+    pub player: PlayerConfig,
+    pub app: AppConfig,
+}
+
+impl Config {
+    /// `overrides` are the dotted keys set from the command line
+    pub fn new(profile: &str, overrides: &[(String, Value)]) -> Self {
+        let base_data_dir = dirs::data_dir()
+            .expect("Failed to get data dir")
+            .join(DATA_DIR);
+
+        let base_runtime_dir = dirs::runtime_dir()
+            .expect("Failed to get runtime dir")
+            .join(DATA_DIR);
+
+        let profile = Profile::new(profile, &base_data_dir, &base_runtime_dir);
+        let data_dir = &profile.data_dir;
+        let runtime_dir = &profile.runtime_dir;
+
+        fs::create_dir_all(data_dir).expect("Failed to create data directory");
+        fs::create_dir_all(runtime_dir).expect("Failed to create runtime directory");
+
+        let current_exe_path = env::current_exe().expect("Failed to get current exe path");
+        let current_dir = current_exe_path
+            .parent()
+            .expect("Failed to get current directory");
+
+        let app = AppConfig::load(Layers::new(data_dir, overrides));
+        let instance = InstanceConfig::new(runtime_dir);
+        let server = ServerConfig::new(current_dir, data_dir, &app.server);
+        let webview = WebViewConfig::new(current_dir, &profile, &app);
+        let tray = TrayConfig::new(&profile);
+        let window = WindowConfig::new(data_dir, &app.window);
+        let player = PlayerConfig::new(&profile, &app);
+        let logging = LoggingConfig::new(
+            data_dir,
+            &app,
+            &[server.log_file.as_path(), webview.log_file.as_path()],
+        );
+
+        Self {
+            profile,
+            logging,
+            instance,
+            server,
+            webview,
+            tray,
+            window,
+            player,
+            app,
+        }
+    }
+}
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "stremio.log";
+const CRASH_DIR: &str = "crashes";
+
+pub struct LoggingConfig {
+    pub file: PathBuf,
+    pub crash_dir: PathBuf,
+    /// Logs of the other processes, added to the crash reports
+    pub extra_files: Vec<PathBuf>,
+    pub settings: LoggingSettings,
+    /// The effective config, added to the crash reports
+    pub app: Value,
+}
+
+impl LoggingConfig {
+    pub fn new(data_dir: &Path, app: &AppConfig, extra_files: &[&Path]) -> Self {
+        let log_dir = data_dir.join(LOG_DIR);
+        fs::create_dir_all(&log_dir).ok();
+
+        Self {
+            file: log_dir.join(LOG_FILE),
+            crash_dir: data_dir.join(CRASH_DIR),
+            extra_files: extra_files.iter().map(|file| file.to_path_buf()).collect(),
+            settings: app.logging.clone(),
+            app: app.get(None).unwrap_or_default(),
+        }
+    }
+}
+
+const INSTANCE_SOCKET_FILE: &str = "stremio.sock";
+
+pub struct InstanceConfig {
+    pub socket_file: PathBuf,
+}
+
+impl InstanceConfig {
+    pub fn new(runtime_dir: &Path) -> Self {
+        let socket_file = runtime_dir.join(INSTANCE_SOCKET_FILE);
+
+        Self { socket_file }
+    }
+
+    pub fn remove_socket_file(&self) {
+        let _ = fs::remove_file(&self.socket_file);
+    }
+}
+
+const SERVER_FILE: &str = "server.js";
+const SERVER_LOG_FILE: &str = "server.log";
+
+#[derive(Clone)]
+pub struct ServerConfig {
+    pub file: PathBuf,
+    pub log_file: PathBuf,
+    pub node: String,
+    pub env: HashMap<String, String>,
+}
+
+impl ServerConfig {
+    pub fn new(current_dir: &Path, data_dir: &Path, settings: &ServerSettings) -> Self {
+        let file = current_dir.join(SERVER_FILE);
+        let log_file = data_dir.join(SERVER_LOG_FILE);
+
+        Self {
+            file,
+            log_file,
+            node: settings.node.clone(),
+            env: settings.env.clone(),
+        }
+    }
+}
+
+const CEF_DIR: &str = "cef";
+const CEF_CACHE_DIR: &str = "cache";
+const CEF_LOG_FILE: &str = "log";
+const CEF_LOCK_FILE: &str = "SingletonLock";
+const WEB_BUNDLE_DIR: &str = "stremio-web";
+const WEB_BUNDLE_ARCHIVE: &str = "stremio-web.tar";
+
+pub struct WebViewConfig {
+    pub cache_dir: PathBuf,
+    pub log_file: PathBuf,
+    pub lock_file: PathBuf,
+    pub user_agent: String,
+    pub zoom_step: f64,
+    pub gpu: GpuConfig,
+    pub bundle_paths: Vec<PathBuf>,
+    pub bundle_version: Option<String>,
+}
+
+impl WebViewConfig {
+    pub fn new(current_dir: &Path, profile: &Profile, app: &AppConfig) -> Self {
+        let cef_dir = profile.data_dir.join(CEF_DIR);
+        let cache_dir = cef_dir.join(CEF_CACHE_DIR);
+        let log_file = cef_dir.join(CEF_LOG_FILE);
+        let lock_file = cache_dir.join(CEF_LOCK_FILE);
+
+        // The configured bundle comes first, then the data dir and the install dir
+        let bundle_paths = app
+            .webview
+            .bundle
+            .iter()
+            .cloned()
+            .chain(
+                [profile.base_dir.as_path(), current_dir]
+                    .iter()
+                    .flat_map(|dir| [dir.join(WEB_BUNDLE_DIR), dir.join(WEB_BUNDLE_ARCHIVE)]),
+            )
+            .collect();
+
+        Self {
+            cache_dir,
+            log_file,
+            lock_file,
+            user_agent: app.webview.user_agent.clone(),
+            zoom_step: app.webview.zoom_step,
+            gpu: app.gpu.clone(),
+            bundle_paths,
+            bundle_version: app.webview.bundle_version.clone(),
+        }
+    }
+
+    pub fn remove_lock_file(&self) {
+        let _ = fs::remove_file(&self.lock_file);
+    }
+}
+
+const TRAY_ICON_DIR: &str = "tray";
+
+pub struct TrayConfig {
+    pub icon_path: PathBuf,
+    pub profile: String,
+    pub profiles: Vec<String>,
+}
+
+impl TrayConfig {
+    pub fn new(profile: &Profile) -> Self {
+        let icon_path = profile.runtime_dir.join(TRAY_ICON_DIR);
+
+        Self {
+            icon_path,
+            profile: profile.name.clone(),
+            profiles: profile.list(),
+        }
+    }
+}
+
+const WINDOW_STATE_FILE: &str = "window.json";
+
+pub struct WindowConfig {
+    pub state_file: PathBuf,
+    pub size: (i32, i32),
+    pub restore: bool,
+    pub fullscreen_monitor: Option<String>,
+}
+
+impl WindowConfig {
+    pub fn new(data_dir: &Path, settings: &WindowSettings) -> Self {
+        let state_file = data_dir.join(WINDOW_STATE_FILE);
+
+        Self {
+            state_file,
+            size: (settings.width, settings.height),
+            restore: settings.restore,
+            fullscreen_monitor: settings.fullscreen_monitor.clone(),
+        }
+    }
+}
+
+const MPV_CONFIG_DIR: &str = "mpv-portable";
+const MPV_OVERLAY_FILE: &str = "mpv.conf";
+
+pub struct PlayerConfig {
+    pub data_dir: PathBuf,
+    /// Directory of mpv.conf, input.conf, the scripts and the shaders
+    pub mpv_config_dir: PathBuf,
+    /// mpv.conf of the profile, loaded on top of the shared config
+    pub mpv_overlay: Option<PathBuf>,
+    pub resume: ResumeConfig,
+    pub shaders: ShadersConfig,
+    pub subtitles: SubtitlesConfig,
+    pub audio: AudioConfig,
+    pub mpv: HashMap<String, Value>,
+    pub gpu: GpuConfig,
+    /// Runs mpv without video and audio output, nor the user's mpv.conf and scripts
+    pub headless: bool,
+}
+
+impl PlayerConfig {
+    pub fn new(profile: &Profile, app: &AppConfig) -> Self {
+        let shared = app.profile.shared_mpv_config && !profile.is_default();
+
+        let (mpv_config_dir, mpv_overlay) = match shared {
+            true => (
+                profile.base_dir.join(MPV_CONFIG_DIR),
+                Some(profile.data_dir.join(MPV_OVERLAY_FILE)),
+            ),
+            false => (profile.data_dir.join(MPV_CONFIG_DIR), None),
+        };
+
+        Self {
+            data_dir: profile.data_dir.clone(),
+            mpv_config_dir,
+            mpv_overlay,
+            resume: app.resume.clone(),
+            shaders: app.shaders.clone(),
+            subtitles: app.subtitles.clone(),
+            audio: app.audio.clone(),
+            mpv: app.mpv.clone(),
+            gpu: app.gpu.clone(),
+            headless: false,
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+const MAX_NAME_LENGTH: usize = 32;
+
+/// Directories of a profile, the default one keeps the directories of the shell
+/// so that the data of a single profile setup doesn't move
+pub struct Profile {
+    pub name: String,
+    /// Data shared by the profiles: the web UI bundle and the base mpv config
+    pub base_dir: PathBuf,
+    /// Config, CEF cache, mpv config overlay, window state and logs of the profile
+    pub data_dir: PathBuf,
+    /// Instance socket and tray icons of the profile
+    pub runtime_dir: PathBuf,
+}
+
+impl Profile {
+    pub fn new(name: &str, base_dir: &Path, base_runtime_dir: &Path) -> Self {
+        let (data_dir, runtime_dir) = match name == DEFAULT_PROFILE {
+            true => (base_dir.to_path_buf(), base_runtime_dir.to_path_buf()),
+            false => (
+                base_dir.join(PROFILES_DIR).join(name),
+                base_runtime_dir.join(PROFILES_DIR).join(name),
+            ),
+        };
+
+        Self {
+            name: name.to_owned(),
+            base_dir: base_dir.to_path_buf(),
+            data_dir,
+            runtime_dir,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_PROFILE
+    }
+
+    /// Names of the profiles created so far, the default one first
+    pub fn list(&self) -> Vec<String> {
+        let mut names = fs::read_dir(self.base_dir.join(PROFILES_DIR))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| parse_profile(name).is_ok_and(|name| name != DEFAULT_PROFILE))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_owned());
+
+        names
+    }
+}
+
+/// Parses a profile name, it's used as a directory name
+pub fn parse_profile(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty()
+        && value.len() <= MAX_NAME_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    match valid {
+        true => Ok(value.to_owned()),
+        false => Err(format!(
+            "Invalid profile name '{value}', use letters, digits, '-' and '_' \
+             (up to {MAX_NAME_LENGTH})"
+        )),
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::{self, Display};
+
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+/// JSON schema of the app config, only the keywords checked by `validate` are used
+pub static SCHEMA: Lazy<Value> =
+    Lazy::new(|| serde_json::from_str(include_str!("schema.json")).expect("Invalid config schema"));
+
+pub struct SchemaError {
+    pub path: Vec<String>,
+    pub message: String,
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path.join("."), self.message),
+        }
+    }
+}
+
+/// Checks a config layer against the schema, errors are ordered by position in the layer
+pub fn validate(value: &Value) -> Vec<SchemaError> {
+    let mut errors = vec![];
+    validate_node(&SCHEMA, value, &mut vec![], &mut errors);
+    errors
+}
+
+/// Whether the key at `path` can be changed from the web UI, keys marked `readOnly`
+/// and the objects holding them are only set in the config files
+pub fn writable(path: &[String]) -> bool {
+    let mut schema = &*SCHEMA;
+
+    for key in path {
+        if read_only(schema) {
+            return false;
+        }
+
+        let property = schema
+            .get("properties")
+            .and_then(|properties| properties.get(key))
+            .or(schema
+                .get("additionalProperties")
+                .filter(|additional| additional.is_object()));
+
+        match property {
+            Some(property) => schema = property,
+            // Unknown keys are rejected by the validation
+            None => return true,
+        }
+    }
+
+    !contains_read_only(schema)
+}
+
+fn read_only(schema: &Value) -> bool {
+    schema.get("readOnly") == Some(&Value::Bool(true))
+}
+
+fn contains_read_only(schema: &Value) -> bool {
+    read_only(schema)
+        || schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .is_some_and(|properties| properties.values().any(contains_read_only))
+        || schema
+            .get("additionalProperties")
+            .is_some_and(contains_read_only)
+}
+
+fn validate_node(
+    schema: &Value,
+    value: &Value,
+    path: &mut Vec<String>,
+    errors: &mut Vec<SchemaError>,
+) {
+    let mut error = |message: String| {
+        errors.push(SchemaError {
+            path: path.clone(),
+            message,
+        })
+    };
+
+    if let Some(types) = schema.get("type") {
+        let types = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            types => types.as_str().into_iter().collect::<Vec<_>>(),
+        };
+
+        if !types.iter().any(|name| type_matches(name, value)) {
+            error(format!("expected {}, found {value}", types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        error(format!(
+            "expected one of {}, found {value}",
+            allowed.iter().join(", ")
+        ));
+        return;
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64)
+            && number < minimum
+        {
+            error(format!("must be at least {minimum}"));
+            return;
+        }
+
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64)
+            && number > maximum
+        {
+            error(format!("must be at most {maximum}"));
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+
+            for (key, value) in object {
+                let property = properties
+                    .and_then(|properties| properties.get(key))
+                    .or(additional.filter(|additional| additional.is_object()));
+
+                path.push(key.to_owned());
+
+                match property {
+                    Some(schema) => validate_node(schema, value, path, errors),
+                    None if additional == Some(&Value::Bool(false)) => errors.push(SchemaError {
+                        path: path.clone(),
+                        message: "unknown key".to_owned(),
+                    }),
+                    None => {}
+                }
+
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            if let Some(schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    path.push(index.to_string());
+                    validate_node(schema, item, path, errors);
+                    path.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
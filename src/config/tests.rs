@@ -0,0 +1,290 @@
+use std::{fs, path::Path};
+
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+use super::{
+    AppConfig, DEFAULT_PROFILE, PlayerConfig, Profile,
+    layers::{self, Layers},
+    migrations::CONFIG_VERSION,
+    parse_profile,
+};
+
+struct Dirs {
+    system: TempDir,
+    data: TempDir,
+}
+
+impl Dirs {
+    fn new() -> Self {
+        Self {
+            system: TempDir::new().unwrap(),
+            data: TempDir::new().unwrap(),
+        }
+    }
+
+    fn write_system(&self, value: Value) {
+        write(self.system.path(), value);
+    }
+
+    fn write_user(&self, value: Value) {
+        write(self.data.path(), value);
+    }
+
+    fn read_user(&self) -> Value {
+        let content = fs::read_to_string(self.data.path().join("config.json")).unwrap();
+        serde_json::from_str(&content).unwrap()
+    }
+
+    fn load(&self, vars: &[(&str, &str)], overrides: &[(String, Value)]) -> AppConfig {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        AppConfig::load(Layers::with_sources(
+            self.data.path(),
+            self.system.path(),
+            vars,
+            overrides,
+        ))
+    }
+}
+
+fn write(dir: &Path, value: Value) {
+    fs::write(dir.join("config.json"), value.to_string()).unwrap();
+}
+
+#[test]
+fn defaults() {
+    let config = Dirs::new().load(&[], &[]);
+
+    assert!(config.discord.enabled);
+    assert!(!config.resume.auto_seek);
+    assert_eq!(config.window.width, 1700);
+}
+
+#[test]
+fn layer_precedence() {
+    let dirs = Dirs::new();
+    dirs.write_system(json!({ "window": { "width": 1000 }, "server": { "node": "system-node" } }));
+    dirs.write_user(json!({ "window": { "width": 2000 }, "resume": { "auto_seek": true } }));
+
+    let config = dirs.load(&[], &[]);
+    assert_eq!(config.window.width, 2000);
+    assert_eq!(config.server.node, "system-node");
+    assert!(config.resume.auto_seek);
+
+    let config = dirs.load(&[("STREMIO_WINDOW__WIDTH", "3000")], &[]);
+    assert_eq!(config.window.width, 3000);
+
+    let overrides = [layers::parse_override("window.width=4000").unwrap()];
+    let config = dirs.load(&[("STREMIO_WINDOW__WIDTH", "3000")], &overrides);
+    assert_eq!(config.window.width, 4000);
+}
+
+#[test]
+fn invalid_values_are_ignored() {
+    let dirs = Dirs::new();
+    dirs.write_user(json!({ "window": { "width": "wide" }, "server": { "node": "user-node" } }));
+
+    let config = dirs.load(&[("STREMIO_RESUME__AUTO_SEEK", "maybe")], &[]);
+    assert_eq!(config.window.width, 1700);
+    assert_eq!(config.server.node, "user-node");
+    assert!(!config.resume.auto_seek);
+}
+
+#[test]
+fn broken_user_file_is_kept() {
+    let dirs = Dirs::new();
+    fs::write(dirs.data.path().join("config.json"), "{ broken").unwrap();
+
+    let mut config = dirs.load(&[], &[]);
+    assert!(config.discord.enabled);
+    assert!(config.set("discord.enabled", json!(false)).is_err());
+
+    let content = fs::read_to_string(dirs.data.path().join("config.json")).unwrap();
+    assert_eq!(content, "{ broken");
+}
+
+#[test]
+fn set_and_unset() {
+    let dirs = Dirs::new();
+    let mut config = dirs.load(&[], &[]);
+
+    config.set("resume.auto_seek", json!(true)).unwrap();
+    assert!(config.resume.auto_seek);
+    assert_eq!(config.changes(), vec!["resume.auto_seek"]);
+
+    // The user file is created with the current config version
+    assert_eq!(
+        dirs.read_user(),
+        json!({ "version": CONFIG_VERSION, "resume": { "auto_seek": true } })
+    );
+
+    assert!(config.set("window.width", json!("wide")).is_err());
+    assert_eq!(config.window.width, 1700);
+    assert_eq!(config.get(Some("window.width")), Some(json!(1700)));
+
+    config.unset("resume.auto_seek").unwrap();
+    assert!(!config.resume.auto_seek);
+    assert_eq!(
+        dirs.read_user(),
+        json!({ "version": CONFIG_VERSION, "resume": {} })
+    );
+}
+
+#[test]
+fn writable_keys() {
+    assert!(AppConfig::writable("resume.auto_seek"));
+    assert!(AppConfig::writable("subtitles.styles.default.font_size"));
+    assert!(AppConfig::writable("gpu.hwdec"));
+
+    // The keys that run programs, and the objects holding them, are only set in the files
+    for key in [
+        "server.node",
+        "server.env.NODE_OPTIONS",
+        "gpu.switches",
+        "mpv.scripts",
+        "keymap",
+        "version",
+        "server",
+        "gpu",
+    ] {
+        assert!(!AppConfig::writable(key), "{key}");
+    }
+}
+
+#[test]
+fn reload() {
+    let dirs = Dirs::new();
+    let mut config = dirs.load(&[], &[]);
+
+    dirs.write_user(json!({ "window": { "width": 5000 } }));
+    config.reload();
+
+    assert_eq!(config.window.width, 5000);
+    assert_eq!(config.changes(), vec!["window.width"]);
+    assert!(config.changes().is_empty());
+}
+
+#[test]
+fn server_port_removed() {
+    let dirs = Dirs::new();
+    dirs.write_user(json!({ "version": 1, "server": { "port": 2000, "node": "user-node" } }));
+
+    let config = dirs.load(&[], &[]);
+    assert_eq!(config.server.node, "user-node");
+    assert_eq!(
+        dirs.read_user(),
+        json!({ "version": CONFIG_VERSION, "server": { "node": "user-node" } })
+    );
+}
+
+#[test]
+fn overrides() {
+    assert_eq!(
+        layers::parse_override("resume.auto_seek=true"),
+        Ok(("resume.auto_seek".to_owned(), json!(true)))
+    );
+    assert_eq!(
+        layers::parse_override("server.node=/usr/bin/node"),
+        Ok(("server.node".to_owned(), json!("/usr/bin/node")))
+    );
+    assert!(layers::parse_override("resume.auto_seek").is_err());
+}
+
+#[test]
+fn merge_and_remove() {
+    let mut base = json!({ "a": { "b": 1, "c": [1, 2] }, "d": true });
+    layers::merge(&mut base, &json!({ "a": { "c": [3] }, "e": "f" }));
+    assert_eq!(
+        base,
+        json!({ "a": { "b": 1, "c": [3] }, "d": true, "e": "f" })
+    );
+
+    layers::remove_key(&mut base, &layers::key_path("a.b"));
+    layers::remove_key(&mut base, &layers::key_path("a.c.0"));
+    layers::remove_key(&mut base, &layers::key_path("missing.key"));
+    assert_eq!(base, json!({ "a": { "c": [] }, "d": true, "e": "f" }));
+}
+
+#[test]
+fn profile_names() {
+    assert_eq!(parse_profile("work"), Ok("work".to_owned()));
+    assert_eq!(parse_profile("kids_2-tv"), Ok("kids_2-tv".to_owned()));
+
+    for name in [
+        "",
+        "../default",
+        "a/b",
+        ".hidden",
+        "with space",
+        "a".repeat(33).as_str(),
+    ] {
+        assert!(parse_profile(name).is_err(), "{name}");
+    }
+}
+
+#[test]
+fn profile_dirs() {
+    let dirs = Dirs::new();
+    let data = dirs.data.path();
+    let runtime = dirs.system.path();
+
+    // The default profile keeps the directories of a single profile setup
+    let default = Profile::new(DEFAULT_PROFILE, data, runtime);
+    assert!(default.is_default());
+    assert_eq!(default.data_dir, data);
+    assert_eq!(default.runtime_dir, runtime);
+
+    let work = Profile::new("work", data, runtime);
+    assert!(!work.is_default());
+    assert_eq!(work.base_dir, data);
+    assert_eq!(work.data_dir, data.join("profiles/work"));
+    assert_eq!(work.runtime_dir, runtime.join("profiles/work"));
+}
+
+#[test]
+fn profile_list() {
+    let dirs = Dirs::new();
+    let profile = Profile::new(DEFAULT_PROFILE, dirs.data.path(), dirs.system.path());
+
+    assert_eq!(profile.list(), [DEFAULT_PROFILE]);
+
+    for name in ["work", "kids", "not a profile", DEFAULT_PROFILE] {
+        fs::create_dir_all(dirs.data.path().join("profiles").join(name)).unwrap();
+    }
+    fs::write(dirs.data.path().join("profiles/file"), "").unwrap();
+
+    assert_eq!(profile.list(), [DEFAULT_PROFILE, "kids", "work"]);
+}
+
+#[test]
+fn profile_mpv_config() {
+    let dirs = Dirs::new();
+    let data = dirs.data.path();
+    let runtime = dirs.system.path();
+    let mut app = AppConfig::default();
+
+    let default = PlayerConfig::new(&Profile::new(DEFAULT_PROFILE, data, runtime), &app);
+    assert_eq!(default.mpv_config_dir, data.join("mpv-portable"));
+    assert_eq!(default.mpv_overlay, None);
+
+    // The profiles share the base config and load their own mpv.conf on top
+    let profile = Profile::new("work", data, runtime);
+    let shared = PlayerConfig::new(&profile, &app);
+    assert_eq!(shared.data_dir, data.join("profiles/work"));
+    assert_eq!(shared.mpv_config_dir, data.join("mpv-portable"));
+    assert_eq!(
+        shared.mpv_overlay,
+        Some(data.join("profiles/work/mpv.conf"))
+    );
+
+    app.profile.shared_mpv_config = false;
+    let isolated = PlayerConfig::new(&profile, &app);
+    assert_eq!(
+        isolated.mpv_config_dir,
+        data.join("profiles/work/mpv-portable")
+    );
+    assert_eq!(isolated.mpv_overlay, None);
+}
@@ -0,0 +1,57 @@
+use std::{fs, path::PathBuf, thread, time::Duration, time::SystemTime};
+
+use crossbeam_channel::Receiver;
+
+use crate::shared::waker;
+
+// How often the config files are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub enum ConfigEvent {
+    Changed,
+}
+
+/// Watches the modification time of the config files
+pub struct ConfigWatcher {
+    receiver: Receiver<ConfigEvent>,
+}
+
+impl ConfigWatcher {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        let (sender, receiver) = waker::channel::<ConfigEvent>();
+
+        thread::spawn(move || {
+            let mut last_modified = modified(&files);
+
+            loop {
+                thread::sleep(WATCH_INTERVAL);
+
+                let modified = modified(&files);
+                if modified != last_modified {
+                    last_modified = modified;
+
+                    if sender.send(ConfigEvent::Changed).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self { receiver }
+    }
+
+    pub fn events<F: FnMut(ConfigEvent)>(&self, handler: F) {
+        self.receiver.try_iter().for_each(handler);
+    }
+}
+
+fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
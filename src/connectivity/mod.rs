@@ -0,0 +1,301 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TryRecvError, unbounded};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::shared::waker::{self, WakingSender};
+
+// Delays between two probes of an unreachable web UI grow exponentially up to the maximum
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(2);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum ConnectivityState {
+    Online,
+    Connecting {
+        url: String,
+    },
+    Offline {
+        url: String,
+        reason: String,
+        attempt: u32,
+        delay: u64,
+    },
+    /// The remote web UI failed to load and the bundled one replaced it
+    Bundled {
+        url: String,
+    },
+}
+
+pub enum ConnectivityEvent {
+    Changed(ConnectivityState),
+    /// The web UI answers again and can be reloaded
+    Reload(String),
+}
+
+/// Tracks whether the remote web UI can be reached, and probes it after a failed load
+pub struct Connectivity {
+    sender: WakingSender<ConnectivityEvent>,
+    receiver: Receiver<ConnectivityEvent>,
+    state: Arc<Mutex<ConnectivityState>>,
+    monitor: Option<Sender<()>>,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Connectivity {
+    pub fn new() -> Self {
+        Self::with_delays(INITIAL_RETRY_DELAY, MAX_RETRY_DELAY)
+    }
+
+    fn with_delays(initial_delay: Duration, max_delay: Duration) -> Self {
+        let (sender, receiver) = waker::channel::<ConnectivityEvent>();
+
+        Self {
+            sender,
+            receiver,
+            state: Arc::new(Mutex::new(ConnectivityState::Online)),
+            monitor: None,
+            initial_delay,
+            max_delay,
+        }
+    }
+
+    /// Only remote pages are tracked, the bundled UI and the shell pages are always available
+    fn is_remote(url: &str) -> bool {
+        url.starts_with("http://") || url.starts_with("https://")
+    }
+
+    pub fn loading(&mut self, url: &str) {
+        if Self::is_remote(url) {
+            self.stop_monitor();
+            set_state(
+                &self.state,
+                &self.sender,
+                ConnectivityState::Connecting {
+                    url: url.to_owned(),
+                },
+            );
+        }
+    }
+
+    pub fn loaded(&mut self, url: &str) {
+        if Self::is_remote(url) {
+            self.stop_monitor();
+            set_state(&self.state, &self.sender, ConnectivityState::Online);
+        }
+    }
+
+    /// Starts probing `url` until it answers, a `Reload` event is sent once it does
+    pub fn failed(&mut self, url: &str, reason: &str) {
+        if !Self::is_remote(url) {
+            return;
+        }
+
+        if let ConnectivityState::Offline { url: current, .. } = self.state()
+            && current == url
+        {
+            return;
+        }
+
+        self.stop_monitor();
+
+        let client = match Client::builder().timeout(PROBE_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(e) => {
+                error!(target: "connectivity", "Failed to create probe client: {e}");
+                return;
+            }
+        };
+
+        // Reported right away, this also leaves the state of a bundled web UI
+        set_state(
+            &self.state,
+            &self.sender,
+            ConnectivityState::Offline {
+                url: url.to_owned(),
+                reason: reason.to_owned(),
+                attempt: 1,
+                delay: self.initial_delay.as_secs(),
+            },
+        );
+
+        let (monitor_sender, monitor_receiver) = unbounded::<()>();
+        self.monitor = Some(monitor_sender);
+
+        let url = url.to_owned();
+        let reason = reason.to_owned();
+        let sender = self.sender.clone();
+        let state = self.state.clone();
+        let (initial_delay, max_delay) = (self.initial_delay, self.max_delay);
+
+        thread::spawn(move || {
+            let mut attempt = 0;
+
+            loop {
+                // A stop can happen while a probe waits for an answer, the state is kept then
+                let retry = match monitor_receiver.try_recv() {
+                    Ok(()) => true,
+                    Err(TryRecvError::Empty) => false,
+                    Err(TryRecvError::Disconnected) => return,
+                };
+
+                let delay = initial_delay
+                    .saturating_mul(2u32.saturating_pow(attempt))
+                    .min(max_delay);
+                attempt += 1;
+
+                set_probe_state(
+                    &state,
+                    &sender,
+                    ConnectivityState::Offline {
+                        url: url.clone(),
+                        reason: reason.clone(),
+                        attempt,
+                        delay: delay.as_secs(),
+                    },
+                );
+
+                // A retry request skips the rest of the delay, a disconnect stops the probes
+                if !retry
+                    && let Err(RecvTimeoutError::Disconnected) =
+                        monitor_receiver.recv_timeout(delay)
+                {
+                    return;
+                }
+
+                match client.get(&url).send() {
+                    Ok(response) if response.status().is_success() => {
+                        // The monitor may have been stopped while the probe waited for an answer
+                        if let Err(TryRecvError::Disconnected) = monitor_receiver.try_recv() {
+                            return;
+                        }
+
+                        info!(target: "connectivity", "{url} is reachable again");
+                        if set_probe_state(
+                            &state,
+                            &sender,
+                            ConnectivityState::Connecting { url: url.clone() },
+                        ) {
+                            sender.send(ConnectivityEvent::Reload(url)).ok();
+                        }
+                        return;
+                    }
+                    Ok(response) => {
+                        warn!(target: "connectivity", "{url} answered {}", response.status());
+                    }
+                    Err(e) => {
+                        warn!(target: "connectivity", "{url} is unreachable: {e}");
+                    }
+                }
+            }
+        });
+    }
+
+    /// The bundled web UI replaced the failed page `url`, the probes stop so that
+    /// it isn't navigated away from once the remote one answers again
+    pub fn fallback(&mut self, url: &str) {
+        self.stop_monitor();
+        set_state(
+            &self.state,
+            &self.sender,
+            ConnectivityState::Bundled {
+                url: url.to_owned(),
+            },
+        );
+    }
+
+    /// Probes the failed page right away, or loads the remote web UI again
+    /// in place of the bundled one when asked to
+    pub fn retry(&self) {
+        if let ConnectivityState::Bundled { url } = self.state() {
+            set_state(
+                &self.state,
+                &self.sender,
+                ConnectivityState::Connecting { url: url.clone() },
+            );
+            self.sender.send(ConnectivityEvent::Reload(url)).ok();
+            return;
+        }
+
+        if let Some(monitor) = &self.monitor {
+            monitor.send(()).ok();
+        }
+    }
+
+    fn stop_monitor(&mut self) {
+        self.monitor.take();
+    }
+
+    pub fn state(&self) -> ConnectivityState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or(ConnectivityState::Online)
+    }
+
+    pub fn events<F: FnMut(ConnectivityEvent)>(&self, handler: F) {
+        // A probe that answered right before the fallback must not replace the bundled web UI
+        let bundled = matches!(self.state(), ConnectivityState::Bundled { .. });
+
+        self.receiver
+            .try_iter()
+            .filter(|event| !(bundled && matches!(event, ConnectivityEvent::Reload(_))))
+            .for_each(handler);
+    }
+}
+
+fn set_state(
+    current: &Mutex<ConnectivityState>,
+    sender: &WakingSender<ConnectivityEvent>,
+    state: ConnectivityState,
+) {
+    if let Ok(mut current) = current.lock() {
+        if *current == state {
+            return;
+        }
+
+        *current = state.clone();
+    }
+
+    sender.send(ConnectivityEvent::Changed(state)).ok();
+}
+
+/// Sets the state of a probe, unless the bundled web UI replaced the page in the meantime
+fn set_probe_state(
+    current: &Mutex<ConnectivityState>,
+    sender: &WakingSender<ConnectivityEvent>,
+    state: ConnectivityState,
+) -> bool {
+    if let Ok(mut current) = current.lock() {
+        if matches!(*current, ConnectivityState::Bundled { .. }) {
+            return false;
+        }
+
+        if *current == state {
+            return true;
+        }
+
+        *current = state.clone();
+    }
+
+    sender.send(ConnectivityEvent::Changed(state)).ok();
+    true
+}
@@ -0,0 +1,163 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Connectivity, ConnectivityEvent, ConnectivityState};
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Serves one response per status, in order, then stops
+fn server(statuses: &'static [u16]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for status in statuses {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+
+            // A GET request ends with an empty line
+            BufReader::new(&stream)
+                .lines()
+                .map_while(Result::ok)
+                .take_while(|line| !line.is_empty())
+                .for_each(drop);
+
+            write!(
+                stream,
+                "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .ok();
+        }
+    });
+
+    url
+}
+
+/// Collects the events of the monitor until a reload or the timeout
+fn events(connectivity: &Connectivity) -> Vec<ConnectivityEvent> {
+    let start = Instant::now();
+    let mut events = vec![];
+
+    while start.elapsed() < TIMEOUT {
+        connectivity.events(|event| events.push(event));
+
+        if matches!(events.last(), Some(ConnectivityEvent::Reload(_))) {
+            break;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    events
+}
+
+fn offline(url: &str, attempt: u32, delay: u64) -> ConnectivityState {
+    ConnectivityState::Offline {
+        url: url.to_owned(),
+        reason: "ERR_FAILED".to_owned(),
+        attempt,
+        delay,
+    }
+}
+
+#[test]
+fn reload_once_reachable() {
+    let url = server(&[503, 500, 200]);
+    let mut connectivity =
+        Connectivity::with_delays(Duration::from_secs(1), Duration::from_secs(2));
+
+    connectivity.loading(&url);
+    connectivity.failed(&url, "ERR_FAILED");
+
+    let states = events(&connectivity)
+        .into_iter()
+        .map(|event| match event {
+            ConnectivityEvent::Changed(state) => Some(state),
+            ConnectivityEvent::Reload(reload) => {
+                assert_eq!(reload, url);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // The delays double up to the maximum
+    assert_eq!(
+        states,
+        [
+            Some(ConnectivityState::Connecting { url: url.clone() }),
+            Some(offline(&url, 1, 1)),
+            Some(offline(&url, 2, 2)),
+            Some(offline(&url, 3, 2)),
+            Some(ConnectivityState::Connecting { url: url.clone() }),
+            None,
+        ]
+    );
+
+    connectivity.loaded(&url);
+    assert_eq!(connectivity.state(), ConnectivityState::Online);
+}
+
+#[test]
+fn fallback_stops_probes() {
+    let url = server(&[200]);
+    let mut connectivity =
+        Connectivity::with_delays(Duration::from_millis(100), Duration::from_millis(100));
+
+    connectivity.failed(&url, "ERR_FAILED");
+    connectivity.fallback(&url);
+    thread::sleep(Duration::from_millis(500));
+
+    let mut reloaded = false;
+    connectivity.events(|event| reloaded |= matches!(event, ConnectivityEvent::Reload(_)));
+    assert!(!reloaded);
+
+    // The failed page isn't reported as waiting for a retry anymore
+    let bundled = ConnectivityState::Bundled { url: url.clone() };
+    assert_eq!(connectivity.state(), bundled);
+
+    // Failures of the same page while the bundled web UI runs are probed again
+    connectivity.failed(&url, "ERR_FAILED");
+    assert!(matches!(
+        events(&connectivity).last(),
+        Some(ConnectivityEvent::Reload(_))
+    ));
+}
+
+#[test]
+fn retry_from_bundle() {
+    let url = "http://127.0.0.1:1/";
+    let mut connectivity = Connectivity::new();
+
+    connectivity.failed(url, "ERR_FAILED");
+    connectivity.fallback(url);
+    connectivity.retry();
+
+    // The remote web UI is loaded again right away, without waiting for a probe
+    let events = events(&connectivity);
+    assert!(matches!(
+        events.as_slice(),
+        [
+            ..,
+            ConnectivityEvent::Changed(ConnectivityState::Bundled { .. }),
+            ConnectivityEvent::Changed(ConnectivityState::Connecting { .. }),
+            ConnectivityEvent::Reload(reload),
+        ] if reload == url
+    ));
+}
+
+#[test]
+fn local_pages() {
+    let mut connectivity = Connectivity::new();
+
+    connectivity.failed("stremio-app://ui/", "ERR_FAILED");
+    assert_eq!(connectivity.state(), ConnectivityState::Online);
+
+    let mut received = false;
+    connectivity.events(|_| received = true);
+    assert!(!received);
+}
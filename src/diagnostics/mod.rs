@@ -0,0 +1,110 @@
+mod overlay;
+mod report;
+
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::Receiver;
+use overlay::Sample;
+pub use report::{report, system};
+use serde_json::{Map, Value};
+
+use crate::shared::{FrameStats, waker};
+
+// How often the overlay is updated while it's shown
+const INTERVAL: Duration = Duration::from_secs(1);
+/// Player properties shown on the overlay
+pub const PROPERTIES: &[&str] = &[
+    "frame-drop-count",
+    "decoder-frame-drop-count",
+    "video-codec",
+    "hwdec-current",
+    "estimated-vf-fps",
+    "demuxer-cache-duration",
+    "cache-buffering-state",
+    "paused-for-cache",
+];
+
+pub enum DiagnosticsEvent {
+    Update,
+}
+
+/// Overlay of the rendering, playback and IPC counters, drawn on the video by mpv's OSD
+pub struct Diagnostics {
+    enabled: Arc<AtomicBool>,
+    receiver: Receiver<DiagnosticsEvent>,
+    ipc_messages: u64,
+    previous: Option<Sample>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        let enabled = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = waker::channel::<DiagnosticsEvent>();
+
+        // The main loop is only woken up while the overlay is shown
+        let ticker_enabled = enabled.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(INTERVAL);
+
+                if ticker_enabled.load(Ordering::Relaxed)
+                    && sender.send(DiagnosticsEvent::Update).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            enabled,
+            receiver,
+            ipc_messages: 0,
+            previous: None,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether the overlay is now shown
+    pub fn toggle(&mut self) -> bool {
+        let enabled = !self.enabled();
+        self.enabled.store(enabled, Ordering::Relaxed);
+        self.previous = None;
+
+        enabled
+    }
+
+    /// Counts a message received from the web UI
+    pub fn record_ipc(&mut self) {
+        self.ipc_messages += 1;
+    }
+
+    /// Text of the overlay, rates are computed since the previous call
+    pub fn overlay(&mut self, stats: &FrameStats, properties: &Map<String, Value>) -> String {
+        let sample = Sample {
+            time: Instant::now(),
+            frames: stats.frames,
+            video_frames: stats.video_frames,
+            paints: stats.paints,
+            ipc_messages: self.ipc_messages,
+        };
+
+        let text = overlay::format(stats, properties, &sample, self.previous.as_ref());
+        self.previous = Some(sample);
+
+        text
+    }
+
+    pub fn events<F: FnMut(DiagnosticsEvent)>(&self, handler: F) {
+        self.receiver.try_iter().for_each(handler);
+    }
+}
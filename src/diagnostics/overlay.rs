@@ -0,0 +1,83 @@
+use std::time::Instant;
+
+use serde_json::{Map, Value};
+
+use crate::{gpu, shared::FrameStats};
+
+/// Counters at the time of an update of the overlay
+pub struct Sample {
+    pub time: Instant,
+    pub frames: u64,
+    pub video_frames: u64,
+    pub paints: u64,
+    pub ipc_messages: u64,
+}
+
+/// Formats the overlay as an ASS event, rates are unknown until the second update
+pub fn format(
+    stats: &FrameStats,
+    properties: &Map<String, Value>,
+    sample: &Sample,
+    previous: Option<&Sample>,
+) -> String {
+    let rate = |count: fn(&Sample) -> u64| {
+        previous
+            .map(|previous| {
+                let elapsed = sample.time.duration_since(previous.time).as_secs_f64();
+                let count = count(sample).saturating_sub(count(previous)) as f64;
+
+                format!("{:.1}/s", count / elapsed.max(f64::EPSILON))
+            })
+            .unwrap_or("-".to_owned())
+    };
+
+    let property = |name: &str| match properties.get(name) {
+        Some(Value::String(value)) => value.to_owned(),
+        Some(Value::Number(value)) => value
+            .as_f64()
+            .map(|value| format!("{value:.1}"))
+            .unwrap_or(value.to_string()),
+        Some(Value::Bool(value)) => value.to_string(),
+        _ => "-".to_owned(),
+    };
+
+    let lines = [
+        "Diagnostics".to_owned(),
+        format!("Renderer: {}", gpu::renderer().unwrap_or("-")),
+        format!(
+            "Frame time: {:.2} ms, {} frames ({} with video)",
+            stats.frame_time,
+            rate(|sample| sample.frames),
+            rate(|sample| sample.video_frames),
+        ),
+        format!(
+            "Dropped frames: {} output, {} decoder",
+            property("frame-drop-count"),
+            property("decoder-frame-drop-count"),
+        ),
+        format!(
+            "Decoder: {}, hwdec {}, {} fps",
+            property("video-codec"),
+            property("hwdec-current"),
+            property("estimated-vf-fps"),
+        ),
+        format!(
+            "Cache: {} s, buffering {}%, paused for cache {}",
+            property("demuxer-cache-duration"),
+            property("cache-buffering-state"),
+            property("paused-for-cache"),
+        ),
+        format!(
+            "Web UI: {} paints, upload {:.2} ms, {} stalls",
+            rate(|sample| sample.paints),
+            stats.upload_time,
+            stats.upload_stalls,
+        ),
+        format!("IPC: {} messages", rate(|sample| sample.ipc_messages)),
+    ];
+
+    // Braces start override tags in ASS
+    let text = lines.join("\\N").replace('{', "(").replace('}', ")");
+
+    format!("{{\\an7\\fs20\\bord1.5}}{text}")
+}
@@ -0,0 +1,92 @@
+use std::{env, fs};
+
+use serde_json::{Value, json};
+
+use crate::{
+    config::Config,
+    gpu::{self, GpuProbe},
+    instance::{Instance, rpc},
+    player,
+};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Everything support needs to know about the setup, printed by `--diagnose`
+pub fn report(config: Config) -> Value {
+    let vendor = gpu::vendor(&config.app.gpu);
+    let switches = gpu::vendor_switches(vendor, &config.app.gpu);
+    let probe = GpuProbe::new();
+
+    let paths = json!({
+        "config": config.app.files(),
+        "data": config.player.data_dir,
+        "mpv": config.player.mpv_config_dir,
+        "mpv_overlay": config.player.mpv_overlay,
+        "cef_cache": config.webview.cache_dir,
+        "log": config.logging.file,
+        "crashes": config.logging.crash_dir,
+        "server_log": config.server.log_file,
+        "socket": config.instance.socket_file,
+    });
+
+    json!({
+        "shell": VERSION,
+        "profile": config.profile.name,
+        "system": system(),
+        "gpu": {
+            "vendor": vendor,
+            "switches": switches,
+            "devices": probe.devices,
+            "active": probe.active().map(|device| &device.card),
+            "offload": probe.offload,
+            "mpv": gpu::mpv_properties(&config.app.gpu, &probe),
+        },
+        "player": player::probe_versions(),
+        "paths": paths,
+        "instance": instance(Instance::new(config.instance)),
+    })
+}
+
+/// State of the running instance and of its server, asked through the control API
+fn instance(instance: Instance) -> Value {
+    if !instance.running() {
+        return json!({ "running": false });
+    }
+
+    let mut status = Value::Null;
+    let result = instance.control(rpc::methods::STATUS, Value::Null, |message| {
+        if let Some(result) = message.get("result") {
+            status = result.clone();
+        }
+    });
+
+    json!({
+        "running": true,
+        "server": status.get("server"),
+        "connectivity": status.get("connectivity"),
+        "error": result.err().map(|e| e.to_string()),
+    })
+}
+
+/// Distribution, kernel and session of the system
+pub fn system() -> Value {
+    let os = fs::read_to_string("/etc/os-release")
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+                .map(|name| name.trim_matches('"').to_owned())
+        });
+
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|kernel| kernel.trim().to_owned());
+
+    json!({
+        "os": os,
+        "kernel": kernel,
+        "session": env::var("XDG_SESSION_TYPE").ok(),
+        "desktop": env::var("XDG_CURRENT_DESKTOP").ok(),
+    })
+}
@@ -6,6 +6,62 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{error, info};
 
 const APP_ID: &str = "1361448446862692492"; // Stremio Discord App ID
+const ICON_URL: &str =
+    "https://raw.githubusercontent.com/Stremio/stremio-web/refs/heads/development/images/icon.png";
+
+/// Activity shown on Discord, built from the arguments sent by the web UI
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Presence {
+    pub details: String,
+    pub state: String,
+    /// Start and end of the playback, in seconds since the epoch
+    pub timestamps: Option<(i64, i64)>,
+    pub large_image: String,
+    pub large_text: String,
+    /// Image and text of the small image
+    pub small_image: Option<(String, String)>,
+    /// Label and URL of each button
+    pub buttons: Vec<(String, String)>,
+}
+
+impl Presence {
+    fn activity(&self) -> Activity<'_> {
+        let mut assets = Assets::new()
+            .large_image(&self.large_image)
+            .large_text(&self.large_text);
+
+        if let Some((image, text)) = &self.small_image {
+            assets = assets.small_image(image).small_text(text);
+        }
+
+        let mut activity = Activity::new()
+            .details(&self.details)
+            .state(&self.state)
+            .assets(assets);
+
+        if let Some((start, end)) = self.timestamps {
+            activity = activity.timestamps(Timestamps::new().start(start).end(end));
+        }
+
+        if !self.buttons.is_empty() {
+            let buttons = self
+                .buttons
+                .iter()
+                .map(|(label, url)| Button::new(label, url))
+                .collect();
+
+            activity = activity.buttons(buttons);
+        }
+
+        activity
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PresenceUpdate {
+    Set(Presence),
+    Clear,
+}
 
 pub struct Discord {
     client: Option<DiscordIpcClient>,
@@ -30,7 +86,7 @@ impl Discord {
         if let Ok(mut client) = DiscordIpcClient::new(APP_ID) {
             match client.connect() {
                 Ok(_) => {
-                    info!("🎮 Discord Rich Presence connected");
+                    info!(target: "discord", "Rich Presence connected");
                     self.client = Some(client);
                 }
                 Err(_e) => {
@@ -66,148 +122,138 @@ impl Discord {
             }
         }
 
-        if args.is_empty() {
-            return;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        match parse_presence(&args, now) {
+            Some(PresenceUpdate::Set(presence)) => self.update_activity(presence.activity()),
+            Some(PresenceUpdate::Clear) => self.clear(),
+            None => {}
         }
+    }
 
-        match args[0].as_str() {
-            "watching" if args.len() >= 10 => self.set_watching_presence(&args),
-            "meta-detail" if args.len() >= 4 => self.set_meta_detail_presence(&args),
-            "board" => self.set_discover_presence("Resuming Favorites", "On Board"),
-            "discover" => self.set_discover_presence("Finding New Gems", "In Discover"),
-            "library" => self.set_discover_presence("Revisiting Old Favorites", "In Library"),
-            "calendar" => self.set_discover_presence("Planning My Next Binge", "On Calendar"),
-            "addons" => self.set_discover_presence("Exploring Add-ons", "In Add-ons"),
-            "settings" => self.set_discover_presence("Tuning Preferences", "In Settings"),
-            "search" => self.set_discover_presence("Searching for Shows & Movies", "In Search"),
-            "clear" => self.clear(),
-            _ => {}
+    fn update_activity(&mut self, activity: Activity) {
+        if let Some(client) = &mut self.client {
+            if let Err(e) = client.set_activity(activity) {
+                error!(target: "discord", "Failed to update presence: {e}");
+            }
         }
     }
 
-    fn set_watching_presence(&mut self, args: &[String]) {
-        // 0: "watching"
-        // 1: type (movie, series)
-        // 2: title
-        // 3: season
-        // 4: episode
-        // 5: episode name
-        // 6: episode thumbnail (small image)
-        // 7: show/movie image (large image)
-        // 8: elapsed seconds
-        // 9: duration seconds
-        // 10: isPaused ("yes" or "no")
-        // 11: more detail button link (imdb)
-        // 12: watch on stremio button link
-
-        let is_paused = args.get(10).map(|s| s.as_str() == "yes").unwrap_or(false);
-
-        let mut activity = Activity::new().details(&args[2]);
-
-        // Build state string outside the conditional blocks to avoid lifetime issues
-        let state_str = if is_paused {
-            "Paused".to_string()
-        } else if args[1] == "series" {
-            format!("{} (S{}-E{})", args[5], args[3], args[4])
-        } else {
-            "Enjoying a Movie".to_string()
-        };
+    pub fn clear(&mut self) {
+        if let Some(client) = &mut self.client {
+            let _ = client.clear_activity();
+        }
+    }
+}
 
-        activity = activity.state(&state_str);
+/// Converts the arguments of `discord-presence` into an update, `now` is in seconds since the epoch
+pub fn parse_presence(args: &[String], now: i64) -> Option<PresenceUpdate> {
+    let presence = match args.first()?.as_str() {
+        "watching" if args.len() >= 10 => watching_presence(args, now),
+        "meta-detail" if args.len() >= 4 => meta_detail_presence(args),
+        "board" => discover_presence("Resuming Favorites", "On Board"),
+        "discover" => discover_presence("Finding New Gems", "In Discover"),
+        "library" => discover_presence("Revisiting Old Favorites", "In Library"),
+        "calendar" => discover_presence("Planning My Next Binge", "On Calendar"),
+        "addons" => discover_presence("Exploring Add-ons", "In Add-ons"),
+        "settings" => discover_presence("Tuning Preferences", "In Settings"),
+        "search" => discover_presence("Searching for Shows & Movies", "In Search"),
+        "clear" => return Some(PresenceUpdate::Clear),
+        _ => return None,
+    };
 
-        // Set timestamps and images
-        if !is_paused {
-            let elapsed: i64 = args[8].parse().unwrap_or(0);
-            let duration: i64 = args[9].parse().unwrap_or(0);
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64;
+    Some(PresenceUpdate::Set(presence))
+}
 
-            let start_time = now - elapsed;
-            let end_time = now + (duration - elapsed);
+fn watching_presence(args: &[String], now: i64) -> Presence {
+    // 0: "watching"
+    // 1: type (movie, series)
+    // 2: title
+    // 3: season
+    // 4: episode
+    // 5: episode name
+    // 6: episode thumbnail (small image)
+    // 7: show/movie image (large image)
+    // 8: elapsed seconds
+    // 9: duration seconds
+    // 10: isPaused ("yes" or "no")
+    // 11: more detail button link (imdb)
+    // 12: watch on stremio button link
 
-            activity = activity.timestamps(Timestamps::new().start(start_time).end(end_time));
-        }
+    let is_paused = args.get(10).map(|s| s.as_str() == "yes").unwrap_or(false);
+    let is_series = args[1] == "series";
 
-        // Set assets (images)
-        if args[1] == "series" {
-            if let Some(thumb) = args.get(6).filter(|s| !s.is_empty()) {
-                activity = activity.assets(
-                    Assets::new()
-                        .large_image(&args[7])
-                        .large_text(&args[2])
-                        .small_image(thumb)
-                        .small_text(&args[5]),
-                );
-            } else {
-                activity =
-                    activity.assets(Assets::new().large_image(&args[7]).large_text(&args[2]));
-            }
-        } else {
-            activity = activity.assets(Assets::new().large_image(&args[7]).large_text(&args[2]));
-        }
+    let state = if is_paused {
+        "Paused".to_string()
+    } else if is_series {
+        format!("{} (S{}-E{})", args[5], args[3], args[4])
+    } else {
+        "Enjoying a Movie".to_string()
+    };
 
-        // Add buttons
-        let mut buttons = Vec::new();
-        if let Some(imdb) = args.get(11).filter(|s| !s.is_empty()) {
-            buttons.push(Button::new("More Details", imdb));
-        }
-        if let Some(stremio) = args.get(12).filter(|s| !s.is_empty()) {
-            buttons.push(Button::new("Watch on Stremio", stremio));
-        }
-        if !buttons.is_empty() {
-            activity = activity.buttons(buttons);
-        }
+    let timestamps = (!is_paused).then(|| {
+        let elapsed: i64 = args[8].parse().unwrap_or(0);
+        let duration: i64 = args[9].parse().unwrap_or(0);
 
-        self.update_activity(activity);
-    }
+        (now - elapsed, now + (duration - elapsed))
+    });
 
-    fn set_meta_detail_presence(&mut self, args: &[String]) {
-        // 0: "meta-detail"
-        // 1: type (movie, series)
-        // 2: title
-        // 3: image URL
-
-        let state = if args[1] == "movie" {
-            "Exploring a Movie"
-        } else {
-            "Exploring a Series"
-        };
+    let small_image = args
+        .get(6)
+        .filter(|thumb| is_series && !thumb.is_empty())
+        .map(|thumb| (thumb.to_owned(), args[5].to_owned()));
 
-        let activity = Activity::new()
-            .details(&args[2])
-            .state(state)
-            .assets(Assets::new().large_image(&args[3]).large_text(&args[2]));
+    let buttons = [(11, "More Details"), (12, "Watch on Stremio")]
+        .into_iter()
+        .filter_map(|(index, label)| {
+            args.get(index)
+                .filter(|url| !url.is_empty())
+                .map(|url| (label.to_owned(), url.to_owned()))
+        })
+        .collect();
 
-        self.update_activity(activity);
+    Presence {
+        details: args[2].to_owned(),
+        state,
+        timestamps,
+        large_image: args[7].to_owned(),
+        large_text: args[2].to_owned(),
+        small_image,
+        buttons,
     }
+}
 
-    fn set_discover_presence(&mut self, details: &str, state: &str) {
-        let activity = Activity::new()
-            .details(details)
-            .state(state)
-            .assets(
-                Assets::new()
-                    .large_image("https://raw.githubusercontent.com/Stremio/stremio-web/refs/heads/development/images/icon.png")
-                    .large_text("Stremio")
-            );
+fn meta_detail_presence(args: &[String]) -> Presence {
+    // 0: "meta-detail"
+    // 1: type (movie, series)
+    // 2: title
+    // 3: image URL
 
-        self.update_activity(activity);
-    }
+    let state = if args[1] == "movie" {
+        "Exploring a Movie"
+    } else {
+        "Exploring a Series"
+    };
 
-    fn update_activity(&mut self, activity: Activity) {
-        if let Some(client) = &mut self.client {
-            if let Err(e) = client.set_activity(activity) {
-                error!("Failed to update Discord presence: {}", e);
-            }
-        }
+    Presence {
+        details: args[2].to_owned(),
+        state: state.to_owned(),
+        large_image: args[3].to_owned(),
+        large_text: args[2].to_owned(),
+        ..Default::default()
     }
+}
 
-    pub fn clear(&mut self) {
-        if let Some(client) = &mut self.client {
-            let _ = client.clear_activity();
-        }
+fn discover_presence(details: &str, state: &str) -> Presence {
+    Presence {
+        details: details.to_owned(),
+        state: state.to_owned(),
+        large_image: ICON_URL.to_owned(),
+        large_text: "Stremio".to_owned(),
+        ..Default::default()
     }
 }
 
@@ -218,3 +264,76 @@ impl Drop for Discord {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_000_000;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn presence(values: &[&str]) -> Presence {
+        match parse_presence(&args(values), NOW) {
+            Some(PresenceUpdate::Set(presence)) => presence,
+            update => panic!("Unexpected update {update:?}"),
+        }
+    }
+
+    #[test]
+    fn watching_series() {
+        let presence = presence(&[
+            "watching", "series", "Show", "1", "2", "Pilot", "thumb", "poster", "60", "1500", "no",
+            "imdb", "",
+        ]);
+
+        assert_eq!(presence.details, "Show");
+        assert_eq!(presence.state, "Pilot (S1-E2)");
+        assert_eq!(presence.timestamps, Some((NOW - 60, NOW + 1440)));
+        assert_eq!(presence.large_image, "poster");
+        assert_eq!(
+            presence.small_image,
+            Some(("thumb".to_owned(), "Pilot".to_owned()))
+        );
+        assert_eq!(
+            presence.buttons,
+            vec![("More Details".to_owned(), "imdb".to_owned())]
+        );
+    }
+
+    #[test]
+    fn paused_movie() {
+        let presence = presence(&[
+            "watching", "movie", "Film", "", "", "", "thumb", "poster", "60", "1500", "yes",
+        ]);
+
+        assert_eq!(presence.state, "Paused");
+        assert_eq!(presence.timestamps, None);
+        assert_eq!(presence.small_image, None);
+        assert!(presence.buttons.is_empty());
+    }
+
+    #[test]
+    fn pages() {
+        let presence = presence(&["meta-detail", "movie", "Film", "poster"]);
+        assert_eq!(presence.state, "Exploring a Movie");
+        assert_eq!(presence.large_image, "poster");
+
+        let presence = self::presence(&["library"]);
+        assert_eq!(presence.details, "Revisiting Old Favorites");
+        assert_eq!(presence.large_image, ICON_URL);
+    }
+
+    #[test]
+    fn clear_and_invalid() {
+        assert_eq!(
+            parse_presence(&args(&["clear"]), NOW),
+            Some(PresenceUpdate::Clear)
+        );
+        assert_eq!(parse_presence(&args(&["watching", "movie"]), NOW), None);
+        assert_eq!(parse_presence(&args(&["unknown"]), NOW), None);
+        assert_eq!(parse_presence(&[], NOW), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fs, path::Path};
+
+use serde::Serialize;
+
+use super::GpuVendor;
+
+/// A GPU as listed by the kernel in /sys/class/drm
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DrmDevice {
+    /// Name of the card node, ex: `card1`
+    pub card: String,
+    /// Name of the render node, ex: `renderD128`
+    pub render_node: Option<String>,
+    pub vendor: GpuVendor,
+    /// PCI vendor and device ids, ex: `10de:1f95`
+    pub pci_id: Option<String>,
+    /// PCI address, ex: `0000:01:00.0`
+    pub slot: Option<String>,
+    pub driver: Option<String>,
+    /// Whether the firmware initialized the display on this GPU
+    pub boot_vga: bool,
+}
+
+impl DrmDevice {
+    /// Path of the render node in /dev
+    pub fn render_path(&self) -> Option<String> {
+        self.render_node
+            .as_ref()
+            .map(|node| format!("/dev/dri/{node}"))
+    }
+}
+
+/// Reads the cards of a DRM class directory, the connectors (`card0-HDMI-A-1`) are skipped
+pub fn devices(root: &Path) -> Vec<DrmDevice> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return vec![];
+    };
+
+    let mut cards = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| is_card(name))
+        .collect::<Vec<_>>();
+
+    // card10 comes after card9
+    cards.sort_by_key(|name| (name.len(), name.clone()));
+
+    cards
+        .into_iter()
+        .map(|card| device(&root.join(&card).join("device"), card))
+        .collect()
+}
+
+fn is_card(name: &str) -> bool {
+    name.strip_prefix("card")
+        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn device(dir: &Path, card: String) -> DrmDevice {
+    let read = |name: &str| {
+        fs::read_to_string(dir.join(name))
+            .ok()
+            .map(|value| value.trim().to_owned())
+    };
+
+    let uevent = read("uevent").unwrap_or_default();
+    let uevent_value = |key: &str| {
+        uevent
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+            .map(str::to_owned)
+    };
+
+    let vendor_id = read("vendor").map(|id| id.trim_start_matches("0x").to_lowercase());
+    let device_id = read("device").map(|id| id.trim_start_matches("0x").to_lowercase());
+
+    let render_node = fs::read_dir(dir.join("drm")).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.starts_with("renderD"))
+            .min()
+    });
+
+    DrmDevice {
+        card,
+        render_node,
+        vendor: vendor_id
+            .as_deref()
+            .map_or(GpuVendor::Unknown, GpuVendor::from_pci_id),
+        pci_id: vendor_id
+            .zip(device_id)
+            .map(|(vendor, device)| format!("{vendor}:{device}")),
+        slot: uevent_value("PCI_SLOT_NAME"),
+        driver: uevent_value("DRIVER"),
+        boot_vga: read("boot_vga").as_deref() == Some("1"),
+    }
+}
+
+/// Index of the device the GL and VA-API drivers render on and whether it's offloaded
+/// from the one showing the desktop, following Mesa's `DRI_PRIME` and NVIDIA's
+/// `__NV_PRIME_RENDER_OFFLOAD`
+pub fn active(
+    devices: &[DrmDevice],
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<(usize, bool)> {
+    let default = devices
+        .iter()
+        .position(|device| device.boot_vga)
+        .or((!devices.is_empty()).then_some(0))?;
+
+    let offloaded = var("DRI_PRIME")
+        .and_then(|prime| prime_device(devices, default, prime.trim()))
+        .or_else(|| {
+            (var("__NV_PRIME_RENDER_OFFLOAD").as_deref() == Some("1"))
+                .then(|| {
+                    devices
+                        .iter()
+                        .position(|device| device.vendor == GpuVendor::Nvidia)
+                })
+                .flatten()
+        });
+
+    match offloaded {
+        Some(index) => Some((index, index != default)),
+        None => Some((default, false)),
+    }
+}
+
+/// Device selected by a `DRI_PRIME` value: a number (any other GPU than the default one
+/// when not 0), a PCI address written `pci-0000_01_00_0` or a `vendor:device` id
+fn prime_device(devices: &[DrmDevice], default: usize, prime: &str) -> Option<usize> {
+    if let Ok(index) = prime.parse::<usize>() {
+        return match index {
+            0 => Some(default),
+            _ => (0..devices.len()).find(|index| *index != default),
+        };
+    }
+
+    if let Some(slot) = prime.strip_prefix("pci-") {
+        return devices.iter().position(|device| {
+            device
+                .slot
+                .as_ref()
+                .is_some_and(|device_slot| device_slot.replace([':', '.'], "_") == slot)
+        });
+    }
+
+    let prime = prime.to_lowercase();
+    devices
+        .iter()
+        .position(|device| device.pci_id.as_deref() == Some(prime.as_str()))
+}
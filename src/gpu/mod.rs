@@ -0,0 +1,235 @@
+mod drm;
+#[cfg(test)]
+mod tests;
+
+use std::{env, ffi::CStr, path::Path};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::GpuConfig;
+pub use drm::DrmDevice;
+
+const DRM_DIR: &str = "/sys/class/drm";
+
+// GL renderer string of the main window's context
+static RENDERER: OnceCell<String> = OnceCell::new();
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuVendor {
+    Intel,
+    Nvidia,
+    Amd,
+    Unknown,
+}
+
+impl GpuVendor {
+    /// Vendor of a PCI vendor id, ex: `8086`
+    pub fn from_pci_id(id: &str) -> Self {
+        match id {
+            "8086" => GpuVendor::Intel,
+            "10de" => GpuVendor::Nvidia,
+            "1002" => GpuVendor::Amd,
+            _ => GpuVendor::Unknown,
+        }
+    }
+}
+
+/// The GPUs of the system and the one used for rendering
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct GpuProbe {
+    pub devices: Vec<DrmDevice>,
+    /// Index of the device the drivers render on
+    pub active: Option<usize>,
+    /// Whether rendering is offloaded from the GPU showing the desktop (PRIME)
+    pub offload: bool,
+}
+
+impl GpuProbe {
+    /// Enumerates the DRM devices of the system
+    pub fn new() -> Self {
+        Self::from_dir(Path::new(DRM_DIR), |name| env::var(name).ok())
+    }
+
+    pub fn from_dir(root: &Path, var: impl Fn(&str) -> Option<String>) -> Self {
+        let devices = drm::devices(root);
+        let (active, offload) = drm::active(&devices, var).unzip();
+
+        Self {
+            devices,
+            active,
+            offload: offload.unwrap_or(false),
+        }
+    }
+
+    pub fn active(&self) -> Option<&DrmDevice> {
+        self.active.and_then(|index| self.devices.get(index))
+    }
+
+    /// Vendor of the active device
+    pub fn vendor(&self) -> GpuVendor {
+        self.active()
+            .map_or(GpuVendor::Unknown, |device| device.vendor)
+    }
+}
+
+/// Returns the vendor the switches are picked for: the one of the config, the detected
+/// one or unknown when the detection is disabled
+pub fn vendor(config: &GpuConfig) -> GpuVendor {
+    if let Some(vendor) = config.vendor {
+        info!(target: "gpu", "Using the configured vendor {vendor:?}");
+        return vendor;
+    }
+
+    if !config.detect {
+        return GpuVendor::Unknown;
+    }
+
+    let probe = GpuProbe::new();
+    probe.devices.iter().for_each(|device| {
+        info!(
+            target: "gpu",
+            "Found {} ({:?}, {}, driver {})",
+            device.card,
+            device.vendor,
+            device.pci_id.as_deref().unwrap_or("unknown id"),
+            device.driver.as_deref().unwrap_or("unknown"),
+        );
+    });
+
+    match probe.active() {
+        Some(device) => {
+            info!(
+                target: "gpu",
+                "Rendering on {}{}",
+                device.card,
+                if probe.offload { " (PRIME offload)" } else { "" },
+            );
+            device.vendor
+        }
+        None => {
+            info!(target: "gpu", "Could not detect GPU vendor, using default settings");
+            GpuVendor::Unknown
+        }
+    }
+}
+
+/// Returns the CEF command-line switches of the config, detected ones first
+pub fn get_switches(config: &GpuConfig) -> Vec<String> {
+    vendor_switches(vendor(config), config)
+}
+
+/// Returns the CEF command-line switches of a vendor without the disabled ones,
+/// followed by the ones of the config
+pub fn vendor_switches(vendor: GpuVendor, config: &GpuConfig) -> Vec<String> {
+    let mut switches: Vec<String> = get_gpu_switches(vendor)
+        .into_iter()
+        .filter(|switch| !is_disabled(switch, &config.disabled_switches))
+        .map(str::to_owned)
+        .collect();
+    switches.extend(config.switches.iter().cloned());
+
+    switches
+}
+
+/// Whether a switch is disabled by its full value or by its name, ex: `enable-features`
+fn is_disabled(switch: &str, disabled: &[String]) -> bool {
+    let name = switch.split_once('=').map_or(switch, |(name, _)| name);
+    disabled
+        .iter()
+        .any(|disabled| disabled == switch || disabled == name)
+}
+
+/// mpv properties of the GPU: the hardware decoding of the config and the render node
+/// of the active device when it's offloaded, VA-API would use the default one otherwise
+pub fn mpv_properties(config: &GpuConfig, probe: &GpuProbe) -> Vec<(&'static str, String)> {
+    let mut properties = vec![];
+
+    if let Some(hwdec) = &config.hwdec {
+        properties.push(("hwdec", hwdec.clone()));
+    }
+
+    if probe.offload
+        && let Some(device) = probe.active()
+        && matches!(device.vendor, GpuVendor::Intel | GpuVendor::Amd)
+        && let Some(path) = device.render_path()
+    {
+        properties.push(("vaapi-device", path));
+    }
+
+    properties
+}
+
+/// Reads the renderer string of the current GL context, called once the main window has one
+pub fn read_renderer() {
+    let renderer = unsafe {
+        let renderer = gl::GetString(gl::RENDERER);
+        (!renderer.is_null()).then(|| CStr::from_ptr(renderer as *const _).to_string_lossy())
+    };
+
+    if let Some(renderer) = renderer {
+        info!(target: "gpu", "GL renderer: {renderer}");
+
+        // Software rendering makes the video and the web UI slow
+        if renderer.contains("llvmpipe") || renderer.contains("softpipe") {
+            warn!(target: "gpu", "No hardware acceleration for the window, check the GL drivers");
+        }
+
+        RENDERER.get_or_init(|| renderer.into_owned());
+    }
+}
+
+/// GL renderer string of the main window, once it's created
+pub fn renderer() -> Option<&'static str> {
+    RENDERER.get().map(String::as_str)
+}
+
+/// Returns CEF command-line switches based on GPU vendor
+pub fn get_gpu_switches(vendor: GpuVendor) -> Vec<&'static str> {
+    let mut switches = vec![
+        // Disable GCM/FCM to suppress DEPRECATED_ENDPOINT and QUOTA_EXCEEDED errors
+        "disable-background-networking",
+        "disable-component-update",
+        "disable-sync",
+        "disable-notifications",
+        "disable-default-apps",
+    ];
+
+    match vendor {
+        GpuVendor::Intel => {
+            info!(target: "gpu", "Configuring for Intel GPU with VA-API");
+            switches.extend_from_slice(&[
+                "disable-cuda",
+                "enable-features=VaapiVideoDecoder,VaapiVideoEncoder,VaapiIgnoreDriverChecks",
+                "enable-gpu-rasterization",
+                "enable-zero-copy",
+            ]);
+        }
+        GpuVendor::Nvidia => {
+            info!(target: "gpu", "Configuring for NVIDIA GPU");
+            switches.extend_from_slice(&[
+                "enable-gpu-rasterization",
+                "enable-features=VaapiVideoDecoder",
+            ]);
+        }
+        GpuVendor::Amd => {
+            info!(target: "gpu", "Configuring for AMD GPU with VA-API");
+            switches.extend_from_slice(&[
+                "disable-cuda",
+                "enable-features=VaapiVideoDecoder,VaapiVideoEncoder",
+                "enable-gpu-rasterization",
+            ]);
+        }
+        GpuVendor::Unknown => {
+            info!(target: "gpu", "Unknown GPU, using safe defaults");
+            switches.extend_from_slice(&[
+                "disable-cuda",
+                "enable-gpu-rasterization",
+            ]);
+        }
+    }
+
+    switches
+}
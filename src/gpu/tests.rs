@@ -0,0 +1,260 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use tempfile::TempDir;
+
+use super::{GpuProbe, GpuVendor, get_gpu_switches, mpv_properties, vendor_switches};
+use crate::config::GpuConfig;
+
+const INTEL: &str = "0x8086";
+const NVIDIA: &str = "0x10de";
+const AMD: &str = "0x1002";
+
+/// Writes a card the way the kernel lays it out under /sys/class/drm
+fn card(root: &Path, card: &str, render_node: &str, vendor: &str, slot: &str, boot_vga: bool) {
+    let device = root.join(card).join("device");
+    fs::create_dir_all(device.join("drm").join(card)).unwrap();
+    fs::create_dir_all(device.join("drm").join(render_node)).unwrap();
+
+    let driver = match vendor {
+        INTEL => "i915",
+        NVIDIA => "nvidia",
+        _ => "amdgpu",
+    };
+
+    fs::write(device.join("vendor"), format!("{vendor}\n")).unwrap();
+    fs::write(device.join("device"), "0x1f95\n").unwrap();
+    fs::write(
+        device.join("boot_vga"),
+        if boot_vga { "1\n" } else { "0\n" },
+    )
+    .unwrap();
+    fs::write(
+        device.join("uevent"),
+        format!("DRIVER={driver}\nPCI_CLASS=30000\nPCI_SLOT_NAME={slot}\n"),
+    )
+    .unwrap();
+
+    // Connectors are listed next to the cards
+    fs::create_dir_all(root.join(format!("{card}-eDP-1"))).unwrap();
+}
+
+/// Intel GPU showing the desktop and an NVIDIA one for offloading
+fn hybrid() -> TempDir {
+    let root = TempDir::new().unwrap();
+    card(
+        root.path(),
+        "card0",
+        "renderD129",
+        NVIDIA,
+        "0000:01:00.0",
+        false,
+    );
+    card(
+        root.path(),
+        "card1",
+        "renderD128",
+        INTEL,
+        "0000:00:02.0",
+        true,
+    );
+    root
+}
+
+fn probe(root: &TempDir, vars: &[(&str, &str)]) -> GpuProbe {
+    let vars = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<HashMap<_, _>>();
+
+    GpuProbe::from_dir(root.path(), |name| vars.get(name).cloned())
+}
+
+#[test]
+fn devices() {
+    let root = hybrid();
+    let probe = probe(&root, &[]);
+
+    assert_eq!(probe.devices.len(), 2);
+
+    let nvidia = &probe.devices[0];
+    assert_eq!(nvidia.card, "card0");
+    assert_eq!(nvidia.vendor, GpuVendor::Nvidia);
+    assert_eq!(nvidia.pci_id.as_deref(), Some("10de:1f95"));
+    assert_eq!(nvidia.slot.as_deref(), Some("0000:01:00.0"));
+    assert_eq!(nvidia.driver.as_deref(), Some("nvidia"));
+    assert_eq!(nvidia.render_path().as_deref(), Some("/dev/dri/renderD129"));
+    assert!(!nvidia.boot_vga);
+
+    let intel = &probe.devices[1];
+    assert_eq!(intel.vendor, GpuVendor::Intel);
+    assert_eq!(intel.driver.as_deref(), Some("i915"));
+    assert!(intel.boot_vga);
+}
+
+#[test]
+fn cards_are_sorted_by_index() {
+    let root = TempDir::new().unwrap();
+    card(
+        root.path(),
+        "card10",
+        "renderD138",
+        AMD,
+        "0000:0a:00.0",
+        false,
+    );
+    card(
+        root.path(),
+        "card9",
+        "renderD137",
+        INTEL,
+        "0000:00:02.0",
+        false,
+    );
+
+    let probe = probe(&root, &[]);
+    let cards = probe
+        .devices
+        .iter()
+        .map(|device| device.card.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(cards, ["card9", "card10"]);
+    // Without a boot VGA device, the first one is used
+    assert_eq!(probe.vendor(), GpuVendor::Intel);
+}
+
+#[test]
+fn missing_files() {
+    let root = TempDir::new().unwrap();
+    fs::create_dir_all(root.path().join("card0").join("device")).unwrap();
+
+    let probe = probe(&root, &[]);
+    let device = &probe.devices[0];
+
+    assert_eq!(device.vendor, GpuVendor::Unknown);
+    assert_eq!(device.pci_id, None);
+    assert_eq!(device.render_node, None);
+    assert_eq!(device.driver, None);
+}
+
+#[test]
+fn no_devices() {
+    let root = TempDir::new().unwrap();
+    let probe = probe(&root, &[]);
+
+    assert!(probe.devices.is_empty());
+    assert_eq!(probe.active(), None);
+    assert_eq!(probe.vendor(), GpuVendor::Unknown);
+    assert!(!probe.offload);
+
+    let probe = GpuProbe::from_dir(&root.path().join("missing"), |_| None);
+    assert!(probe.devices.is_empty());
+}
+
+#[test]
+fn boot_vga_is_active() {
+    let root = hybrid();
+    let probe = probe(&root, &[]);
+
+    // The first device isn't the one showing the desktop on hybrid laptops
+    assert_eq!(probe.active, Some(1));
+    assert_eq!(probe.vendor(), GpuVendor::Intel);
+    assert!(!probe.offload);
+}
+
+#[test]
+fn prime_offload() {
+    let root = hybrid();
+
+    for vars in [
+        [("DRI_PRIME", "1")],
+        [("DRI_PRIME", "pci-0000_01_00_0")],
+        [("DRI_PRIME", "10DE:1F95")],
+        [("__NV_PRIME_RENDER_OFFLOAD", "1")],
+    ] {
+        let probe = probe(&root, &vars);
+        assert_eq!(probe.vendor(), GpuVendor::Nvidia, "{vars:?}");
+        assert!(probe.offload, "{vars:?}");
+    }
+
+    for vars in [
+        [("DRI_PRIME", "0")],
+        [("DRI_PRIME", "pci-0000_02_00_0")],
+        [("__NV_PRIME_RENDER_OFFLOAD", "0")],
+    ] {
+        let probe = probe(&root, &vars);
+        assert_eq!(probe.vendor(), GpuVendor::Intel, "{vars:?}");
+        assert!(!probe.offload, "{vars:?}");
+    }
+}
+
+#[test]
+fn vaapi_device() {
+    let root = TempDir::new().unwrap();
+    card(
+        root.path(),
+        "card0",
+        "renderD128",
+        INTEL,
+        "0000:00:02.0",
+        true,
+    );
+    card(
+        root.path(),
+        "card1",
+        "renderD129",
+        AMD,
+        "0000:03:00.0",
+        false,
+    );
+
+    let config = GpuConfig::default();
+
+    let default = probe(&root, &[]);
+    assert!(mpv_properties(&config, &default).is_empty());
+
+    let offloaded = probe(&root, &[("DRI_PRIME", "1")]);
+    assert_eq!(
+        mpv_properties(&config, &offloaded),
+        [("vaapi-device", "/dev/dri/renderD129".to_owned())]
+    );
+
+    let config = GpuConfig {
+        hwdec: Some("no".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(
+        mpv_properties(&config, &offloaded),
+        [
+            ("hwdec", "no".to_owned()),
+            ("vaapi-device", "/dev/dri/renderD129".to_owned())
+        ]
+    );
+}
+
+#[test]
+fn switch_overrides() {
+    let config = GpuConfig {
+        switches: vec!["disable-gpu-vsync".to_owned()],
+        disabled_switches: vec!["enable-zero-copy".to_owned(), "enable-features".to_owned()],
+        ..Default::default()
+    };
+
+    let switches = vendor_switches(GpuVendor::Intel, &config);
+
+    assert!(switches.contains(&"enable-gpu-rasterization".to_owned()));
+    assert!(!switches.contains(&"enable-zero-copy".to_owned()));
+    assert!(
+        !switches
+            .iter()
+            .any(|switch| switch.starts_with("enable-features"))
+    );
+    assert_eq!(
+        switches.last().map(String::as_str),
+        Some("disable-gpu-vsync")
+    );
+    assert_eq!(
+        switches.len(),
+        get_gpu_switches(GpuVendor::Intel).len() - 2 + 1
+    );
+}
@@ -0,0 +1,296 @@
+pub mod rpc;
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, bail};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use rpc::{ControlEvent, RpcError, RpcRequest, RpcResult};
+use serde_json::{Value, json};
+use tracing::{debug, warn};
+
+use crate::{
+    config::InstanceConfig,
+    player::MpvProperty,
+    shared::waker::{self, WakingSender},
+};
+
+pub struct ControlResponder {
+    pub session: u64,
+    id: Option<Value>,
+    sender: Sender<String>,
+}
+
+impl ControlResponder {
+    /// Sends the result back to the client, notifications (requests without an id) get no reply
+    pub fn respond(&self, result: RpcResult) {
+        if self.id.is_some() {
+            let response = rpc::create_response(self.id.clone(), result);
+            self.sender.send(response).ok();
+        }
+    }
+}
+
+pub enum InstanceEvent {
+    Open(String),
+    Control(ControlEvent, ControlResponder),
+}
+
+struct Session {
+    sender: Sender<String>,
+    properties: HashSet<String>,
+}
+
+type Sessions = Arc<Mutex<HashMap<u64, Session>>>;
+
+pub struct Instance {
+    sender: WakingSender<InstanceEvent>,
+    receiver: Receiver<InstanceEvent>,
+    socket: Option<UnixStream>,
+    sessions: Sessions,
+    config: InstanceConfig,
+}
+
+impl Instance {
+    pub fn new(config: InstanceConfig) -> Self {
+        let (sender, receiver) = waker::channel::<InstanceEvent>();
+        let socket = UnixStream::connect(&config.socket_file).ok();
+
+        Self {
+            sender,
+            receiver,
+            socket,
+            sessions: Default::default(),
+            config,
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.socket.is_some()
+    }
+
+    pub fn send(&self, data: String) {
+        if let Some(mut stream) = self.socket.as_ref() {
+            stream
+                .write_all(data.as_bytes())
+                .expect("Failed to write to stream");
+        }
+    }
+
+    /// Calls a method of the control API on the running instance and passes every
+    /// message received back to `handler`, subscriptions keep the connection open
+    pub fn control<F: FnMut(&Value)>(
+        &self,
+        method: &str,
+        params: Value,
+        mut handler: F,
+    ) -> anyhow::Result<()> {
+        let Some(mut stream) = self.socket.as_ref() else {
+            bail!("No running instance found at {:?}", self.config.socket_file);
+        };
+
+        let request = rpc::create_request(1, method, params);
+        writeln!(stream, "{request}").context("Failed to send request")?;
+
+        let keep_open = method == rpc::methods::SUBSCRIBE;
+
+        for line in BufReader::new(stream).lines() {
+            let line = line.context("Failed to read response")?;
+            let message = serde_json::from_str::<Value>(&line).context("Invalid response")?;
+
+            handler(&message);
+
+            if message.get("id").is_some_and(|id| id == &json!(1)) {
+                if let Some(error) = message.get("error") {
+                    bail!("Request failed: {error}");
+                }
+
+                if !keep_open {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn start(&self) {
+        self.config.remove_socket_file();
+
+        let listener =
+            UnixListener::bind(&self.config.socket_file).expect("Failed to create socket");
+
+        let sender = self.sender.clone();
+        let sessions = self.sessions.clone();
+        thread::spawn(move || {
+            let next_session = AtomicU64::new(1);
+
+            for stream in listener.incoming().flatten() {
+                let session = next_session.fetch_add(1, Ordering::Relaxed);
+                let sender = sender.clone();
+                let sessions = sessions.clone();
+
+                thread::spawn(move || {
+                    handle_connection(stream, session, sender, sessions);
+                });
+            }
+        });
+    }
+
+    pub fn stop(&self) {
+        self.config.remove_socket_file();
+    }
+
+    pub fn events<F: FnMut(InstanceEvent)>(&self, handler: F) {
+        self.receiver.try_iter().for_each(handler);
+    }
+
+    pub fn subscribe(&self, session: u64, names: Vec<String>) {
+        if let Ok(mut sessions) = self.sessions.lock()
+            && let Some(session) = sessions.get_mut(&session)
+        {
+            session.properties.extend(names);
+        }
+    }
+
+    pub fn unsubscribe(&self, session: u64, names: Vec<String>) {
+        if let Ok(mut sessions) = self.sessions.lock()
+            && let Some(session) = sessions.get_mut(&session)
+        {
+            names.iter().for_each(|name| {
+                session.properties.remove(name);
+            });
+        }
+    }
+
+    /// Forwards a property change to every session subscribed to it
+    pub fn notify_property(&self, property: &MpvProperty) {
+        if let Ok(sessions) = self.sessions.lock() {
+            let subscribers = sessions
+                .values()
+                .filter(|session| session.properties.contains(property.name()))
+                .collect::<Vec<_>>();
+
+            if !subscribers.is_empty() {
+                let notification =
+                    rpc::create_notification(rpc::PROPERTY_CHANGE_NOTIFICATION, json!(property));
+
+                subscribers.iter().for_each(|session| {
+                    session.sender.send(notification.clone()).ok();
+                });
+            }
+        }
+    }
+}
+
+/// Reads the first line of a connection to tell a JSON-RPC session
+/// apart from a raw deeplink sent by a second instance of the shell
+fn handle_connection(
+    stream: UnixStream,
+    session: u64,
+    sender: WakingSender<InstanceEvent>,
+    sessions: Sessions,
+) {
+    let Ok(reader_stream) = stream.try_clone() else {
+        return;
+    };
+
+    let mut reader = BufReader::new(reader_stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).is_err() {
+        return;
+    }
+
+    if RpcRequest::parse(&line).is_none() {
+        let mut rest = String::new();
+        if reader.read_to_string(&mut rest).is_ok() {
+            sender.send(InstanceEvent::Open(line + &rest)).ok();
+        }
+
+        return;
+    }
+
+    debug!(target: "instance", "Control session {session} opened");
+
+    let (responder, responses) = unbounded::<String>();
+
+    if let Ok(mut sessions) = sessions.lock() {
+        sessions.insert(
+            session,
+            Session {
+                sender: responder.clone(),
+                properties: HashSet::new(),
+            },
+        );
+    }
+
+    let mut writer = stream;
+    let writer_thread = thread::spawn(move || {
+        for response in responses.iter() {
+            if writeln!(writer, "{response}").is_err() {
+                break;
+            }
+        }
+    });
+
+    let lines = std::iter::once(Ok(line)).chain(reader.lines());
+    for line in lines {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request = RpcRequest::parse(&line)
+            .unwrap_or(Err(RpcError::new(
+                rpc::PARSE_ERROR,
+                "Invalid JSON-RPC message",
+            )))
+            .map_err(|error| (None, error))
+            .and_then(|request| {
+                let id = request.id.clone();
+                ControlEvent::try_from(request)
+                    .map(|event| (id.clone(), event))
+                    .map_err(|error| (id, error))
+            });
+
+        match request {
+            Ok((id, event)) => {
+                let control_responder = ControlResponder {
+                    session,
+                    id,
+                    sender: responder.clone(),
+                };
+
+                sender
+                    .send(InstanceEvent::Control(event, control_responder))
+                    .ok();
+            }
+            Err((id, error)) => {
+                warn!(target: "instance", "Control session {session}: {}", error.message);
+                responder.send(rpc::create_response(id, Err(error))).ok();
+            }
+        }
+    }
+
+    if let Ok(mut sessions) = sessions.lock() {
+        sessions.remove(&session);
+    }
+
+    drop(responder);
+    writer_thread.join().ok();
+
+    debug!(target: "instance", "Control session {session} closed");
+}
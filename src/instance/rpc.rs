@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+
+use crate::player::MpvProperty;
+
+const JSONRPC_VERSION: &str = "2.0";
+
+// Error codes defined by the JSON-RPC 2.0 specification
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const SERVER_ERROR: i64 = -32000;
+
+/// Names of every method of the control API
+pub mod methods {
+    pub const STATUS: &str = "status";
+    pub const MPV_COMMAND: &str = "mpv-command";
+    pub const MPV_GET: &str = "mpv-get";
+    pub const MPV_SET: &str = "mpv-set";
+    pub const PAUSE: &str = "pause";
+    pub const SEEK: &str = "seek";
+    pub const FULLSCREEN: &str = "fullscreen";
+    pub const SHOW: &str = "show";
+    pub const HIDE: &str = "hide";
+    pub const OPEN: &str = "open";
+    pub const SUBSCRIBE: &str = "subscribe";
+    pub const UNSUBSCRIBE: &str = "unsubscribe";
+    pub const QUIT: &str = "quit";
+}
+
+/// Name of the notification sent to subscribers when a property changes
+pub const PROPERTY_CHANGE_NOTIFICATION: &str = "property-change";
+
+#[derive(Debug)]
+pub enum ControlEvent {
+    Status,
+    MpvCommand((String, Vec<String>)),
+    MpvGet(String),
+    MpvSet(MpvProperty),
+    Pause(Option<bool>),
+    Seek { seconds: f64, absolute: bool },
+    Fullscreen(Option<bool>),
+    Show,
+    Hide,
+    Open(String),
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    Quit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new<T: Into<String>>(code: i64, message: T) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn server<T: Into<String>>(message: T) -> Self {
+        Self::new(SERVER_ERROR, message)
+    }
+}
+
+pub type RpcResult = Result<Value, RpcError>;
+
+#[derive(Deserialize, Debug)]
+pub struct RpcRequest {
+    jsonrpc: String,
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+}
+
+#[derive(Deserialize, Debug)]
+struct SeekParams {
+    seconds: f64,
+    #[serde(default)]
+    absolute: bool,
+}
+
+fn params<T: DeserializeOwned>(method: &str, params: Option<Value>) -> Result<T, RpcError> {
+    serde_json::from_value(params.unwrap_or(Value::Null))
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params for {method}: {e}")))
+}
+
+/// Accepts either a single name or a list of names
+fn names(method: &str, value: Option<Value>) -> Result<Vec<String>, RpcError> {
+    match value {
+        Some(Value::String(name)) => Ok(vec![name]),
+        value => params::<Vec<String>>(method, value),
+    }
+}
+
+impl RpcRequest {
+    /// Parses a line received on the socket, returns `None` if it is not a JSON-RPC message
+    pub fn parse(line: &str) -> Option<Result<Self, RpcError>> {
+        let value = serde_json::from_str::<Value>(line.trim()).ok()?;
+        value.get("jsonrpc")?;
+
+        Some(
+            serde_json::from_value::<RpcRequest>(value)
+                .map_err(|e| RpcError::new(INVALID_REQUEST, e.to_string()))
+                .and_then(|request| match request.jsonrpc == JSONRPC_VERSION {
+                    true => Ok(request),
+                    false => Err(RpcError::new(
+                        INVALID_REQUEST,
+                        format!("Unsupported jsonrpc version {}", request.jsonrpc),
+                    )),
+                }),
+        )
+    }
+}
+
+impl TryFrom<RpcRequest> for ControlEvent {
+    type Error = RpcError;
+
+    fn try_from(value: RpcRequest) -> Result<Self, Self::Error> {
+        let method = value.method.as_str();
+
+        match method {
+            methods::STATUS => Ok(ControlEvent::Status),
+            methods::MPV_COMMAND => {
+                let mut args = params::<Vec<String>>(method, value.params)?;
+                match args.is_empty() {
+                    true => Err(RpcError::new(INVALID_PARAMS, "Missing command name")),
+                    false => {
+                        let name = args.remove(0);
+                        Ok(ControlEvent::MpvCommand((name, args)))
+                    }
+                }
+            }
+            methods::MPV_GET => {
+                let name = params::<String>(method, value.params)?;
+                Ok(ControlEvent::MpvGet(name))
+            }
+            methods::MPV_SET => {
+                let (name, data) = params::<(String, Value)>(method, value.params)?;
+                Ok(ControlEvent::MpvSet(MpvProperty(name, Some(data))))
+            }
+            methods::PAUSE => {
+                let state = params::<Option<bool>>(method, value.params)?;
+                Ok(ControlEvent::Pause(state))
+            }
+            methods::SEEK => {
+                let seek = match value.params {
+                    Some(Value::Number(seconds)) => SeekParams {
+                        seconds: seconds.as_f64().unwrap_or_default(),
+                        absolute: false,
+                    },
+                    params => self::params::<SeekParams>(method, params)?,
+                };
+
+                Ok(ControlEvent::Seek {
+                    seconds: seek.seconds,
+                    absolute: seek.absolute,
+                })
+            }
+            methods::FULLSCREEN => {
+                let state = params::<Option<bool>>(method, value.params)?;
+                Ok(ControlEvent::Fullscreen(state))
+            }
+            methods::SHOW => Ok(ControlEvent::Show),
+            methods::HIDE => Ok(ControlEvent::Hide),
+            methods::OPEN => {
+                let deeplink = params::<String>(method, value.params)?;
+                Ok(ControlEvent::Open(deeplink))
+            }
+            methods::SUBSCRIBE => names(method, value.params).map(ControlEvent::Subscribe),
+            methods::UNSUBSCRIBE => names(method, value.params).map(ControlEvent::Unsubscribe),
+            methods::QUIT => Ok(ControlEvent::Quit),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{method}'"),
+            )),
+        }
+    }
+}
+
+pub fn create_request(id: u64, method: &str, params: Value) -> String {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "id": id,
+        "method": method,
+        "params": params,
+    })
+    .to_string()
+}
+
+pub fn create_response(id: Option<Value>, result: RpcResult) -> String {
+    let id = id.unwrap_or(Value::Null);
+
+    match result {
+        Ok(result) => json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "id": id,
+            "result": result,
+        }),
+        Err(error) => json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "id": id,
+            "error": error,
+        }),
+    }
+    .to_string()
+}
+
+pub fn create_notification(method: &str, params: Value) -> String {
+    json!({
+        "jsonrpc": JSONRPC_VERSION,
+        "method": method,
+        "params": params,
+    })
+    .to_string()
+}
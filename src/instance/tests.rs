@@ -0,0 +1,145 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+use super::{
+    Instance, InstanceEvent,
+    rpc::{self, ControlEvent},
+};
+use crate::{config::InstanceConfig, player::MpvProperty};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn server(runtime_dir: &TempDir) -> Instance {
+    let instance = Instance::new(InstanceConfig::new(runtime_dir.path()));
+    assert!(!instance.running());
+
+    instance.start();
+    instance
+}
+
+fn client(runtime_dir: &TempDir) -> Instance {
+    let instance = Instance::new(InstanceConfig::new(runtime_dir.path()));
+    assert!(instance.running());
+
+    instance
+}
+
+/// Polls the events of the instance until one is received
+fn next_event(instance: &Instance) -> InstanceEvent {
+    let start = Instant::now();
+
+    while start.elapsed() < TIMEOUT {
+        if let Ok(event) = instance.receiver.try_recv() {
+            return event;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("No instance event received");
+}
+
+#[test]
+fn deeplink() {
+    let runtime_dir = TempDir::new().unwrap();
+    let server = server(&runtime_dir);
+
+    client(&runtime_dir).send("stremio:///detail/movie/tt0000001".to_owned());
+
+    match next_event(&server) {
+        InstanceEvent::Open(deeplink) => assert_eq!(deeplink, "stremio:///detail/movie/tt0000001"),
+        InstanceEvent::Control(event, _) => panic!("Unexpected control event {event:?}"),
+    }
+
+    server.stop();
+    assert!(!runtime_dir.path().join("stremio.sock").exists());
+}
+
+#[test]
+fn control_request() {
+    let runtime_dir = TempDir::new().unwrap();
+    let server = server(&runtime_dir);
+    let client = client(&runtime_dir);
+
+    let request = thread::spawn(move || {
+        let mut messages = vec![];
+        let result = client.control(rpc::methods::STATUS, Value::Null, |message| {
+            messages.push(message.clone())
+        });
+
+        (result.is_ok(), messages)
+    });
+
+    match next_event(&server) {
+        InstanceEvent::Control(ControlEvent::Status, responder) => {
+            responder.respond(Ok(json!({ "paused": false })))
+        }
+        _ => panic!("Expected a status request"),
+    }
+
+    let (ok, messages) = request.join().unwrap();
+    assert!(ok);
+    assert_eq!(messages[0]["id"], 1);
+    assert_eq!(messages[0]["result"], json!({ "paused": false }));
+}
+
+#[test]
+fn invalid_requests() {
+    let runtime_dir = TempDir::new().unwrap();
+    let _server = server(&runtime_dir);
+
+    let mut stream = UnixStream::connect(runtime_dir.path().join("stremio.sock")).unwrap();
+    writeln!(stream, "{}", rpc::create_request(7, "unknown", Value::Null)).unwrap();
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line).unwrap();
+
+    let response = serde_json::from_str::<Value>(&line).unwrap();
+    assert_eq!(response["id"], 7);
+    assert_eq!(response["error"]["code"], rpc::METHOD_NOT_FOUND);
+}
+
+#[test]
+fn subscriptions() {
+    let runtime_dir = TempDir::new().unwrap();
+    let server = server(&runtime_dir);
+
+    let stream = UnixStream::connect(runtime_dir.path().join("stremio.sock")).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut read = || {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        serde_json::from_str::<Value>(&line).unwrap()
+    };
+
+    let request = rpc::create_request(1, rpc::methods::SUBSCRIBE, json!("pause"));
+    writeln!(writer, "{request}").unwrap();
+
+    match next_event(&server) {
+        InstanceEvent::Control(ControlEvent::Subscribe(names), responder) => {
+            server.subscribe(responder.session, names);
+            responder.respond(Ok(Value::Null));
+        }
+        _ => panic!("Expected a subscribe request"),
+    }
+
+    assert_eq!(read()["id"], 1);
+
+    server.notify_property(&MpvProperty("volume".to_owned(), Some(json!(50))));
+    server.notify_property(&MpvProperty("pause".to_owned(), Some(json!(true))));
+
+    let notification = read();
+    assert_eq!(notification["method"], rpc::PROPERTY_CHANGE_NOTIFICATION);
+    assert_eq!(
+        notification["params"],
+        json!({ "name": "pause", "data": true })
+    );
+}
@@ -1,179 +1,550 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::{Value, json};
+use tracing::warn;
 
-use crate::player::MpvProperty;
+use crate::{
+    config::{self, AudioNormalization},
+    connectivity::ConnectivityState,
+    player::MpvProperty,
+    server::ServerStatus,
+};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const TRANSPORT_NAME: &str = "transport";
 
+/// Version of the shell <-> web UI protocol, bumped on every breaking change
+pub const PROTOCOL_VERSION: u64 = 2;
+/// Oldest web UI protocol version the shell still understands
+pub const MIN_PROTOCOL_VERSION: u64 = 1;
+
+// Message types, following the QWebChannel transport numbering
+const SIGNAL_TYPE: u8 = 1;
+const INIT_TYPE: u8 = 3;
+const INVOKE_TYPE: u8 = 6;
+const EVENT_TYPE: u8 = 7;
+const RESPONSE_TYPE: u8 = 10;
+
+/// Names of every method the web UI can call, shared with the UI through the init handshake
+pub mod methods {
+    pub const APP_READY: &str = "app-ready";
+    pub const QUIT: &str = "quit";
+    pub const WIN_SET_VISIBILITY: &str = "win-set-visibility";
+    pub const WIN_SET_PIP: &str = "win-set-pip";
+    pub const WIN_GET_MONITORS: &str = "win-get-monitors";
+    pub const WIN_GET_FRAME_STATS: &str = "win-get-frame-stats";
+    pub const OPEN_EXTERNAL: &str = "open-external";
+    pub const MPV_COMMAND: &str = "mpv-command";
+    pub const MPV_OBSERVE_PROP: &str = "mpv-observe-prop";
+    pub const MPV_SET_PROP: &str = "mpv-set-prop";
+    pub const MPV_GET_PROP: &str = "mpv-get-prop";
+    pub const MPV_GET_PROP_TYPE: &str = "mpv-get-prop-type";
+    pub const SEEK_HOVER: &str = "seek-hover";
+    pub const SEEK_LEAVE: &str = "seek-leave";
+    pub const RESUME_GET: &str = "resume-get";
+    pub const SHADERS_GET: &str = "shaders-get";
+    pub const SHADERS_SET: &str = "shaders-set";
+    pub const SHADERS_CONTENT: &str = "shaders-content";
+    pub const SUBTITLES_TRACKS: &str = "subtitles-tracks";
+    pub const SUBTITLES_LOAD: &str = "subtitles-load";
+    pub const AUDIO_DEVICE_LIST: &str = "audio-device-list";
+    pub const AUDIO_DEVICE: &str = "audio-device";
+    pub const AUDIO_SPDIF: &str = "audio-spdif";
+    pub const AUDIO_CHANNELS: &str = "audio-channels";
+    pub const AUDIO_NORMALIZATION: &str = "audio-normalization";
+    pub const SERVER_STATUS: &str = "server-status";
+    pub const CONNECTIVITY_STATUS: &str = "connectivity-status";
+    pub const CONNECTIVITY_RETRY: &str = "connectivity-retry";
+    pub const CONFIG_GET: &str = "config-get";
+    pub const CONFIG_SET: &str = "config-set";
+    pub const CONFIG_SCHEMA: &str = "config-schema";
+    pub const DISCORD_PRESENCE: &str = "discord-presence";
+    pub const DISCORD_TOGGLE: &str = "discord-toggle";
+    pub const PROFILES_GET: &str = "profiles-get";
+    pub const PROFILES_SWITCH: &str = "profiles-switch";
+
+    pub const ALL: &[&str] = &[
+        APP_READY,
+        QUIT,
+        WIN_SET_VISIBILITY,
+        WIN_SET_PIP,
+        WIN_GET_MONITORS,
+        WIN_GET_FRAME_STATS,
+        OPEN_EXTERNAL,
+        MPV_COMMAND,
+        MPV_OBSERVE_PROP,
+        MPV_SET_PROP,
+        MPV_GET_PROP,
+        MPV_GET_PROP_TYPE,
+        SEEK_HOVER,
+        SEEK_LEAVE,
+        RESUME_GET,
+        SHADERS_GET,
+        SHADERS_SET,
+        SHADERS_CONTENT,
+        SUBTITLES_TRACKS,
+        SUBTITLES_LOAD,
+        AUDIO_DEVICE_LIST,
+        AUDIO_DEVICE,
+        AUDIO_SPDIF,
+        AUDIO_CHANNELS,
+        AUDIO_NORMALIZATION,
+        SERVER_STATUS,
+        CONNECTIVITY_STATUS,
+        CONNECTIVITY_RETRY,
+        CONFIG_GET,
+        CONFIG_SET,
+        CONFIG_SCHEMA,
+        DISCORD_PRESENCE,
+        DISCORD_TOGGLE,
+        PROFILES_GET,
+        PROFILES_SWITCH,
+    ];
+}
+
+/// Names of every signal the shell can emit to the web UI
+pub mod signals {
+    pub const WIN_VISIBILITY_CHANGED: &str = "win-visibility-changed";
+    pub const WIN_STATE_CHANGED: &str = "win-state-changed";
+    pub const WIN_PIP_CHANGED: &str = "win-pip-changed";
+    pub const OPEN_MEDIA: &str = "open-media";
+    pub const MPV_PROP_CHANGE: &str = "mpv-prop-change";
+    pub const MPV_EVENT_ENDED: &str = "mpv-event-ended";
+    pub const KEYMAP_ACTION: &str = "keymap-action";
+    pub const SHADERS_CHANGED: &str = "shaders-changed";
+    pub const SUBTITLES_LOADED: &str = "subtitles-loaded";
+    pub const SERVER_STATUS_CHANGED: &str = "server-status-changed";
+    pub const CONNECTIVITY_CHANGED: &str = "connectivity-changed";
+    pub const CONFIG_CHANGED: &str = "config-changed";
+
+    pub const ALL: &[&str] = &[
+        WIN_VISIBILITY_CHANGED,
+        WIN_STATE_CHANGED,
+        WIN_PIP_CHANGED,
+        OPEN_MEDIA,
+        MPV_PROP_CHANGE,
+        MPV_EVENT_ENDED,
+        KEYMAP_ACTION,
+        SHADERS_CHANGED,
+        SUBTITLES_LOADED,
+        SERVER_STATUS_CHANGED,
+        CONNECTIVITY_CHANGED,
+        CONFIG_CHANGED,
+    ];
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpcErrorCode {
+    ParseError,
+    UnknownType,
+    UnknownMethod,
+    InvalidArguments,
+    Failed,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IpcError {
+    pub code: IpcErrorCode,
+    pub message: String,
+}
+
+impl IpcError {
+    pub fn new<T: Into<String>>(code: IpcErrorCode, message: T) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn failed<T: Into<String>>(message: T) -> Self {
+        Self::new(IpcErrorCode::Failed, message)
+    }
+
+    fn invalid_arguments(method: &str, error: impl fmt::Display) -> Self {
+        Self::new(
+            IpcErrorCode::InvalidArguments,
+            format!("Invalid arguments for {method}: {error}"),
+        )
+    }
+}
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+/// Result of handling a request, sent back to the web UI as a response
+pub type IpcResult = Result<Value, IpcError>;
+
 #[derive(Deserialize, Debug)]
 pub enum IpcEventMpv {
     Observe(String),
     Command((String, Vec<String>)),
     Set(MpvProperty),
+    Get(String),
+    GetType(String),
     Change(MpvProperty),
     Ended(Option<String>),
 }
 
 #[derive(Deserialize, Debug)]
 pub enum IpcEvent {
-    Init(u64),
+    Init { id: u64, version: Option<u64> },
     Quit,
     Fullscreen(bool),
     Minimized(bool),
     Visibility(bool),
+    PictureInPicture(Option<bool>),
+    Monitors,
+    FrameStats,
+    PictureInPictureChanged(bool),
     OpenMedia(String),
     OpenExternal(String),
     Mpv(IpcEventMpv),
     DiscordPresence(Vec<String>),
     DiscordToggle(bool),
-    SeekHover(String, String, i64),  // (seconds, x, y)
+    SeekHover(SeekHoverArgs),
     SeekLeave,
+    ResumeGet(Option<String>),
+    KeymapAction(String),
+    ShadersGet,
+    ShadersSet(Option<String>),
+    ShadersContent(Option<String>),
+    ShadersChanged(Option<String>),
+    SubtitlesTracks,
+    SubtitlesLoad(SubtitlesLoadArgs),
+    SubtitlesLoaded(String, Option<String>),
+    AudioDeviceList,
+    AudioDevice(Option<String>),
+    AudioSpdif(Option<Vec<String>>),
+    AudioChannels(Option<String>),
+    AudioNormalization(AudioNormalization),
+    ServerStatus,
+    ServerStatusChanged(ServerStatus),
+    ConnectivityStatus,
+    ConnectivityRetry,
+    ConnectivityChanged(ConnectivityState),
+    ConfigGet(Option<String>),
+    ConfigSet(String, Option<Value>),
+    ConfigSchema,
+    ConfigChanged(Value),
+    ProfilesGet,
+    ProfilesSwitch(String),
 }
 
 #[derive(Deserialize, Debug)]
-pub struct IpcMessageRequestWinSetVisilibty {
+pub struct InitArgs {
+    #[serde(rename = "protocolVersion")]
+    protocol_version: Option<u64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WinSetVisibilityArgs {
     fullscreen: bool,
 }
 
+/// Arguments of `mpv-command`: the command name followed by its arguments
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "Vec<String>")]
+pub struct MpvCommandArgs(String, Vec<String>);
+
+impl TryFrom<Vec<String>> for MpvCommandArgs {
+    type Error = &'static str;
+
+    fn try_from(mut value: Vec<String>) -> Result<Self, Self::Error> {
+        match value.is_empty() {
+            true => Err("Missing command name"),
+            false => {
+                let name = value.remove(0);
+                Ok(Self(name, value))
+            }
+        }
+    }
+}
+
+/// Arguments of `mpv-set-prop`: the property name and its optional value
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "Vec<Value>")]
+pub struct MpvSetPropArgs(String, Option<Value>);
+
+impl TryFrom<Vec<Value>> for MpvSetPropArgs {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<Value>) -> Result<Self, Self::Error> {
+        let name = value
+            .first()
+            .and_then(Value::as_str)
+            .ok_or("Missing property name")?;
+
+        Ok(Self(name.to_owned(), value.get(1).cloned()))
+    }
+}
+
+/// Arguments of `config-set`: the dotted key and its optional value
+#[derive(Deserialize, Debug)]
+#[serde(try_from = "Vec<Value>")]
+pub struct ConfigSetArgs(String, Option<Value>);
+
+impl TryFrom<Vec<Value>> for ConfigSetArgs {
+    type Error = &'static str;
+
+    fn try_from(value: Vec<Value>) -> Result<Self, Self::Error> {
+        let key = value
+            .first()
+            .and_then(Value::as_str)
+            .ok_or("Missing config key")?;
+
+        Ok(Self(key.to_owned(), value.get(1).cloned()))
+    }
+}
+
+/// Arguments of `subtitles-load`: a local path or an HTTP URL, with the track language and title
+#[derive(Deserialize, Debug)]
+pub struct SubtitlesLoadArgs {
+    pub source: String,
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Arguments of `seek-hover`: (seconds, x, y)
+#[derive(Deserialize, Debug)]
+pub struct SeekHoverArgs(
+    pub String,
+    pub String,
+    #[serde(deserialize_with = "de_i64")] pub i64,
+);
+
+fn de_i64<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    use serde::de::Error;
+
+    match Value::deserialize(deserializer)? {
+        Value::Number(number) => number
+            .as_f64()
+            .map(|value| value as i64)
+            .ok_or(D::Error::custom("Invalid number")),
+        Value::String(value) => value.parse::<i64>().map_err(D::Error::custom),
+        _ => Err(D::Error::custom("Expected a number or a numeric string")),
+    }
+}
+
+/// Deserializes the arguments of `method` into their typed representation
+fn args<T: DeserializeOwned>(method: &str, data: Option<Value>) -> Result<T, IpcError> {
+    serde_json::from_value(data.unwrap_or(Value::Null))
+        .map_err(|e| IpcError::invalid_arguments(method, e))
+}
+
 #[derive(Deserialize, Debug)]
 pub struct IpcMessageRequest {
-    id: u64,
+    id: Option<u64>,
     r#type: u8,
     #[serde(skip_serializing_if = "Option::is_none")]
-    args: Option<serde_json::Value>,
+    args: Option<Value>,
+}
+
+impl IpcMessageRequest {
+    /// Splits the request args into the method name and its optional data
+    fn method(&self) -> Result<(String, Option<Value>), IpcError> {
+        let args = match &self.args {
+            Some(Value::Array(args)) => args,
+            Some(_) => {
+                return Err(IpcError::new(
+                    IpcErrorCode::ParseError,
+                    "Expected args to be an array",
+                ));
+            }
+            None => return Err(IpcError::new(IpcErrorCode::ParseError, "Missing args")),
+        };
+
+        let name = args
+            .first()
+            .and_then(Value::as_str)
+            .ok_or(IpcError::new(IpcErrorCode::ParseError, "Invalid name"))?;
+
+        Ok((name.to_owned(), args.get(1).cloned()))
+    }
 }
 
 impl TryFrom<IpcMessageRequest> for IpcEvent {
-    type Error = String;
+    type Error = IpcError;
 
     fn try_from(value: IpcMessageRequest) -> Result<Self, Self::Error> {
         match value.r#type {
-            3 => Ok(IpcEvent::Init(value.id)),
-            6 => match value.args {
-                Some(args) => {
-                    let args: Vec<Value> = serde_json::from_value(args).expect("Invalid arguments");
-                    let name = args.first().and_then(Value::as_str).ok_or("Invalid name")?;
-                    let data = args.get(1).cloned();
-
-                    match data {
-                        Some(data) => match name {
-                            "app-ready" => Ok(IpcEvent::Init(value.id)),  // Handle app-ready event
-                            "win-set-visibility" => {
-                                let data: IpcMessageRequestWinSetVisilibty =
-                                    serde_json::from_value(data)
-                                        .expect("Invalid win-set-visibility object");
-
-                                Ok(IpcEvent::Fullscreen(data.fullscreen))
-                            }
-                            "open-external" => {
-                                let data: String = serde_json::from_value(data)
-                                    .expect("Invalid open-external argument");
-
-                                Ok(IpcEvent::OpenExternal(data))
-                            }
-                            "mpv-command" => {
-                                let data: Vec<String> = serde_json::from_value(data)
-                                    .expect("Invalid mpv-command arguments");
-                                let name = data[0].clone();
-
-                                let mut args = vec![];
-                                for arg in data.iter().skip(1) {
-                                    args.push(arg.clone());
-                                }
-
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Command((name, args))))
-                            }
-                            "mpv-observe-prop" => {
-                                let name = data.as_str().expect("Invalid mpv-observe-prop name");
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Observe(name.to_owned())))
-                            }
-                            "mpv-set-prop" => {
-                                let key_value: Vec<Value> = serde_json::from_value(data)
-                                    .expect("Invalid mpv-set-prop arguments");
-
-                                let name = key_value[0]
-                                    .as_str()
-                                    .expect("Invalid mpv-set-prop name")
-                                    .to_owned();
-
-                                let value = key_value.get(1).cloned();
-
-                                Ok(IpcEvent::Mpv(IpcEventMpv::Set(MpvProperty(name, value))))
-                            }
-                            "seek-hover" => {
-                                let hover_args: Vec<Value> = serde_json::from_value(data)
-                                    .expect("Invalid seek-hover arguments");
-
-                                let seconds = hover_args.get(0)
-                                    .and_then(Value::as_str)
-                                    .ok_or("Invalid seek-hover seconds")?
-                                    .to_string();
-
-                                let x = hover_args.get(1)
-                                    .and_then(Value::as_str)
-                                    .ok_or("Invalid seek-hover x")?
-                                    .to_string();
-
-                                let y = hover_args.get(2)
-                                    .and_then(Value::as_str)
-                                    .ok_or("Invalid seek-hover y")?
-                                    .parse::<i64>()
-                                    .map_err(|_| "Failed to parse y coordinate")?;
-
-                                Ok(IpcEvent::SeekHover(seconds, x, y))
-                            }
-                            "seek-leave" => {
-                                // seek-leave is sent with empty object {}, just ignore the data
-                                Ok(IpcEvent::SeekLeave)
-                            }
-                            _ => Err(format!("Unknown method (type=6, with_data): '{}' | full_args: {:?}", name, args)),
-                        },
-                        None => match name {
-                            "quit" => Ok(IpcEvent::Quit),
-                            "seek-leave" => Ok(IpcEvent::SeekLeave),
-                            _ => Err(format!("Unknown method (type=6, no_data): '{}' | full_args: {:?}", name, args)),
-                        },
+            INIT_TYPE => {
+                let init = match value.args {
+                    Some(Value::Object(_)) => args::<InitArgs>("init", value.args)?,
+                    _ => InitArgs {
+                        protocol_version: None,
+                    },
+                };
+
+                Ok(IpcEvent::Init {
+                    id: value.id.unwrap_or_default(),
+                    version: init.protocol_version,
+                })
+            }
+            INVOKE_TYPE => {
+                let (name, data) = value.method()?;
+
+                match name.as_str() {
+                    methods::APP_READY => Ok(IpcEvent::Init {
+                        id: value.id.unwrap_or_default(),
+                        version: None,
+                    }),
+                    methods::QUIT => Ok(IpcEvent::Quit),
+                    methods::WIN_SET_VISIBILITY => {
+                        let data = args::<WinSetVisibilityArgs>(&name, data)?;
+                        Ok(IpcEvent::Fullscreen(data.fullscreen))
                     }
-                }
-                None => Err("Missing args".to_string()),
-            },
-            7 => match value.args {
-                Some(args) => {
-                    let args: Vec<Value> = serde_json::from_value(args).expect("Invalid arguments");
-                    let name = args.first().and_then(Value::as_str).ok_or("Invalid name")?;
-
-                    match name {
-                        "discord-presence" => {
-                            let presence_args: Vec<String> = args
-                                .iter()
-                                .skip(1)
-                                .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                                .collect();
-                            Ok(IpcEvent::DiscordPresence(presence_args))
-                        }
-                        "discord-toggle" => {
-                            let enabled = args.get(1).and_then(Value::as_bool).unwrap_or(false);
-                            Ok(IpcEvent::DiscordToggle(enabled))
-                        }
-                        _ => Err(format!("Unknown method (type=7): '{}' | full_args: {:?}", name, args)),
+                    // Without a state the picture-in-picture window is toggled
+                    methods::WIN_SET_PIP => {
+                        let state = args::<Option<bool>>(&name, data)?;
+                        Ok(IpcEvent::PictureInPicture(state))
+                    }
+                    methods::WIN_GET_MONITORS => Ok(IpcEvent::Monitors),
+                    methods::WIN_GET_FRAME_STATS => Ok(IpcEvent::FrameStats),
+                    methods::OPEN_EXTERNAL => {
+                        let url = args::<String>(&name, data)?;
+                        Ok(IpcEvent::OpenExternal(url))
+                    }
+                    methods::MPV_COMMAND => {
+                        let MpvCommandArgs(command, args) = args(&name, data)?;
+                        Ok(IpcEvent::Mpv(IpcEventMpv::Command((command, args))))
+                    }
+                    methods::MPV_OBSERVE_PROP => {
+                        let property = args::<String>(&name, data)?;
+                        Ok(IpcEvent::Mpv(IpcEventMpv::Observe(property)))
+                    }
+                    methods::MPV_SET_PROP => {
+                        let MpvSetPropArgs(property, value) = args(&name, data)?;
+                        Ok(IpcEvent::Mpv(IpcEventMpv::Set(MpvProperty(
+                            property, value,
+                        ))))
+                    }
+                    methods::MPV_GET_PROP => {
+                        let property = args::<String>(&name, data)?;
+                        Ok(IpcEvent::Mpv(IpcEventMpv::Get(property)))
+                    }
+                    methods::MPV_GET_PROP_TYPE => {
+                        let property = args::<String>(&name, data)?;
+                        Ok(IpcEvent::Mpv(IpcEventMpv::GetType(property)))
+                    }
+                    methods::SEEK_HOVER => {
+                        let data = args::<SeekHoverArgs>(&name, data)?;
+                        Ok(IpcEvent::SeekHover(data))
+                    }
+                    // seek-leave is sent with an empty object {}, the data is ignored
+                    methods::SEEK_LEAVE => Ok(IpcEvent::SeekLeave),
+                    // Without a path the position of the stream currently playing is returned
+                    methods::RESUME_GET => {
+                        let path = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::ResumeGet(path))
+                    }
+                    methods::SHADERS_GET => Ok(IpcEvent::ShadersGet),
+                    // A null preset clears the shaders
+                    methods::SHADERS_SET => {
+                        let preset = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::ShadersSet(preset))
+                    }
+                    methods::SHADERS_CONTENT => {
+                        let content = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::ShadersContent(content))
                     }
+                    methods::SUBTITLES_TRACKS => Ok(IpcEvent::SubtitlesTracks),
+                    methods::SUBTITLES_LOAD => {
+                        let data = args::<SubtitlesLoadArgs>(&name, data)?;
+                        Ok(IpcEvent::SubtitlesLoad(data))
+                    }
+                    methods::AUDIO_DEVICE_LIST => Ok(IpcEvent::AudioDeviceList),
+                    // A null device lets mpv pick one
+                    methods::AUDIO_DEVICE => {
+                        let device = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::AudioDevice(device))
+                    }
+                    // The settings below apply to the current device, null restores mpv.conf
+                    methods::AUDIO_SPDIF => {
+                        let codecs = args::<Option<Vec<String>>>(&name, data)?;
+                        Ok(IpcEvent::AudioSpdif(codecs))
+                    }
+                    methods::AUDIO_CHANNELS => {
+                        let channels = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::AudioChannels(channels))
+                    }
+                    methods::AUDIO_NORMALIZATION => {
+                        let normalization = args::<AudioNormalization>(&name, data)?;
+                        Ok(IpcEvent::AudioNormalization(normalization))
+                    }
+                    methods::SERVER_STATUS => Ok(IpcEvent::ServerStatus),
+                    methods::CONNECTIVITY_STATUS => Ok(IpcEvent::ConnectivityStatus),
+                    // Probes the web UI that failed to load right away
+                    methods::CONNECTIVITY_RETRY => Ok(IpcEvent::ConnectivityRetry),
+                    // Without a key the whole config is returned
+                    methods::CONFIG_GET => {
+                        let key = args::<Option<String>>(&name, data)?;
+                        Ok(IpcEvent::ConfigGet(key))
+                    }
+                    // Without a value the key is reset to its default
+                    methods::CONFIG_SET => {
+                        let ConfigSetArgs(key, value) = args(&name, data)?;
+                        Ok(IpcEvent::ConfigSet(key, value))
+                    }
+                    methods::CONFIG_SCHEMA => Ok(IpcEvent::ConfigSchema),
+                    methods::PROFILES_GET => Ok(IpcEvent::ProfilesGet),
+                    // Unknown profiles are created
+                    methods::PROFILES_SWITCH => {
+                        let profile = args::<String>(&name, data)?;
+                        let profile = config::parse_profile(&profile)
+                            .map_err(|e| IpcError::invalid_arguments(&name, e))?;
+                        Ok(IpcEvent::ProfilesSwitch(profile))
+                    }
+                    _ => Err(IpcError::new(
+                        IpcErrorCode::UnknownMethod,
+                        format!("Unknown method '{name}'"),
+                    )),
                 }
-                None => Err("Missing args".to_string()),
-            },
-            _ => Err(format!("Unknown IPC message type: {}", value.r#type)),
-        }
-    }
-}
+            }
+            EVENT_TYPE => {
+                let args = match value.args {
+                    Some(Value::Array(args)) => args,
+                    _ => return Err(IpcError::new(IpcErrorCode::ParseError, "Missing args")),
+                };
 
-impl TryFrom<String> for IpcEvent {
-    type Error = String;
+                let name = args
+                    .first()
+                    .and_then(Value::as_str)
+                    .ok_or(IpcError::new(IpcErrorCode::ParseError, "Invalid name"))?;
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        serde_json::from_str::<IpcMessageRequest>(&value)
-            .map_err(|e| format!("Failed to parse IPC JSON: {e}"))?
-            .try_into()
-            .map_err(|e| format!("Failed to convert IpcMessageRequest to IpcEvent: {e}"))
+                match name {
+                    methods::DISCORD_PRESENCE => {
+                        let presence_args: Vec<String> = args
+                            .iter()
+                            .skip(1)
+                            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                            .collect();
+                        Ok(IpcEvent::DiscordPresence(presence_args))
+                    }
+                    methods::DISCORD_TOGGLE => {
+                        let enabled = args
+                            .get(1)
+                            .and_then(Value::as_bool)
+                            .ok_or(IpcError::invalid_arguments(name, "expected a boolean"))?;
+                        Ok(IpcEvent::DiscordToggle(enabled))
+                    }
+                    _ => Err(IpcError::new(
+                        IpcErrorCode::UnknownMethod,
+                        format!("Unknown method '{name}'"),
+                    )),
+                }
+            }
+            r#type => Err(IpcError::new(
+                IpcErrorCode::UnknownType,
+                format!("Unknown IPC message type: {type}"),
+            )),
+        }
     }
 }
 
@@ -183,9 +554,60 @@ pub struct IpcMessageResponse {
     r#type: u8,
     object: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<serde_json::Value>,
+    data: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    args: Option<serde_json::Value>,
+    args: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<IpcError>,
+}
+
+impl IpcMessageResponse {
+    fn signal(name: &str, data: Value) -> Self {
+        Self {
+            id: 1,
+            r#type: SIGNAL_TYPE,
+            object: TRANSPORT_NAME.to_owned(),
+            data: None,
+            args: Some(json!([name, data])),
+            error: None,
+        }
+    }
+
+    fn init(id: u64) -> Self {
+        Self {
+            id,
+            r#type: INIT_TYPE,
+            object: TRANSPORT_NAME.to_owned(),
+            args: None,
+            error: None,
+            data: Some(json!({
+                "transport": {
+                    "properties": [[], ["", "shellVersion", "", VERSION]],
+                    "signals": signals::ALL,
+                    "methods": [["onEvent"]],
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "minProtocolVersion": MIN_PROTOCOL_VERSION,
+                    "shellMethods": methods::ALL,
+                }
+            })),
+        }
+    }
+
+    fn reply(id: u64, result: IpcResult) -> Self {
+        let (data, error) = match result {
+            Ok(data) => (Some(data), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            id,
+            r#type: RESPONSE_TYPE,
+            object: TRANSPORT_NAME.to_owned(),
+            data,
+            args: None,
+            error,
+        }
+    }
 }
 
 impl TryFrom<IpcEvent> for IpcMessageResponse {
@@ -193,101 +615,291 @@ impl TryFrom<IpcEvent> for IpcMessageResponse {
 
     fn try_from(value: IpcEvent) -> Result<Self, Self::Error> {
         match value {
-            IpcEvent::Init(id) => Ok(IpcMessageResponse {
-                id,
-                r#type: 3,
-                object: TRANSPORT_NAME.to_owned(),
-                args: None,
-                data: Some(json!({
-                    "transport": {
-                        "properties": [[], ["", "shellVersion", "", VERSION]],
-                        "signals": [],
-                        "methods": [["onEvent"]]
-                    }
-                })),
-            }),
-            IpcEvent::Fullscreen(state) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "win-visibility-changed",
-                    {
-                        "visible": true,
-                        "visibility": 1,
-                        "isFullscreen": state,
-                    }
-                ])),
-            }),
-            IpcEvent::Visibility(state) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "win-visibility-changed",
-                    {
-                        "visible": state,
-                        "visibility": state as u32,
-                        "isFullscreen": false,
-                    }
-                ])),
-            }),
-            IpcEvent::Minimized(state) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "win-state-changed",
-                    {
-                        "state": match state {
-                            true => 9,
-                            false => 8,
-                        },
-                    }
-                ])),
-            }),
-            IpcEvent::OpenMedia(deeplink) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["open-media", deeplink])),
-            }),
-            IpcEvent::Mpv(IpcEventMpv::Change(property)) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!(["mpv-prop-change", property])),
-            }),
-            IpcEvent::Mpv(IpcEventMpv::Ended(error)) => Ok(IpcMessageResponse {
-                id: 1,
-                r#type: 1,
-                object: TRANSPORT_NAME.to_owned(),
-                data: None,
-                args: Some(json!([
-                    "mpv-event-ended",
-                    {
-                        "error": error,
-                    }
-                ])),
-            }),
+            IpcEvent::Init { id, .. } => Ok(IpcMessageResponse::init(id)),
+            IpcEvent::Fullscreen(state) => Ok(IpcMessageResponse::signal(
+                signals::WIN_VISIBILITY_CHANGED,
+                json!({
+                    "visible": true,
+                    "visibility": 1,
+                    "isFullscreen": state,
+                }),
+            )),
+            IpcEvent::Visibility(state) => Ok(IpcMessageResponse::signal(
+                signals::WIN_VISIBILITY_CHANGED,
+                json!({
+                    "visible": state,
+                    "visibility": state as u32,
+                    "isFullscreen": false,
+                }),
+            )),
+            IpcEvent::Minimized(state) => Ok(IpcMessageResponse::signal(
+                signals::WIN_STATE_CHANGED,
+                json!({
+                    "state": match state {
+                        true => 9,
+                        false => 8,
+                    },
+                }),
+            )),
+            IpcEvent::PictureInPictureChanged(state) => Ok(IpcMessageResponse::signal(
+                signals::WIN_PIP_CHANGED,
+                json!({
+                    "active": state,
+                }),
+            )),
+            IpcEvent::OpenMedia(deeplink) => Ok(IpcMessageResponse::signal(
+                signals::OPEN_MEDIA,
+                json!(deeplink),
+            )),
+            IpcEvent::Mpv(IpcEventMpv::Change(property)) => Ok(IpcMessageResponse::signal(
+                signals::MPV_PROP_CHANGE,
+                json!(property),
+            )),
+            IpcEvent::Mpv(IpcEventMpv::Ended(error)) => Ok(IpcMessageResponse::signal(
+                signals::MPV_EVENT_ENDED,
+                json!({
+                    "error": error,
+                }),
+            )),
+            IpcEvent::KeymapAction(action) => Ok(IpcMessageResponse::signal(
+                signals::KEYMAP_ACTION,
+                json!(action),
+            )),
+            IpcEvent::ShadersChanged(preset) => Ok(IpcMessageResponse::signal(
+                signals::SHADERS_CHANGED,
+                json!({
+                    "preset": preset,
+                }),
+            )),
+            IpcEvent::SubtitlesLoaded(source, error) => Ok(IpcMessageResponse::signal(
+                signals::SUBTITLES_LOADED,
+                json!({
+                    "source": source,
+                    "error": error,
+                }),
+            )),
+            IpcEvent::ServerStatusChanged(status) => Ok(IpcMessageResponse::signal(
+                signals::SERVER_STATUS_CHANGED,
+                json!(status),
+            )),
+            IpcEvent::ConnectivityChanged(state) => Ok(IpcMessageResponse::signal(
+                signals::CONNECTIVITY_CHANGED,
+                json!(state),
+            )),
+            IpcEvent::ConfigChanged(changes) => {
+                Ok(IpcMessageResponse::signal(signals::CONFIG_CHANGED, changes))
+            }
             _ => Err("Failed to convert IpcEvent to IpcMessageResponse"),
         }
     }
 }
 
-pub fn parse_request<T: Fn(IpcEvent)>(data: String, handler: T) {
-    IpcEvent::try_from(data)
-        .map(handler)
-        .map_err(|e| eprintln!("❌ [IPC ERROR] {}", e))
-        .ok();
+/// Parses a message from the web UI and passes the typed event to `handler`.
+///
+/// Returns the message to post back: the init handshake, the result of an
+/// invoked method correlated by request id, or a structured error.
+pub fn parse_request<T: FnMut(IpcEvent) -> IpcResult>(
+    data: String,
+    mut handler: T,
+) -> Option<String> {
+    let request = match serde_json::from_str::<IpcMessageRequest>(&data) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = IpcError::new(
+                IpcErrorCode::ParseError,
+                format!("Failed to parse IPC JSON: {e}"),
+            );
+            warn!(target: "ipc", "{error}");
+
+            // Try to recover the id so the caller can still be rejected
+            let id = serde_json::from_str::<Value>(&data)
+                .ok()
+                .and_then(|value| value.get("id").and_then(Value::as_u64))?;
+
+            return serialize(IpcMessageResponse::reply(id, Err(error)));
+        }
+    };
+
+    let id = request.id;
+    let r#type = request.r#type;
+
+    let event = match IpcEvent::try_from(request) {
+        Ok(event) => event,
+        Err(error) => {
+            warn!(target: "ipc", "{error}");
+            return id.and_then(|id| serialize(IpcMessageResponse::reply(id, Err(error))));
+        }
+    };
+
+    if let IpcEvent::Init { id, version } = event {
+        if let Some(version) = version
+            && !(MIN_PROTOCOL_VERSION..=PROTOCOL_VERSION).contains(&version)
+        {
+            warn!(
+                target: "ipc",
+                "Web UI protocol version {version} is not supported (expected {MIN_PROTOCOL_VERSION}..={PROTOCOL_VERSION})"
+            );
+        }
+
+        handler(IpcEvent::Init { id, version })
+            .map_err(|e| warn!(target: "ipc", "{e}"))
+            .ok();
+
+        return serialize(IpcMessageResponse::init(id));
+    }
+
+    match (handler(event), id) {
+        (Ok(data), Some(id)) if r#type == INVOKE_TYPE => {
+            serialize(IpcMessageResponse::reply(id, Ok(data)))
+        }
+        (Ok(_), _) => None,
+        (Err(error), id) => {
+            warn!(target: "ipc", "{error}");
+            id.and_then(|id| serialize(IpcMessageResponse::reply(id, Err(error))))
+        }
+    }
 }
 
 pub fn create_response(event: IpcEvent) -> String {
     let message = IpcMessageResponse::try_from(event).ok();
     serde_json::to_string(&message).expect("Failed to convert IpcMessage to string")
 }
+
+fn serialize(message: IpcMessageResponse) -> Option<String> {
+    serde_json::to_string(&message)
+        .map_err(|e| warn!(target: "ipc", "Failed to serialize IPC response: {e}"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(request: Value, handler: impl FnMut(IpcEvent) -> IpcResult) -> Option<Value> {
+        parse_request(request.to_string(), handler)
+            .map(|message| serde_json::from_str(&message).unwrap())
+    }
+
+    #[test]
+    fn init_handshake() {
+        let mut version = None;
+        let response = reply(
+            json!({ "id": 0, "type": INIT_TYPE, "args": { "protocolVersion": 2 } }),
+            |event| {
+                if let IpcEvent::Init { version: v, .. } = event {
+                    version = v;
+                }
+                Ok(Value::Null)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(version, Some(2));
+        assert_eq!(response["type"], INIT_TYPE);
+        assert_eq!(
+            response["data"]["transport"]["protocolVersion"],
+            PROTOCOL_VERSION
+        );
+        assert_eq!(
+            response["data"]["transport"]["shellMethods"],
+            json!(methods::ALL)
+        );
+    }
+
+    #[test]
+    fn invoke_reply() {
+        let response = reply(
+            json!({ "id": 42, "type": INVOKE_TYPE, "args": ["mpv-command", ["loadfile", "a.mkv"]] }),
+            |event| match event {
+                IpcEvent::Mpv(IpcEventMpv::Command((command, args))) => {
+                    assert_eq!(command, "loadfile");
+                    assert_eq!(args, vec!["a.mkv"]);
+                    Ok(json!("done"))
+                }
+                event => panic!("Unexpected event {event:?}"),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(response["id"], 42);
+        assert_eq!(response["type"], RESPONSE_TYPE);
+        assert_eq!(response["data"], "done");
+        assert!(response.get("error").is_none());
+    }
+
+    #[test]
+    fn handler_error() {
+        let response = reply(
+            json!({ "id": 1, "type": INVOKE_TYPE, "args": ["server-status"] }),
+            |_| Err(IpcError::failed("Server stopped")),
+        )
+        .unwrap();
+
+        assert_eq!(response["error"]["code"], "failed");
+        assert_eq!(response["error"]["message"], "Server stopped");
+    }
+
+    #[test]
+    fn request_errors() {
+        let unknown = reply(
+            json!({ "id": 2, "type": INVOKE_TYPE, "args": ["unknown"] }),
+            |_| panic!("Handler called for an unknown method"),
+        )
+        .unwrap();
+        assert_eq!(unknown["error"]["code"], "unknown-method");
+
+        let invalid = reply(
+            json!({ "id": 3, "type": INVOKE_TYPE, "args": ["win-set-visibility", 1] }),
+            |_| panic!("Handler called with invalid arguments"),
+        )
+        .unwrap();
+        assert_eq!(invalid["error"]["code"], "invalid-arguments");
+
+        let unknown_type = reply(json!({ "id": 4, "type": 99 }), |_| {
+            panic!("Handler called for an unknown type")
+        })
+        .unwrap();
+        assert_eq!(unknown_type["error"]["code"], "unknown-type");
+
+        let parse_error = reply(json!({ "id": 5, "type": "invoke" }), |_| {
+            panic!("Handler called for an invalid message")
+        })
+        .unwrap();
+        assert_eq!(parse_error["id"], 5);
+        assert_eq!(parse_error["error"]["code"], "parse-error");
+
+        assert!(parse_request("not json".to_owned(), |_| Ok(Value::Null)).is_none());
+    }
+
+    #[test]
+    fn events_have_no_reply() {
+        let mut presence = vec![];
+        let response = reply(
+            json!({ "type": EVENT_TYPE, "args": ["discord-presence", "library"] }),
+            |event| {
+                if let IpcEvent::DiscordPresence(args) = event {
+                    presence = args;
+                }
+                Ok(Value::Null)
+            },
+        );
+
+        assert!(response.is_none());
+        assert_eq!(presence, vec!["library"]);
+    }
+
+    #[test]
+    fn signals() {
+        let message: Value = serde_json::from_str(&create_response(IpcEvent::Mpv(
+            IpcEventMpv::Change(MpvProperty("pause".to_owned(), Some(json!(true)))),
+        )))
+        .unwrap();
+
+        assert_eq!(message["type"], SIGNAL_TYPE);
+        assert_eq!(message["object"], TRANSPORT_NAME);
+        assert_eq!(message["args"][0], signals::MPV_PROP_CHANGE);
+        assert_eq!(message["args"][1], json!({ "name": "pause", "data": true }));
+
+        assert_eq!(create_response(IpcEvent::Quit), "null");
+    }
+}
@@ -0,0 +1,43 @@
+use super::{Action, Context, KeyBinding, ShellAction};
+
+fn binding(key: &str, context: Context, action: Action) -> KeyBinding {
+    KeyBinding {
+        key: key.to_owned(),
+        context,
+        action,
+    }
+}
+
+fn shell(key: &str, action: ShellAction) -> KeyBinding {
+    binding(key, Context::Global, Action::Shell(action))
+}
+
+fn shaders(key: &str, preset: Option<&str>) -> KeyBinding {
+    binding(
+        key,
+        Context::Global,
+        Action::Shaders(preset.map(str::to_owned)),
+    )
+}
+
+/// Default bindings, unbound keys of the player context are forwarded to mpv's input.conf
+pub fn bindings() -> Vec<KeyBinding> {
+    vec![
+        // CEF in windowless mode doesn't sync with the system clipboard
+        shell("Ctrl+v", ShellAction::Paste),
+        shell("Ctrl+=", ShellAction::ZoomIn),
+        shell("Ctrl+-", ShellAction::ZoomOut),
+        shell("Ctrl+p", ShellAction::PictureInPicture),
+        shell("Ctrl+Shift+d", ShellAction::Diagnostics),
+        shaders("Ctrl+0", None),
+        shaders("Ctrl+1", Some("anime4k-a")),
+        shaders("Ctrl+2", Some("anime4k-b")),
+        shaders("Ctrl+3", Some("anime4k-c")),
+        shaders("Ctrl+4", Some("anime4k-aa")),
+        shaders("Ctrl+5", Some("anime4k-bb")),
+        shaders("Ctrl+6", Some("anime4k-ca")),
+        // Fullscreen is toggled by the web UI
+        binding("f", Context::Player, Action::WebUi),
+        binding("F11", Context::Player, Action::WebUi),
+    ]
+}
@@ -0,0 +1,264 @@
+mod constants;
+mod defaults;
+mod utils;
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use winit::{
+    event::KeyEvent,
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+/// Where a binding applies, context specific bindings take precedence over global ones
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Context {
+    #[default]
+    Global,
+    /// A video is playing
+    Player,
+    /// The web UI has focus and no video is playing
+    Webview,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShellAction {
+    Paste,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    Fullscreen,
+    /// Toggles the picture-in-picture window
+    PictureInPicture,
+    /// Toggles the diagnostics overlay on the video
+    Diagnostics,
+    Quit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Handled by the shell itself
+    Shell(ShellAction),
+    /// Activates a shader preset, `null` clears the shaders
+    Shaders(Option<String>),
+    /// Runs an mpv command, optionally showing a message on the OSD
+    Mpv {
+        command: Vec<String>,
+        #[serde(default)]
+        osd: Option<String>,
+    },
+    /// Sends the key to mpv, triggering its input.conf bindings
+    MpvKey,
+    /// Passes the key through to the web UI
+    WebUi,
+    /// Sends a named action to the web UI through the `keymap-action` signal
+    #[serde(rename = "web-ui-action")]
+    WebUiSignal(String),
+    /// Swallows the key
+    Ignore,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeyBinding {
+    pub key: String,
+    #[serde(default)]
+    pub context: Context,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyCombo {
+    /// Formats the combination the way mpv's `keypress` command expects it
+    pub fn mpv_key(&self) -> Option<String> {
+        utils::mpv_key(self)
+    }
+}
+
+#[derive(Default)]
+pub struct Keymap {
+    bindings: HashMap<(Context, KeyCombo), Action>,
+}
+
+impl Keymap {
+    /// Builds the keymap from the default bindings overridden by the user ones
+    pub fn new(user_bindings: &[KeyBinding]) -> Self {
+        let mut keymap = Self::default();
+
+        defaults::bindings().into_iter().for_each(|binding| {
+            if let Ok(combo) = utils::parse_combo(&binding.key) {
+                keymap
+                    .bindings
+                    .insert((binding.context, combo), binding.action);
+            }
+        });
+
+        let mut user_keys = HashMap::new();
+
+        for binding in user_bindings {
+            let combo = match utils::parse_combo(&binding.key) {
+                Ok(combo) => combo,
+                Err(e) => {
+                    warn!(target: "keymap", "Ignoring binding: {e}");
+                    continue;
+                }
+            };
+
+            if let Some(previous) = user_keys.insert((binding.context, combo), &binding.key) {
+                warn!(
+                    target: "keymap",
+                    "Conflicting bindings '{previous}' and '{}' in the {:?} context, the last one is used",
+                    binding.key,
+                    binding.context
+                );
+            }
+
+            keymap
+                .bindings
+                .insert((binding.context, combo), binding.action.clone());
+        }
+
+        // Global bindings are never reached in contexts that bind the same keys
+        for ((context, combo), key) in user_keys.iter() {
+            if *context != Context::Global {
+                continue;
+            }
+
+            [Context::Player, Context::Webview]
+                .iter()
+                .filter(|shadowing| keymap.bindings.contains_key(&(**shadowing, *combo)))
+                .for_each(|shadowing| {
+                    warn!(
+                        target: "keymap",
+                        "Global binding '{key}' is shadowed in the {shadowing:?} context"
+                    );
+                });
+        }
+
+        keymap
+    }
+
+    /// Returns the action bound to a key press in the given context
+    pub fn resolve(
+        &self,
+        key_event: &KeyEvent,
+        modifiers: ModifiersState,
+        context: Context,
+    ) -> Option<(KeyCombo, Action)> {
+        if !key_event.state.is_pressed() {
+            return None;
+        }
+
+        let PhysicalKey::Code(key) = key_event.physical_key else {
+            return None;
+        };
+
+        let combo = KeyCombo {
+            key,
+            ctrl: modifiers.control_key(),
+            shift: modifiers.shift_key(),
+            alt: modifiers.alt_key(),
+        };
+
+        self.action(combo, context).map(|action| (combo, action))
+    }
+
+    /// Returns the action bound to a key combination in the given context
+    pub fn action(&self, combo: KeyCombo, context: Context) -> Option<Action> {
+        self.bindings
+            .get(&(context, combo))
+            .or(self.bindings.get(&(Context::Global, combo)))
+            .cloned()
+            .or_else(|| match context {
+                // While playing, every key mpv knows about goes to its input.conf
+                Context::Player => utils::key_name(combo.key).map(|_| Action::MpvKey),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn combo(value: &str) -> KeyCombo {
+        utils::parse_combo(value).expect("Invalid combo")
+    }
+
+    fn binding(value: serde_json::Value) -> KeyBinding {
+        serde_json::from_value(value).expect("Invalid binding")
+    }
+
+    #[test]
+    fn parses_combos() {
+        let parsed = combo("ctrl+Shift+Z");
+        assert_eq!(parsed.key, KeyCode::KeyZ);
+        assert!(parsed.ctrl && parsed.shift && !parsed.alt);
+
+        assert!(utils::parse_combo("Hyper+z").is_err());
+        assert!(utils::parse_combo("Ctrl+Nope").is_err());
+    }
+
+    #[test]
+    fn formats_mpv_keys() {
+        let key = |value: &str| combo(value).mpv_key();
+
+        assert_eq!(key("z").as_deref(), Some("z"));
+        assert_eq!(key("Shift+z").as_deref(), Some("Z"));
+        assert_eq!(key("Shift+LEFT").as_deref(), Some("Shift+LEFT"));
+        assert_eq!(key("Ctrl+Alt+s").as_deref(), Some("ALT+CTRL+s"));
+    }
+
+    #[test]
+    fn user_bindings_override_defaults() {
+        let keymap = Keymap::new(&[binding(json!({
+            "key": "Ctrl+v",
+            "action": "ignore",
+        }))]);
+
+        assert_eq!(
+            keymap.action(combo("Ctrl+v"), Context::Webview),
+            Some(Action::Ignore)
+        );
+    }
+
+    #[test]
+    fn context_bindings_take_precedence() {
+        let keymap = Keymap::new(&[
+            binding(json!({ "key": "k", "action": "ignore" })),
+            binding(json!({ "key": "k", "context": "player", "action": "mpv-key" })),
+        ]);
+
+        assert_eq!(
+            keymap.action(combo("k"), Context::Player),
+            Some(Action::MpvKey)
+        );
+        assert_eq!(
+            keymap.action(combo("k"), Context::Webview),
+            Some(Action::Ignore)
+        );
+    }
+
+    #[test]
+    fn player_sends_unbound_keys_to_mpv() {
+        let keymap = Keymap::new(&[]);
+
+        assert_eq!(
+            keymap.action(combo("Alt+j"), Context::Player),
+            Some(Action::MpvKey)
+        );
+        assert_eq!(keymap.action(combo("Alt+j"), Context::Webview), None);
+    }
+}
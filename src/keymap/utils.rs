@@ -0,0 +1,62 @@
+use winit::keyboard::KeyCode;
+
+use super::{KeyCombo, constants::KEY_NAMES};
+
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(code, _)| *code == key)
+        .map(|(_, name)| *name)
+}
+
+/// Parses a key combination written as `Ctrl+Shift+Alt+<key>`, modifiers are case insensitive
+pub fn parse_combo(value: &str) -> Result<KeyCombo, String> {
+    let (modifiers, key) = value.rsplit_once('+').unwrap_or(("", value));
+
+    let code = KEY_NAMES
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(key))
+        .map(|(code, _)| *code)
+        .ok_or(format!("Unknown key '{key}' in '{value}'"))?;
+
+    let mut combo = KeyCombo {
+        key: code,
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+
+    for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+        match modifier.to_lowercase().as_str() {
+            "ctrl" => combo.ctrl = true,
+            "shift" => combo.shift = true,
+            "alt" => combo.alt = true,
+            _ => return Err(format!("Unknown modifier '{modifier}' in '{value}'")),
+        }
+    }
+
+    Ok(combo)
+}
+
+/// Formats a key combination the way mpv's `keypress` command expects it
+pub fn mpv_key(combo: &KeyCombo) -> Option<String> {
+    let name = key_name(combo.key)?;
+    let is_letter = name.len() == 1 && name.chars().all(|c| c.is_ascii_lowercase());
+
+    // For letters, Shift means uppercase (z -> Z), other keys use the Shift+ prefix
+    let mut key = match (combo.shift, is_letter) {
+        (true, true) => name.to_uppercase(),
+        (true, false) => format!("Shift+{name}"),
+        (false, _) => name.to_owned(),
+    };
+
+    if combo.ctrl {
+        key = format!("CTRL+{key}");
+    }
+
+    if combo.alt {
+        key = format!("ALT+{key}");
+    }
+
+    Some(key)
+}
@@ -0,0 +1,139 @@
+use std::{
+    backtrace::Backtrace,
+    fs,
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value, json};
+use tracing::error;
+
+use crate::{config::LoggingConfig, diagnostics};
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const REPORT_FILE: &str = "report.txt";
+const VERSIONS_FILE: &str = "versions.json";
+const CONFIG_FILE: &str = "config.json";
+const LOGS_DIR: &str = "logs";
+// Older reports are removed when a new one is written
+const MAX_REPORTS: usize = 5;
+
+/// Values added to the crash reports, such as `versions`, kept up to date by the subsystems
+static CONTEXT: Lazy<Mutex<Map<String, Value>>> = Lazy::new(Default::default);
+
+pub fn set_context(key: &str, value: Value) {
+    if let Ok(mut context) = CONTEXT.lock() {
+        context.insert(key.to_owned(), value);
+    }
+}
+
+/// Writes a report with the panic, the versions, the config and the logs to the crash dir
+/// before the default panic hook runs
+pub fn install(config: &LoggingConfig) {
+    set_context("config", config.app.clone());
+
+    let crash_dir = config.crash_dir.clone();
+    let mut log_files = config.extra_files.clone();
+    log_files.extend(
+        config
+            .file
+            .parent()
+            .and_then(|dir| fs::read_dir(dir).ok())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path()),
+    );
+
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        error!(target: "crash", "{info}");
+
+        match write_report(&crash_dir, &log_files, info) {
+            Ok(dir) => error!(target: "crash", "Crash report written to {}", dir.display()),
+            Err(e) => error!(target: "crash", "Failed to write crash report: {e:#}"),
+        }
+
+        default_hook(info);
+    }));
+}
+
+fn write_report(
+    crash_dir: &Path,
+    log_files: &[PathBuf],
+    info: &PanicHookInfo,
+) -> anyhow::Result<PathBuf> {
+    remove_old_reports(crash_dir);
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let dir = crash_dir.join(format!("crash-{timestamp}"));
+    fs::create_dir_all(dir.join(LOGS_DIR)).context("Failed to create report dir")?;
+
+    let thread = thread::current();
+    let report = format!(
+        "{info}\n\nThread: {}\n\nBacktrace:\n{}\n",
+        thread.name().unwrap_or("unnamed"),
+        Backtrace::force_capture()
+    );
+    fs::write(dir.join(REPORT_FILE), report).context("Failed to write report")?;
+
+    // The lock may be held by the panicking thread
+    let context = CONTEXT
+        .try_lock()
+        .map(|context| context.clone())
+        .unwrap_or_default();
+
+    let versions = json!({
+        "shell": VERSION,
+        "player": context.get("versions"),
+        "system": diagnostics::system(),
+    });
+    fs::write(
+        dir.join(VERSIONS_FILE),
+        serde_json::to_string_pretty(&versions)?,
+    )
+    .context("Failed to write versions")?;
+
+    let config = context.get("config").cloned().unwrap_or_default();
+    fs::write(
+        dir.join(CONFIG_FILE),
+        serde_json::to_string_pretty(&config)?,
+    )
+    .context("Failed to write config")?;
+
+    log_files
+        .iter()
+        .filter_map(|file| Some((file, file.file_name()?)))
+        .for_each(|(file, name)| {
+            fs::copy(file, dir.join(LOGS_DIR).join(name)).ok();
+        });
+
+    Ok(dir)
+}
+
+fn remove_old_reports(crash_dir: &Path) {
+    let mut reports = fs::read_dir(crash_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<_>>();
+
+    // Names end with the timestamp of the crash
+    reports.sort();
+
+    let count = reports.len().saturating_sub(MAX_REPORTS - 1);
+    reports.iter().take(count).for_each(|report| {
+        fs::remove_dir_all(report).ok();
+    });
+}
@@ -0,0 +1,116 @@
+mod crash;
+mod rotating;
+#[cfg(test)]
+mod tests;
+
+use std::{
+    env,
+    io::{self, Write},
+    iter, mem,
+    sync::Mutex,
+};
+
+pub use crash::set_context;
+use once_cell::sync::{Lazy, OnceCell};
+pub use rotating::RotatingLog;
+use tracing::warn;
+use tracing_subscriber::{
+    EnvFilter, Registry, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt,
+};
+
+use crate::config::{LoggingConfig, LoggingSettings};
+
+// The log is rotated once it grows past this size
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+// Lines logged before the log file is opened are kept up to this size
+const MAX_PENDING_SIZE: usize = 64 * 1024;
+
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+static SINK: Lazy<Mutex<Sink>> = Lazy::new(|| Mutex::new(Sink::Pending(vec![])));
+
+enum Sink {
+    /// The config isn't loaded yet, the lines are written once the file is opened
+    Pending(Vec<u8>),
+    File(RotatingLog),
+    Disabled,
+}
+
+struct FileWriter;
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Ok(mut sink) = SINK.lock() {
+            match &mut *sink {
+                Sink::Pending(pending) if pending.len() + buf.len() <= MAX_PENDING_SIZE => {
+                    pending.extend_from_slice(buf)
+                }
+                Sink::File(log) => log.append(buf),
+                _ => {}
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Logs to stderr with the default levels until `configure` is called
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(filter(&LoggingSettings::default()));
+    FILTER.set(handle).ok();
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(fmt::layer().with_ansi(false).with_writer(|| FileWriter))
+        .init();
+}
+
+/// Applies the levels, opens the log file and installs the crash reporter
+pub fn configure(config: &LoggingConfig) {
+    set_levels(&config.settings);
+
+    let sink = match config.settings.file {
+        true => Sink::File(RotatingLog::new(
+            &config.file,
+            MAX_LOG_SIZE,
+            config.settings.max_files,
+        )),
+        false => Sink::Disabled,
+    };
+
+    if let Ok(mut current) = SINK.lock()
+        && let Sink::Pending(pending) = mem::replace(&mut *current, sink)
+        && let Sink::File(log) = &*current
+    {
+        log.append(&pending);
+    }
+
+    crash::install(config);
+}
+
+pub fn set_levels(settings: &LoggingSettings) {
+    if let Some(handle) = FILTER.get()
+        && let Err(e) = handle.reload(filter(settings))
+    {
+        warn!(target: "logging", "Failed to apply the log levels: {e}");
+    }
+}
+
+/// The level of the config, then the levels of its subsystems, `RUST_LOG` overrides both
+fn filter(settings: &LoggingSettings) -> EnvFilter {
+    let directives = iter::once(settings.level.clone())
+        .chain(
+            settings
+                .targets
+                .iter()
+                .map(|(target, level)| format!("{target}={level}")),
+        )
+        .chain(env::var(EnvFilter::DEFAULT_ENV).ok())
+        .collect::<Vec<_>>();
+
+    EnvFilter::builder().parse_lossy(directives.join(","))
+}
@@ -0,0 +1,97 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+/// Size based rotating log, rotated files are kept next to the current one (.1, .2, ...)
+pub struct RotatingLog {
+    path: PathBuf,
+    max_size: u64,
+    max_rotated: usize,
+    file: Mutex<Option<LogFile>>,
+}
+
+impl RotatingLog {
+    /// The log is rotated once it grows past `max_size` bytes
+    pub fn new(path: &Path, max_size: u64, max_rotated: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            max_size,
+            max_rotated,
+            file: Mutex::new(Self::open(path)),
+        }
+    }
+
+    fn open(path: &Path) -> Option<LogFile> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok()?;
+
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+
+        Some(LogFile { file, size })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{index}"));
+        path.into()
+    }
+
+    fn rotate(&self) -> Option<LogFile> {
+        for index in (1..self.max_rotated).rev() {
+            fs::rename(self.rotated_path(index), self.rotated_path(index + 1)).ok();
+        }
+
+        match self.max_rotated {
+            0 => fs::remove_file(&self.path).ok(),
+            _ => fs::rename(&self.path, self.rotated_path(1)).ok(),
+        };
+
+        Self::open(&self.path)
+    }
+
+    /// The current log first, then the rotated ones from the newest to the oldest
+    pub fn files(&self) -> Vec<PathBuf> {
+        (0..=self.max_rotated)
+            .map(|index| match index {
+                0 => self.path.clone(),
+                index => self.rotated_path(index),
+            })
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    pub fn append(&self, data: &[u8]) {
+        let Ok(mut log_file) = self.file.lock() else {
+            return;
+        };
+
+        if log_file
+            .as_ref()
+            .is_some_and(|log_file| log_file.size >= self.max_size)
+        {
+            *log_file = self.rotate();
+        }
+
+        if let Some(log_file) = log_file.as_mut()
+            && log_file.file.write_all(data).is_ok()
+        {
+            log_file.size += data.len() as u64;
+        }
+    }
+
+    /// Appends a line prefixed by the stream it comes from
+    pub fn write(&self, stream: &str, line: &str) {
+        self.append(format!("[{stream}] {line}\n").as_bytes());
+    }
+}
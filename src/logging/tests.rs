@@ -0,0 +1,78 @@
+use std::{fs, path::PathBuf};
+
+use tempfile::TempDir;
+
+use super::RotatingLog;
+
+fn contents(files: &[PathBuf]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| fs::read_to_string(file).unwrap())
+        .collect()
+}
+
+#[test]
+fn rotation() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("server.log");
+    let log = RotatingLog::new(&path, 10, 2);
+
+    // Each line fills the log, it's rotated before every line but the first
+    for line in ["1", "2", "3", "4"] {
+        log.write("stdout", line);
+    }
+
+    let files = log.files();
+    assert_eq!(
+        files,
+        [
+            path.clone(),
+            dir.path().join("server.log.1"),
+            dir.path().join("server.log.2"),
+        ]
+    );
+    assert_eq!(
+        contents(&files),
+        ["[stdout] 4\n", "[stdout] 3\n", "[stdout] 2\n"]
+    );
+    assert!(!dir.path().join("server.log.3").exists());
+}
+
+#[test]
+fn size_below_limit() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("server.log");
+    let log = RotatingLog::new(&path, 1024, 2);
+
+    log.write("stdout", "a");
+    log.write("stderr", "b");
+
+    assert_eq!(log.files(), [path]);
+    assert_eq!(contents(&log.files()), ["[stdout] a\n[stderr] b\n"]);
+}
+
+#[test]
+fn existing_log() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("server.log");
+    fs::write(&path, "0123456789").unwrap();
+
+    // The size of the existing file counts towards the limit
+    let log = RotatingLog::new(&path, 10, 1);
+    log.append(b"new\n");
+
+    assert_eq!(contents(&log.files()), ["new\n", "0123456789"]);
+}
+
+#[test]
+fn no_rotated_logs() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("server.log");
+    let log = RotatingLog::new(&path, 4, 0);
+
+    log.append(b"old\n");
+    log.append(b"new\n");
+
+    assert_eq!(log.files(), [path]);
+    assert_eq!(contents(&log.files()), ["new\n"]);
+}
@@ -1,156 +1,213 @@
 mod app;
 mod config;
+mod connectivity;
 mod constants;
+mod diagnostics;
 mod discord;
 mod gpu;
 mod instance;
 mod ipc;
+mod keymap;
+mod logging;
+mod mpris;
 mod player;
+mod router;
 mod server;
 mod shared;
 mod tray;
 mod webview;
 
-use app::{App, AppEvent};
+use app::{App, AppEvent, PipAction};
 use clap::Parser;
-use config::Config;
-use constants::{STARTUP_URL, URI_SCHEME};
+use config::{Config, ConfigEvent, ConfigWatcher, InstanceConfig};
+use connectivity::{Connectivity, ConnectivityEvent};
+use diagnostics::{Diagnostics, DiagnosticsEvent};
 use discord::Discord;
 use glutin::{display::GetGlDisplay, surface::GlSurface};
-use instance::{Instance, InstanceEvent};
-use ipc::{IpcEvent, IpcEventMpv};
-use player::{Player, PlayerEvent};
+use instance::{
+    Instance, InstanceEvent,
+    rpc::{self, ControlEvent, RpcError},
+};
+use ipc::{IpcError, IpcErrorCode, IpcEvent, IpcEventMpv, SeekHoverArgs, SubtitlesLoadArgs};
+use keymap::{Action, Context, Keymap, ShellAction};
+use mpris::{Mpris, MprisEvent};
+use player::{MpvProperty, Player, PlayerEvent};
+use router::Route;
 use rust_i18n::i18n;
-use server::Server;
-use shared::{types::UserEvent, with_gl, with_renderer_read, with_renderer_write};
-use std::{num::NonZeroU32, process::ExitCode, rc::Rc, time::Duration};
+use serde_json::{Value, json};
+use server::{Server, ServerEvent};
+use shared::{
+    FrameStats, types::UserEvent, with_gl, with_pip_gl, with_renderer_read, with_renderer_write,
+};
+use std::{
+    collections::HashSet,
+    env,
+    num::NonZeroU32,
+    process::ExitCode,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use tracing::{error, info, warn};
 use tray::Tray;
 use webview::{WebView, WebViewEvent};
 use winit::{
     event_loop::{ControlFlow, EventLoop},
-    keyboard::{KeyCode, PhysicalKey},
     platform::pump_events::{EventLoopExtPumpEvents, PumpStatus},
 };
 
 i18n!("locales", fallback = "en");
 
+/// Player properties reported by the `status` control method
+const STATUS_PROPERTIES: &[&str] = &["path", "pause", "time-pos", "duration", "volume", "mute"];
+
 #[derive(Parser, Debug)]
 #[command(version, ignore_errors(true))]
 struct Args {
     /// Open dev tools
     #[arg(short, long)]
     dev: bool,
-    /// Startup url
-    #[arg(short, long, default_value = STARTUP_URL)]
-    url: String,
-    /// Open a deeplink
+    /// Startup url, overrides webview.url
+    #[arg(short, long)]
+    url: Option<String>,
+    /// Open a deeplink, a magnet link, a torrent or media file, a directory or a stream url
     #[arg(short, long)]
     open: Option<String>,
+    /// Same as --open, used by the desktop entry
+    #[arg(value_name = "INPUT", conflicts_with = "open")]
+    input: Option<String>,
     /// Disable server
     #[arg(short, long)]
     no_server: bool,
+    /// Use a profile, with its own config, web UI data and mpv config (created on first use)
+    #[arg(long, value_parser = config::parse_profile, default_value = config::DEFAULT_PROFILE)]
+    profile: String,
+    /// Print a JSON report of the setup for bug reports
+    #[arg(long)]
+    diagnose: bool,
+    /// Call a method of the control API on the running instance (ex: --ctl pause true)
+    #[arg(long, num_args = 1.., value_names = ["METHOD", "PARAMS"], allow_hyphen_values = true)]
+    ctl: Option<Vec<String>>,
+    /// Override a config key for this run (ex: --set resume.auto_seek=true)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = config::parse_override)]
+    set: Vec<(String, Value)>,
 }
 
-/// Converts KeyCode to MPV key name for input.conf bindings
-fn keycode_to_mpv_key(key_code: KeyCode) -> Option<String> {
-    let key_str = match key_code {
-        // Letters
-        KeyCode::KeyA => "a", KeyCode::KeyB => "b", KeyCode::KeyC => "c", KeyCode::KeyD => "d",
-        KeyCode::KeyE => "e", KeyCode::KeyF => "f", KeyCode::KeyG => "g", KeyCode::KeyH => "h",
-        KeyCode::KeyI => "i", KeyCode::KeyJ => "j", KeyCode::KeyK => "k", KeyCode::KeyL => "l",
-        KeyCode::KeyM => "m", KeyCode::KeyN => "n", KeyCode::KeyO => "o", KeyCode::KeyP => "p",
-        KeyCode::KeyQ => "q", KeyCode::KeyR => "r", KeyCode::KeyS => "s", KeyCode::KeyT => "t",
-        KeyCode::KeyU => "u", KeyCode::KeyV => "v", KeyCode::KeyW => "w", KeyCode::KeyX => "x",
-        KeyCode::KeyY => "y", KeyCode::KeyZ => "z",
-        // Numbers
-        KeyCode::Digit0 => "0", KeyCode::Digit1 => "1", KeyCode::Digit2 => "2", KeyCode::Digit3 => "3",
-        KeyCode::Digit4 => "4", KeyCode::Digit5 => "5", KeyCode::Digit6 => "6", KeyCode::Digit7 => "7",
-        KeyCode::Digit8 => "8", KeyCode::Digit9 => "9",
-        // Function keys
-        KeyCode::F1 => "F1", KeyCode::F2 => "F2", KeyCode::F3 => "F3", KeyCode::F4 => "F4",
-        KeyCode::F5 => "F5", KeyCode::F6 => "F6", KeyCode::F7 => "F7", KeyCode::F8 => "F8",
-        KeyCode::F9 => "F9", KeyCode::F10 => "F10", KeyCode::F11 => "F11", KeyCode::F12 => "F12",
-        // Special keys
-        KeyCode::Space => "SPACE",
-        KeyCode::Enter => "ENTER",
-        KeyCode::Backspace => "BS",
-        KeyCode::Tab => "TAB",
-        KeyCode::Escape => "ESC",
-        KeyCode::Insert => "INS",
-        KeyCode::Delete => "DEL",
-        KeyCode::Home => "HOME",
-        KeyCode::End => "END",
-        KeyCode::PageUp => "PGUP",
-        KeyCode::PageDown => "PGDWN",
-        // Arrow keys
-        KeyCode::ArrowLeft => "LEFT",
-        KeyCode::ArrowRight => "RIGHT",
-        KeyCode::ArrowUp => "UP",
-        KeyCode::ArrowDown => "DOWN",
-        // Punctuation
-        KeyCode::Minus => "-",
-        KeyCode::Equal => "=",
-        KeyCode::BracketLeft => "[",
-        KeyCode::BracketRight => "]",
-        KeyCode::Backslash => "\\",
-        KeyCode::Semicolon => ";",
-        KeyCode::Quote => "'",
-        KeyCode::Backquote => "`",
-        KeyCode::Comma => ",",
-        KeyCode::Period => ".",
-        KeyCode::Slash => "/",
-        _ => return None,
+impl Args {
+    /// Config keys set from the command line
+    fn overrides(&self) -> Vec<(String, Value)> {
+        let mut overrides = self.set.clone();
+
+        if let Some(url) = &self.url {
+            overrides.push(("webview.url".to_owned(), json!(url)));
+        }
+
+        overrides
+    }
+}
+
+/// Sends a control API request to the running instance and prints every message received
+fn control(config: InstanceConfig, ctl: Vec<String>) -> ExitCode {
+    let mut ctl = ctl.into_iter();
+    let method = ctl.next().unwrap_or_default();
+
+    // Params are parsed as JSON when possible and fall back to plain strings,
+    // several params are sent as an array
+    let params = ctl
+        .map(|param| serde_json::from_str::<Value>(&param).unwrap_or(Value::String(param)))
+        .collect::<Vec<_>>();
+
+    let params = match params.len() {
+        0 => Value::Null,
+        1 => params.into_iter().next().unwrap_or_default(),
+        _ => Value::Array(params),
     };
-    Some(key_str.to_string())
+
+    let instance = Instance::new(config);
+    let result = instance.control(&method, params, |message| {
+        let output = message.get("result").or(message.get("params")).unwrap_or(message);
+        println!("{output}");
+    });
+
+    match result {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
-/// Returns the Anime4K shader command for a given key
-fn get_anime4k_shader_command(key_code: KeyCode) -> Option<(&'static str, &'static str)> {
-    match key_code {
-        KeyCode::Digit0 => Some(("clr", "Shaders cleared")),
-        KeyCode::Digit1 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Restore/Anime4K_Restore_CNN_VL.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_VL.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        KeyCode::Digit2 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_Denoise_CNN_x2_VL.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        KeyCode::Digit3 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_VL.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        KeyCode::Digit4 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Restore/Anime4K_Restore_CNN_M.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        KeyCode::Digit5 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_Denoise_CNN_x2_M.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        KeyCode::Digit6 => Some((
-            "set",
-            "~~/shaders/anime4k/Restore/Anime4K_Clamp_Highlights.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x2.glsl;~~/shaders/anime4k/Restore/Anime4K_AutoDownscalePre_x4.glsl;~~/shaders/anime4k/Upscale/Anime4K_Upscale_CNN_x2_M.glsl",
-        )),
-        _ => None,
+/// Updates the diagnostics overlay with the current counters
+fn show_diagnostics(diagnostics: &mut Diagnostics, player: &Player) {
+    let mut stats = FrameStats::default();
+    with_renderer_read(|renderer| stats = renderer.stats.clone());
+
+    let properties = diagnostics::PROPERTIES
+        .iter()
+        .filter_map(|name| player.get_property(name.to_string()).ok())
+        .filter_map(|MpvProperty(name, value)| Some((name, value?)))
+        .collect();
+
+    let text = diagnostics.overlay(&stats, &properties);
+    player.set_overlay(Some(&text));
+}
+
+/// Opens a `--open` input: links and torrents in the web UI, files and streams in the player
+fn open(input: &str, webview: &WebView, player: &Player) -> Result<(), String> {
+    match router::route(input)? {
+        Route::OpenMedia(link) => {
+            info!(target: "router", "Opening {link}");
+            let message = ipc::create_response(IpcEvent::OpenMedia(link));
+            webview.post_message(message);
+        }
+        Route::Play(path) => {
+            info!(target: "router", "Playing {path}");
+            player.try_command("loadfile".to_owned(), vec![path])?;
+        }
     }
+
+    Ok(())
 }
 
 fn main() -> ExitCode {
-    tracing_subscriber::fmt::init();
+    logging::init();
 
     let args = Args::parse();
-    let config = Config::new();
+    let config = Config::new(&args.profile, &args.overrides());
+
+    if args.diagnose {
+        let report = diagnostics::report(config);
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(ctl) = args.ctl {
+        return control(config.instance, ctl);
+    }
 
     let mut webview = WebView::new(config.webview);
     if webview.should_exit() {
         return ExitCode::SUCCESS;
     }
 
+    // Relative paths are resolved here, the running instance has its own working directory
+    let current_dir = env::current_dir().unwrap_or_default();
+    let mut open_input = args
+        .open
+        .as_deref()
+        .or(args.input.as_deref())
+        .map(|input| router::resolve(input, &current_dir));
+
     let instance = Instance::new(config.instance);
     if instance.running() {
-        if let Some(deeplink) = args.open {
-            instance.send(deeplink);
+        match open_input {
+            Some(input) => instance.send(input),
+            // Starting the profile again, ex: from the tray of another one, shows its window
+            None => {
+                if let Err(e) = instance.control(rpc::methods::SHOW, Value::Null, |_| {}) {
+                    warn!(target: "instance", "{e:#}");
+                }
+            }
         }
 
         return ExitCode::SUCCESS;
@@ -158,42 +215,71 @@ fn main() -> ExitCode {
 
     instance.start();
 
+    // Only the main process writes the log file
+    logging::configure(&config.logging);
+
     let mut server = Server::new(config.server);
-    if !args.no_server {
-        server.start(args.dev).expect("Failed to start server");
+    // A server that can't be started is reported through its status, the shell keeps running
+    if !args.no_server
+        && let Err(e) = server.start(args.dev)
+    {
+        error!(target: "server", "{e:#}");
     }
 
+    let mut connectivity = Connectivity::new();
+    let mut diagnostics = Diagnostics::new();
+
+    let profile = config.profile;
+    info!(target: "config", "Using profile {}", profile.name);
+
+    let mut app_config = config.app;
+    let config_watcher = ConfigWatcher::new(app_config.files());
+
     let tray = Tray::new(config.tray);
-    let mut app = App::new();
+    let mut keymap = Keymap::new(&app_config.keymap);
+    let mut app = App::new(config.window);
     let mut player = Player::new(config.player);
+    logging::set_context("versions", player.versions());
+    let mpris = Mpris::new();
+    mpris::MPRIS_PROPERTIES
+        .iter()
+        .for_each(|name| player.observe_property(name.to_string()));
     let mut is_playing = false; // Track if video is actively playing
+    // The player also observes properties for the shell itself, only these reach the web UI
+    let mut web_ui_properties = HashSet::new();
 
     // Discord needs to be in an Rc<RefCell<>> to be accessed from closures
     use std::cell::RefCell;
-    let discord = Rc::new(RefCell::new(Discord::new(config.app.discord.enabled)));
+    let discord = Rc::new(RefCell::new(Discord::new(app_config.discord.enabled)));
     let discord_clone = discord.clone();
-    let discord_clone2 = discord.clone();
 
     let mut event_loop = EventLoop::<UserEvent>::with_user_event()
         .build()
         .expect("Failed to create event loop");
 
-    event_loop.set_control_flow(ControlFlow::Poll);
+    // The loop sleeps until an event arrives, the subsystems wake it through the waker
+    event_loop.set_control_flow(ControlFlow::Wait);
 
     let event_loop_proxy = event_loop.create_proxy();
+    shared::waker::init(event_loop.create_proxy());
 
-    let mut needs_redraw = false;
+    // The video and the web UI are redrawn independently, the other one is kept in its texture
+    let mut redraw_video = false;
+    let mut redraw_ui = false;
 
     loop {
-        let timeout = match needs_redraw {
+        let timeout = match redraw_video || redraw_ui {
             true => Some(Duration::ZERO),
             false => None,
         };
 
         let status = event_loop.pump_app_events(timeout, &mut app);
+        shared::waker::woken();
 
         if let PumpStatus::Exit(exit_code) = status {
-            server.stop().expect("Failed to stop server");
+            if let Err(e) = server.stop() {
+                error!(target: "server", "Failed to stop server: {e}");
+            }
             webview.stop();
             instance.stop();
             shared::drop_renderer();
@@ -202,38 +288,317 @@ fn main() -> ExitCode {
             break ExitCode::from(exit_code as u8);
         }
 
-        if needs_redraw {
-            with_gl(|surface, context| {
-                with_renderer_read(|renderer| {
-                    player.render(renderer.fbo, renderer.width, renderer.height);
-                    renderer.draw();
+        if redraw_video || redraw_ui {
+            // A main window hidden for the picture-in-picture window isn't drawn
+            if app.is_visible() {
+                with_gl(|surface, context| {
+                    with_renderer_write(|renderer| {
+                        let start = Instant::now();
+
+                        if redraw_video {
+                            player.render(renderer.fbo, renderer.width, renderer.height);
+                        }
+
+                        renderer.draw();
+                        renderer.stats.record_frame(redraw_video, start.elapsed());
+                    });
+
+                    surface
+                        .swap_buffers(context)
+                        .expect("Failed to swap buffers");
                 });
+            }
 
-                surface
-                    .swap_buffers(context)
-                    .expect("Failed to swap buffers");
+            if redraw_video && let Some((width, height)) = app.pip_size() {
+                with_pip_gl(|surface, context| {
+                    player.render_window(width, height);
 
+                    surface
+                        .swap_buffers(context)
+                        .expect("Failed to swap buffers");
+                });
+            }
+
+            if redraw_video {
                 player.report_swap();
-            });
+            }
 
-            needs_redraw = false;
+            redraw_video = false;
+            redraw_ui = false;
         }
 
         instance.events(|event| match event {
-            InstanceEvent::Open(deeplink) => {
+            InstanceEvent::Open(input) => {
                 event_loop_proxy.send_event(UserEvent::Raise).ok();
 
-                if deeplink.starts_with(URI_SCHEME) {
-                    let message = ipc::create_response(IpcEvent::OpenMedia(deeplink.to_string()));
-                    webview.post_message(message);
+                if let Err(e) = open(&input, &webview, &player) {
+                    warn!(target: "router", "{e}");
                 }
             }
+            InstanceEvent::Control(event, responder) => {
+                let result = match event {
+                    ControlEvent::Status => {
+                        let properties = STATUS_PROPERTIES
+                            .iter()
+                            .map(|name| {
+                                let value = player
+                                    .get_property(name.to_string())
+                                    .ok()
+                                    .and_then(|property| property.1);
+
+                                (name.to_string(), value.unwrap_or(Value::Null))
+                            })
+                            .collect::<serde_json::Map<_, _>>();
+
+                        Ok(json!({
+                            "playing": is_playing,
+                            "visible": app.is_visible(),
+                            "fullscreen": app.is_fullscreen(),
+                            "server": server.status(),
+                            "connectivity": connectivity.state(),
+                            "properties": properties,
+                        }))
+                    }
+                    ControlEvent::MpvCommand((name, args)) => player
+                        .try_command(name, args)
+                        .map(|_| Value::Null)
+                        .map_err(RpcError::server),
+                    ControlEvent::MpvGet(name) => player
+                        .get_property(name)
+                        .map(|property| property.1.unwrap_or(Value::Null))
+                        .map_err(RpcError::server),
+                    ControlEvent::MpvSet(property) => player
+                        .set_property(property)
+                        .map(|_| Value::Null)
+                        .map_err(RpcError::server),
+                    ControlEvent::Pause(state) => match state {
+                        Some(state) => player
+                            .set_property(MpvProperty("pause".to_owned(), Some(json!(state)))),
+                        None => player
+                            .try_command("cycle".to_owned(), vec!["pause".to_owned()]),
+                    }
+                    .map(|_| Value::Null)
+                    .map_err(RpcError::server),
+                    ControlEvent::Seek { seconds, absolute } => {
+                        let mode = match absolute {
+                            true => "absolute",
+                            false => "relative",
+                        };
+
+                        player
+                            .try_command(
+                                "seek".to_owned(),
+                                vec![seconds.to_string(), mode.to_owned()],
+                            )
+                            .map(|_| Value::Null)
+                            .map_err(RpcError::server)
+                    }
+                    ControlEvent::Fullscreen(state) => {
+                        let state = state.unwrap_or(!app.is_fullscreen());
+                        app.set_fullscreen(state);
+                        Ok(json!(state))
+                    }
+                    ControlEvent::Show => {
+                        event_loop_proxy.send_event(UserEvent::Show).ok();
+                        Ok(Value::Null)
+                    }
+                    ControlEvent::Hide => {
+                        event_loop_proxy.send_event(UserEvent::Hide).ok();
+                        Ok(Value::Null)
+                    }
+                    ControlEvent::Open(input) => {
+                        event_loop_proxy.send_event(UserEvent::Raise).ok();
+
+                        open(&input, &webview, &player)
+                            .map(|_| Value::Null)
+                            .map_err(|e| RpcError::new(rpc::INVALID_PARAMS, e))
+                    }
+                    ControlEvent::Subscribe(names) => {
+                        names
+                            .iter()
+                            .for_each(|name| player.observe_property(name.to_owned()));
+
+                        instance.subscribe(responder.session, names);
+                        Ok(Value::Null)
+                    }
+                    ControlEvent::Unsubscribe(names) => {
+                        instance.unsubscribe(responder.session, names);
+                        Ok(Value::Null)
+                    }
+                    ControlEvent::Quit => {
+                        event_loop_proxy.send_event(UserEvent::Quit).ok();
+                        Ok(Value::Null)
+                    }
+                };
+
+                responder.respond(result);
+            }
         });
 
         tray.events(|event| {
             event_loop_proxy.send_event(event).ok();
         });
 
+        config_watcher.events(|event| match event {
+            ConfigEvent::Changed => app_config.reload(),
+        });
+
+        // Changes come from the config files and from the web UI through config-set
+        let changes = app_config.changes();
+        if !changes.is_empty() {
+            let mut restart = vec![];
+
+            for key in changes.iter() {
+                match key.as_str() {
+                    "discord.enabled" => {
+                        discord.borrow_mut().set_enabled(app_config.discord.enabled);
+                    }
+                    "resume.auto_seek" => player.set_auto_seek(app_config.resume.auto_seek),
+                    "shaders.auto" => player.set_shaders_auto(app_config.shaders.auto),
+                    "webview.zoom_step" => webview.set_zoom_step(app_config.webview.zoom_step),
+                    "window.restore" => app.set_restore(app_config.window.restore),
+                    "window.fullscreen_monitor" => {
+                        app.set_fullscreen_monitor(app_config.window.fullscreen_monitor.clone());
+                    }
+                    "shaders.preset" => {
+                        // The presets activated by the player are already applied
+                        let preset = app_config.shaders.preset.as_deref();
+                        if player.shader_presets()["active"] != json!(preset)
+                            && let Err(e) = player.set_shader_preset(preset)
+                        {
+                            warn!(target: "config", "{e}");
+                        }
+                    }
+                    "keymap" => keymap = Keymap::new(&app_config.keymap),
+                    key if key == "logging.level" || key.starts_with("logging.targets.") => {
+                        logging::set_levels(&app_config.logging);
+                        player.update_log_level();
+                    }
+                    key if key.starts_with("subtitles.") => {
+                        player.set_subtitles_config(app_config.subtitles.clone());
+                    }
+                    key if key.starts_with("audio.") => {
+                        player.set_audio_config(app_config.audio.clone());
+                    }
+                    "gpu.hwdec" if app_config.gpu.hwdec.is_some() => {
+                        let hwdec = app_config.gpu.hwdec.clone();
+                        let property = MpvProperty("hwdec".to_owned(), hwdec.map(Value::from));
+                        if let Err(e) = player.set_property(property) {
+                            warn!(target: "config", "{e}");
+                        }
+                    }
+                    // Read on every use
+                    "thumbfast.enabled" | "thumbfast.height" => {}
+                    key => match key.strip_prefix("mpv.").zip(app_config.get(Some(key))) {
+                        Some((name, value)) if !value.is_null() => {
+                            let property = MpvProperty(name.to_owned(), Some(value));
+                            if let Err(e) = player.set_property(property) {
+                                warn!(target: "config", "{e}");
+                            }
+                        }
+                        _ => {
+                            warn!(target: "config", "{key} changed, restart to apply it");
+                            restart.push(key.to_owned());
+                        }
+                    },
+                }
+            }
+
+            logging::set_context("config", app_config.get(None).unwrap_or_default());
+
+            let values = changes
+                .iter()
+                .map(|key| (key.to_owned(), app_config.get(Some(key)).unwrap_or_default()))
+                .collect::<serde_json::Map<_, _>>();
+
+            let message = ipc::create_response(IpcEvent::ConfigChanged(json!({
+                "changes": values,
+                "restart": restart,
+            })));
+            webview.post_message(message);
+        }
+
+        server.events(|event| match event {
+            ServerEvent::Status(status) => {
+                let message = ipc::create_response(IpcEvent::ServerStatusChanged(status));
+                webview.post_message(message);
+            }
+        });
+
+        connectivity.events(|event| match event {
+            ConnectivityEvent::Changed(state) => {
+                let message = ipc::create_response(IpcEvent::ConnectivityChanged(state));
+                webview.post_message(message);
+            }
+            ConnectivityEvent::Reload(url) => {
+                info!(target: "webview", "Web UI reachable again, reloading");
+                webview.navigate(&url);
+            }
+        });
+
+        let mut update_diagnostics = false;
+        diagnostics.events(|event| match event {
+            DiagnosticsEvent::Update => update_diagnostics = true,
+        });
+
+        if update_diagnostics && diagnostics.enabled() {
+            show_diagnostics(&mut diagnostics, &player);
+        }
+
+        mpris.events(|event| match event {
+            MprisEvent::Play => {
+                let property = MpvProperty("pause".to_owned(), Some(json!(false)));
+                player.set_property(property).ok();
+            }
+            MprisEvent::Pause => {
+                let property = MpvProperty("pause".to_owned(), Some(json!(true)));
+                player.set_property(property).ok();
+            }
+            MprisEvent::PlayPause => {
+                player.command("cycle".to_owned(), vec!["pause".to_owned()]);
+            }
+            MprisEvent::Stop => {
+                player.command("stop".to_owned(), vec![]);
+            }
+            MprisEvent::Next => {
+                player.command("playlist-next".to_owned(), vec![]);
+            }
+            MprisEvent::Previous => {
+                player.command("playlist-prev".to_owned(), vec![]);
+            }
+            MprisEvent::Seek(offset) => {
+                let seconds = offset as f64 / 1_000_000.0;
+                player.command(
+                    "seek".to_owned(),
+                    vec![seconds.to_string(), "relative".to_owned()],
+                );
+            }
+            MprisEvent::SetPosition(position) => {
+                let seconds = position as f64 / 1_000_000.0;
+                player.command(
+                    "seek".to_owned(),
+                    vec![seconds.to_string(), "absolute".to_owned()],
+                );
+            }
+            MprisEvent::Volume(volume) => {
+                let property = MpvProperty("volume".to_owned(), Some(json!(volume * 100.0)));
+                player.set_property(property).ok();
+            }
+            MprisEvent::OpenUri(uri) => {
+                event_loop_proxy.send_event(UserEvent::Raise).ok();
+
+                if let Err(e) = open(&uri, &webview, &player) {
+                    warn!(target: "router", "{e}");
+                }
+            }
+            MprisEvent::Raise => {
+                event_loop_proxy.send_event(UserEvent::Raise).ok();
+            }
+            MprisEvent::Quit => {
+                event_loop_proxy.send_event(UserEvent::Quit).ok();
+            }
+        });
+
         app.events(|event| match event {
             AppEvent::Init => {
                 webview.start();
@@ -256,7 +621,8 @@ fn main() -> ExitCode {
                     });
 
                     webview.update();
-                    needs_redraw = true;
+                    redraw_video = true;
+                    redraw_ui = true;
                 });
             }
             AppEvent::Focused(state) => {
@@ -267,6 +633,10 @@ fn main() -> ExitCode {
                 webview.post_message(message);
 
                 tray.update(visible);
+
+                // The main window wasn't drawn while it was hidden
+                redraw_video = true;
+                redraw_ui = true;
             }
             AppEvent::Minimized(minimized) => {
                 let message = ipc::create_response(IpcEvent::Minimized(minimized));
@@ -289,122 +659,60 @@ fn main() -> ExitCode {
                 webview.touch_input(touch);
             }
             AppEvent::KeyboardInput((key_event, modifiers)) => {
-                // Intercept Ctrl+V for clipboard paste from system
-                // CEF in windowless mode doesn't automatically sync with system clipboard
-                if modifiers.control_key() && key_event.state.is_pressed() {
-                    if let PhysicalKey::Code(KeyCode::KeyV) = key_event.physical_key {
-                        webview.paste_from_clipboard();
-                        return; // Don't forward to CEF or webview
-                    }
-                }
+                let context = match is_playing {
+                    true => Context::Player,
+                    false => Context::Webview,
+                };
 
-                // Intercept Ctrl+0-6 for Anime4K shader switching
-                if modifiers.control_key() && key_event.state.is_pressed() {
-                    if let PhysicalKey::Code(key_code) = key_event.physical_key {
-                        if let Some((action, _label)) = get_anime4k_shader_command(key_code) {
-                            if action == "clr" {
-                                println!("🎨 [ANIME4K] Clearing all shaders");
-                                player.command(
-                                    "change-list".to_string(),
-                                    vec![
-                                        "glsl-shaders".to_string(),
-                                        "clr".to_string(),
-                                        "".to_string(),
-                                    ],
-                                );
-                                player.command(
-                                    "show-text".to_string(),
-                                    vec!["Shaders cleared".to_string()],
-                                );
-                            } else {
-                                let shader_list = _label;
-                                let mode_label = match key_code {
-                                    KeyCode::Digit1 => "Anime4K: Mode A (HQ)",
-                                    KeyCode::Digit2 => "Anime4K: Mode B (HQ+Denoise)",
-                                    KeyCode::Digit3 => "Anime4K: Mode C (Fast)",
-                                    KeyCode::Digit4 => "Anime4K: Mode A+A (HQ)",
-                                    KeyCode::Digit5 => "Anime4K: Mode B+B (HQ+Denoise)",
-                                    KeyCode::Digit6 => "Anime4K: Mode C+A (Fast)",
-                                    _ => "Anime4K",
-                                };
-                                println!("🎨 [ANIME4K] Activating: {}", mode_label);
-                                player.command(
-                                    "change-list".to_string(),
-                                    vec![
-                                        "glsl-shaders".to_string(),
-                                        "set".to_string(),
-                                        shader_list.to_string(),
-                                    ],
-                                );
-                                player
-                                    .command("show-text".to_string(), vec![mode_label.to_string()]);
-                            }
-                            return; // Don't forward to webview
+                match keymap.resolve(&key_event, modifiers, context) {
+                    Some((_, Action::Shell(action))) => match action {
+                        ShellAction::Paste => webview.paste_from_clipboard(),
+                        ShellAction::ZoomIn => webview.zoom_in(),
+                        ShellAction::ZoomOut => webview.zoom_out(),
+                        ShellAction::ZoomReset => webview.zoom_reset(),
+                        ShellAction::Fullscreen => app.set_fullscreen(!app.is_fullscreen()),
+                        ShellAction::PictureInPicture => {
+                            event_loop_proxy
+                                .send_event(UserEvent::PictureInPicture(None))
+                                .ok();
+                        }
+                        ShellAction::Diagnostics => match diagnostics.toggle() {
+                            true => show_diagnostics(&mut diagnostics, &player),
+                            false => player.set_overlay(None),
+                        },
+                        ShellAction::Quit => {
+                            event_loop_proxy.send_event(UserEvent::Quit).ok();
+                        }
+                    },
+                    Some((_, Action::Shaders(preset))) => {
+                        if let Err(e) = player.set_shader_preset(preset.as_deref()) {
+                            player.command("show-text".to_owned(), vec![e]);
                         }
                     }
-                }
-
-                // Track if we forwarded the key to MPV
-                let mut forwarded_to_mpv = false;
-
-                // Forward keypresses to MPV ONLY when video is playing
-                if is_playing && key_event.state.is_pressed() {
-                    if let PhysicalKey::Code(key_code) = key_event.physical_key {
-                        // Skip keys that should be handled by the web UI, not MPV
-                        // F and F11 are for fullscreen toggle, handled by useFullscreen.ts
-                        let is_webui_key = matches!(key_code, KeyCode::KeyF | KeyCode::F11);
-
-                        if !is_webui_key && let Some(mut mpv_key) = keycode_to_mpv_key(key_code) {
-                            // Handle modifiers (MPV format: CTRL+s, Shift+g, etc.)
-                            // For letters with Shift, MPV expects uppercase letter (z -> Z)
-                            // For other keys, use explicit Shift+ prefix
-                            let is_letter = matches!(key_code,
-                                KeyCode::KeyA | KeyCode::KeyB | KeyCode::KeyC | KeyCode::KeyD |
-                                KeyCode::KeyE | KeyCode::KeyF | KeyCode::KeyG | KeyCode::KeyH |
-                                KeyCode::KeyI | KeyCode::KeyJ | KeyCode::KeyK | KeyCode::KeyL |
-                                KeyCode::KeyM | KeyCode::KeyN | KeyCode::KeyO | KeyCode::KeyP |
-                                KeyCode::KeyQ | KeyCode::KeyR | KeyCode::KeyS | KeyCode::KeyT |
-                                KeyCode::KeyU | KeyCode::KeyV | KeyCode::KeyW | KeyCode::KeyX |
-                                KeyCode::KeyY | KeyCode::KeyZ
-                            );
-
-                            if modifiers.shift_key() && is_letter {
-                                // For letters, Shift means uppercase (z -> Z)
-                                mpv_key = mpv_key.to_uppercase();
-                            } else if modifiers.shift_key() {
-                                // For non-letters, use Shift+ prefix
-                                mpv_key = format!("Shift+{}", mpv_key);
-                            }
-
-                            if modifiers.control_key() {
-                                mpv_key = format!("CTRL+{}", mpv_key);
-                            }
-                            if modifiers.alt_key() {
-                                mpv_key = format!("ALT+{}", mpv_key);
-                            }
-
-                            // Send keypress to MPV (will trigger input.conf bindings)
-                            player.command("keypress".to_string(), vec![mpv_key]);
-                            forwarded_to_mpv = true;
-
-                            // Don't forward navigation/editing keys to webview to prevent conflicts
-                            // These keys can cause crashes or unwanted behavior in CEF
-                            let is_navigation_key = matches!(key_code,
-                                KeyCode::Backspace | KeyCode::Delete | KeyCode::Home | KeyCode::End |
-                                KeyCode::PageUp | KeyCode::PageDown | KeyCode::ArrowLeft |
-                                KeyCode::ArrowRight | KeyCode::ArrowUp | KeyCode::ArrowDown
-                            );
+                    Some((_, Action::Mpv { command, osd })) => {
+                        let mut command = command.into_iter();
+                        if let Some(name) = command.next() {
+                            player.command(name, command.collect());
+                        }
 
-                            if is_navigation_key {
-                                return; // Don't forward to webview
-                            }
+                        if let Some(osd) = osd {
+                            player.command("show-text".to_owned(), vec![osd]);
                         }
                     }
-                }
-
-                // Only forward to webview if we didn't handle it for MPV, or if it's a safe key
-                if !forwarded_to_mpv {
-                    webview.keyboard_input(key_event, modifiers);
+                    Some((combo, Action::MpvKey)) => {
+                        // Sends the keypress to mpv, triggering input.conf bindings
+                        if let Some(mpv_key) = combo.mpv_key() {
+                            player.command("keypress".to_owned(), vec![mpv_key]);
+                        }
+                    }
+                    Some((_, Action::WebUiSignal(action))) => {
+                        let message = ipc::create_response(IpcEvent::KeymapAction(action));
+                        webview.post_message(message);
+                    }
+                    Some((_, Action::Ignore)) => {}
+                    Some((_, Action::WebUi)) | None => {
+                        webview.keyboard_input(key_event, modifiers);
+                    }
                 }
             }
             AppEvent::FileHover((path, state)) => {
@@ -416,31 +724,97 @@ fn main() -> ExitCode {
             AppEvent::FileCancel => {
                 webview.file_cancel();
             }
+            AppEvent::PictureInPicture(state) => {
+                let message = ipc::create_response(IpcEvent::PictureInPictureChanged(state));
+                webview.post_message(message);
+                redraw_video = true;
+                redraw_ui = true;
+            }
+            AppEvent::PipAction(action) => {
+                let command = match action {
+                    PipAction::TogglePause => vec!["cycle".to_owned(), "pause".to_owned()],
+                    PipAction::Seek(seconds) => vec!["seek".to_owned(), seconds.to_string()],
+                    PipAction::Volume(step) => {
+                        vec!["add".to_owned(), "volume".to_owned(), step.to_string()]
+                    }
+                    PipAction::ShowControls => vec!["show-progress".to_owned()],
+                };
+
+                // The OSD is the only overlay of the picture-in-picture window
+                player.command("osd-msg-bar".to_owned(), command);
+            }
+            AppEvent::PipResized => {
+                redraw_video = true;
+            }
+            AppEvent::Monitor(monitor) => {
+                info!(
+                    target: "window",
+                    "On monitor {} ({} Hz, scale {})",
+                    monitor.name.as_deref().unwrap_or("unknown"),
+                    monitor.refresh_rate,
+                    monitor.scale_factor
+                );
+
+                with_renderer_write(|renderer| {
+                    renderer.refresh_rate = monitor.refresh_rate;
+                });
+
+                webview.set_frame_rate(monitor.refresh_rate);
+            }
+            AppEvent::ScaleFactor(scale_factor) => {
+                with_renderer_write(|renderer| {
+                    renderer.set_scale_factor(scale_factor);
+                });
+
+                webview.scale_changed();
+                redraw_ui = true;
+            }
         });
 
         webview.events(|event| match event {
             WebViewEvent::Ready => {
-                webview.navigate(&args.url);
+                webview.navigate(&app_config.webview.url);
+                connectivity.loading(&app_config.webview.url);
                 webview.dev_tools(args.dev);
             }
-            WebViewEvent::Loaded => {
+            WebViewEvent::Loaded(url) => {
+                connectivity.loaded(&url);
+
                 // Proactively send Init message to tell web UI we're a shell (enables MPV)
-                let init_message = ipc::create_response(IpcEvent::Init(1));
+                let init_message = ipc::create_response(IpcEvent::Init {
+                    id: 1,
+                    version: Some(ipc::PROTOCOL_VERSION),
+                });
                 webview.post_message(init_message);
 
-                if let Some(deeplink) = &args.open
-                    && deeplink.starts_with(URI_SCHEME)
+                // Reloads (reconnection, bundled web UI) must not open it again,
+                // it waits for a web UI when the error page is shown first
+                if !WebView::is_shell_page(&url)
+                    && let Some(input) = open_input.take()
+                    && let Err(e) = open(&input, &webview, &player)
                 {
-                    let message = ipc::create_response(IpcEvent::OpenMedia(deeplink.to_string()));
-                    webview.post_message(message);
+                    warn!(target: "router", "{e}");
+                }
+            }
+            // Failures of the shell pages are ignored, showing the error page again would loop
+            WebViewEvent::LoadFailed(url, _) if WebView::is_local_url(&url) => {}
+            WebViewEvent::LoadFailed(url, reason) => {
+                warn!(target: "webview", "Failed to load {url}: {reason}");
+                connectivity.failed(&url, &reason);
+
+                if app_config.webview.fallback && webview.load_bundle() {
+                    info!(target: "webview", "Remote web UI unavailable, loading the bundled one");
+                    connectivity.fallback(&url);
+                } else {
+                    webview.show_error_page(&url, &reason);
                 }
             }
             WebViewEvent::Paint => {
-                needs_redraw = true;
+                redraw_ui = true;
             }
             WebViewEvent::Resized => {
                 webview.update();
-                needs_redraw = true;
+                redraw_ui = true;
             }
             WebViewEvent::Cursor(cursor) => {
                 app.set_cursor(cursor);
@@ -448,92 +822,234 @@ fn main() -> ExitCode {
             WebViewEvent::Open(url) => {
                 futures::executor::block_on(app.open_url(url));
             }
-            WebViewEvent::Ipc(data) => ipc::parse_request(data, |event| match event {
-                IpcEvent::Init(id) => {
-                    let message = ipc::create_response(IpcEvent::Init(id));
-                    webview.post_message(message);
-                }
-                IpcEvent::Fullscreen(state) => {
-                    app.set_fullscreen(state);
-                }
-                IpcEvent::OpenExternal(url) => {
-                    futures::executor::block_on(app.open_url(url));
-                }
-                IpcEvent::Quit => {
-                    event_loop_proxy.send_event(UserEvent::Quit).ok();
-                }
-                IpcEvent::Mpv(event) => match event {
-                    IpcEventMpv::Observe(name) => {
-                        player.observe_property(name);
+            WebViewEvent::Ipc(data) => {
+                diagnostics.record_ipc();
+                let reply = ipc::parse_request(data, |event| match event {
+                    IpcEvent::Init { .. } => Ok(Value::Null),
+                    IpcEvent::Fullscreen(state) => {
+                        app.set_fullscreen(state);
+                        Ok(Value::Null)
                     }
-                    IpcEventMpv::Command((name, args)) => {
-                        player.command(name, args);
+                    IpcEvent::Monitors => Ok(json!({
+                        "current": app.monitor().and_then(|monitor| monitor.name.clone()),
+                        "fullscreen": app_config.window.fullscreen_monitor,
+                        "monitors": app.monitors(),
+                    })),
+                    IpcEvent::FrameStats => {
+                        let mut stats = Value::Null;
+                        with_renderer_read(|renderer| stats = json!(renderer.stats));
+
+                        Ok(stats)
                     }
-                    IpcEventMpv::Set(property) => {
-                        player.set_property(property);
+                    IpcEvent::PictureInPicture(state) => {
+                        event_loop_proxy
+                            .send_event(UserEvent::PictureInPicture(state))
+                            .ok();
+                        Ok(Value::Null)
                     }
-                    _ => {}
-                },
-                IpcEvent::DiscordPresence(args) => {
-                    discord_clone.borrow_mut().update_presence(args);
-                }
-                IpcEvent::DiscordToggle(enabled) => {
-                    discord_clone2.borrow_mut().set_enabled(enabled);
-                    // Save to unified config file
-                    let data_dir = dirs::data_dir()
-                        .expect("Failed to get data dir")
-                        .join(crate::constants::DATA_DIR);
-                    let mut app_config = config::AppConfig::load(&data_dir);
-                    app_config.set_discord_enabled(enabled);
-                }
-                IpcEvent::SeekHover(seconds, x, y) => {
-                    if config.app.thumbfast.enabled && config.app.thumbfast.height > 0 {
-                        let adjusted_y = y - config.app.thumbfast.height;
-                        player.command(
-                            "script-message-to".to_string(),
-                            vec![
-                                "thumbfast".to_string(),
-                                "thumb".to_string(),
-                                seconds,
-                                x,
-                                adjusted_y.to_string()
-                            ]
-                        );
+                    IpcEvent::OpenExternal(url) => {
+                        futures::executor::block_on(app.open_url(url));
+                        Ok(Value::Null)
                     }
-                }
-                IpcEvent::SeekLeave => {
-                    if config.app.thumbfast.enabled && config.app.thumbfast.height > 0 {
-                        player.command(
-                            "script-message-to".to_string(),
-                            vec!["thumbfast".to_string(), "clear".to_string()]
-                        );
+                    IpcEvent::Quit => {
+                        event_loop_proxy.send_event(UserEvent::Quit).ok();
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::ProfilesGet => Ok(json!({
+                        "current": profile.name,
+                        "profiles": profile.list(),
+                    })),
+                    IpcEvent::ProfilesSwitch(name) => {
+                        if name != profile.name {
+                            event_loop_proxy.send_event(UserEvent::SwitchProfile(name)).ok();
+                        }
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::Mpv(event) => match event {
+                        IpcEventMpv::Observe(name) => {
+                            web_ui_properties.insert(name.clone());
+                            player.observe_property(name);
+                            Ok(Value::Null)
+                        }
+                        IpcEventMpv::Command((name, args)) => player
+                            .try_command(name, args)
+                            .map(|_| Value::Null)
+                            .map_err(IpcError::failed),
+                        IpcEventMpv::Set(property) => player
+                            .set_property(property)
+                            .map(|_| Value::Null)
+                            .map_err(IpcError::failed),
+                        IpcEventMpv::Get(name) => player
+                            .get_property(name)
+                            .map(|property| json!(property))
+                            .map_err(IpcError::failed),
+                        IpcEventMpv::GetType(name) => Ok(json!(player.property_type(&name))),
+                        _ => Ok(Value::Null),
+                    },
+                    IpcEvent::DiscordPresence(args) => {
+                        mpris.update_presence(&args);
+                        discord_clone.borrow_mut().update_presence(args);
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::DiscordToggle(enabled) => {
+                        app_config.set_discord_enabled(enabled);
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::SeekHover(SeekHoverArgs(seconds, x, y)) => {
+                        if app_config.thumbfast.enabled && app_config.thumbfast.height > 0 {
+                            // The web UI positions are in device independent pixels
+                            let scale = app.scale_factor();
+                            let x = x
+                                .parse::<f64>()
+                                .map_or(x, |x| ((x * scale).round() as i64).to_string());
+                            let adjusted_y =
+                                (y as f64 * scale).round() as i64 - app_config.thumbfast.height;
+                            player.command(
+                                "script-message-to".to_string(),
+                                vec![
+                                    "thumbfast".to_string(),
+                                    "thumb".to_string(),
+                                    seconds,
+                                    x,
+                                    adjusted_y.to_string()
+                                ]
+                            );
+                        }
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::SeekLeave => {
+                        if app_config.thumbfast.enabled && app_config.thumbfast.height > 0 {
+                            player.command(
+                                "script-message-to".to_string(),
+                                vec!["thumbfast".to_string(), "clear".to_string()]
+                            );
+                        }
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::ResumeGet(path) => {
+                        Ok(json!(player.resume_position(path.as_deref())))
+                    }
+                    IpcEvent::ShadersGet => Ok(player.shader_presets()),
+                    IpcEvent::ServerStatus => Ok(json!(server.status())),
+                    IpcEvent::ConnectivityStatus => Ok(json!(connectivity.state())),
+                    IpcEvent::ConnectivityRetry => {
+                        connectivity.retry();
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::ConfigGet(key) => {
+                        app_config.get(key.as_deref()).ok_or_else(|| {
+                            IpcError::new(
+                                IpcErrorCode::InvalidArguments,
+                                format!("Unknown config key '{}'", key.unwrap_or_default()),
+                            )
+                        })
+                    }
+                    IpcEvent::ConfigSet(key, _) if !config::AppConfig::writable(&key) => {
+                        Err(IpcError::new(
+                            IpcErrorCode::InvalidArguments,
+                            format!("Config key '{key}' can only be changed in the config files"),
+                        ))
+                    }
+                    IpcEvent::ConfigSet(key, value) => match value {
+                        Some(value) => app_config.set(&key, value),
+                        None => app_config.unset(&key),
                     }
+                    .map(|_| Value::Null)
+                    .map_err(|e| IpcError::failed(format!("{e:#}"))),
+                    IpcEvent::ConfigSchema => Ok(config::AppConfig::schema()),
+                    IpcEvent::ShadersSet(preset) => player
+                        .set_shader_preset(preset.as_deref())
+                        .map(|_| Value::Null)
+                        .map_err(IpcError::failed),
+                    IpcEvent::ShadersContent(content) => {
+                        player.set_shader_content(content);
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::SubtitlesTracks => Ok(json!(player.subtitle_tracks())),
+                    IpcEvent::SubtitlesLoad(SubtitlesLoadArgs {
+                        source,
+                        lang,
+                        title,
+                    }) => {
+                        player.load_subtitle(source, lang, title);
+                        Ok(Value::Null)
+                    }
+                    IpcEvent::AudioDeviceList => Ok(player.audio_devices()),
+                    IpcEvent::AudioDevice(device) => app_config
+                        .set("audio.device", json!(device))
+                        .map(|_| Value::Null)
+                        .map_err(|e| IpcError::failed(format!("{e:#}"))),
+                    IpcEvent::AudioSpdif(_)
+                    | IpcEvent::AudioChannels(_)
+                    | IpcEvent::AudioNormalization(_) => {
+                        // Saved for the current device, applied once the config change is seen
+                        let mut settings = player.audio_device_settings();
+                        match event {
+                            IpcEvent::AudioSpdif(codecs) => settings.spdif = codecs,
+                            IpcEvent::AudioChannels(channels) => settings.channels = channels,
+                            IpcEvent::AudioNormalization(mode) => settings.normalization = mode,
+                            _ => {}
+                        }
+
+                        app_config
+                            .set_audio_device_settings(settings.clone())
+                            .map(|_| json!(settings))
+                            .map_err(|e| IpcError::failed(format!("{e:#}")))
+                    }
+                    event => Err(IpcError::new(
+                        IpcErrorCode::UnknownMethod,
+                        format!("{event:?} cannot be sent by the web UI"),
+                    )),
+                });
+
+                if let Some(message) = reply {
+                    webview.post_message(message);
                 }
-                _ => {}
-            }),
+            }
         });
 
         player.events(|event| match event {
             PlayerEvent::Start => {
                 is_playing = true;
-                println!("🎬 [PLAYER] Video started - MPV shortcuts enabled");
+                info!(target: "player", "Video started, mpv shortcuts enabled");
+                mpris.start();
                 futures::executor::block_on(app.disable_idling());
             }
             PlayerEvent::Stop(error) => {
                 is_playing = false;
-                println!("⏹️  [PLAYER] Video stopped - MPV shortcuts disabled");
+                info!(target: "player", "Video stopped, mpv shortcuts disabled");
+                mpris.stop();
                 futures::executor::block_on(app.enable_idling());
 
                 let message = ipc::create_response(IpcEvent::Mpv(IpcEventMpv::Ended(error)));
                 webview.post_message(message);
             }
             PlayerEvent::Update => {
-                needs_redraw = true;
+                redraw_video = true;
             }
-            PlayerEvent::PropertyChange(property) => {
-                let message = ipc::create_response(IpcEvent::Mpv(IpcEventMpv::Change(property)));
+            PlayerEvent::ShadersChanged { preset, manual } => {
+                // Only presets picked by the user are remembered, not the automatic ones
+                if manual {
+                    app_config.set_shader_preset(preset.clone());
+                }
+
+                let message = ipc::create_response(IpcEvent::ShadersChanged(preset));
                 webview.post_message(message);
             }
+            PlayerEvent::SubtitleLoaded { source, error } => {
+                let message = ipc::create_response(IpcEvent::SubtitlesLoaded(source, error));
+                webview.post_message(message);
+            }
+            PlayerEvent::PropertyChange(property) => {
+                instance.notify_property(&property);
+                mpris.update_property(&property);
+
+                if web_ui_properties.contains(property.name()) {
+                    let message =
+                        ipc::create_response(IpcEvent::Mpv(IpcEventMpv::Change(property)));
+                    webview.post_message(message);
+                }
+            }
         });
     }
 }
@@ -0,0 +1,529 @@
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashMap, process, thread};
+
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde_json::Value;
+use tracing::{error, info};
+use zbus::{
+    blocking::{Connection, connection},
+    interface,
+    object_server::SignalEmitter,
+    zvariant::{ObjectPath, OwnedValue, Str},
+};
+
+use crate::{
+    constants::{APP_ID, APP_NAME},
+    player::MpvProperty,
+    router,
+    shared::waker::{self, WakingSender},
+};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.stremio";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const TRACK_ID: &str = "/com/stremio/Stremio/CurrentTrack";
+const NO_TRACK_ID: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+// A jump bigger than this between two `time-pos` updates is reported as a seek
+const SEEK_THRESHOLD_US: i64 = 2_000_000;
+
+/// Player properties needed to keep the MPRIS state up to date
+pub const MPRIS_PROPERTIES: &[&str] = &["pause", "time-pos", "duration", "volume", "path"];
+
+#[derive(Debug)]
+pub enum MprisEvent {
+    Play,
+    Pause,
+    PlayPause,
+    Stop,
+    Next,
+    Previous,
+    Seek(i64),
+    SetPosition(i64),
+    Volume(f64),
+    OpenUri(String),
+    Raise,
+    Quit,
+}
+
+enum MprisUpdate {
+    Property(String, Value),
+    Metadata(Metadata),
+    Start,
+    Stop,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Metadata {
+    title: Option<String>,
+    subtitle: Option<String>,
+    album: Option<String>,
+    art_url: Option<String>,
+}
+
+impl Metadata {
+    /// Builds the metadata from the arguments of the `discord-presence` IPC method
+    fn from_presence(args: &[String]) -> Option<Self> {
+        let arg = |index: usize| args.get(index).filter(|arg| !arg.is_empty()).cloned();
+
+        match args.first().map(String::as_str) {
+            Some("watching") => {
+                let series = args.get(1).is_some_and(|kind| kind == "series");
+
+                Some(match series {
+                    true => Self {
+                        title: arg(5).or(arg(2)),
+                        subtitle: Some(format!(
+                            "S{}E{}",
+                            arg(3).unwrap_or_default(),
+                            arg(4).unwrap_or_default()
+                        )),
+                        album: arg(2),
+                        art_url: arg(6).or(arg(7)),
+                    },
+                    false => Self {
+                        title: arg(2),
+                        subtitle: None,
+                        album: None,
+                        art_url: arg(7),
+                    },
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    active: bool,
+    paused: bool,
+    position: i64,
+    duration: i64,
+    volume: f64,
+    path: Option<String>,
+    metadata: Metadata,
+}
+
+/// What changed on the MPRIS side after a player property change
+#[derive(Debug, PartialEq)]
+enum Change {
+    PlaybackStatus,
+    Seeked(i64),
+    Duration,
+    Volume,
+    Metadata,
+}
+
+impl State {
+    /// Applies a change of one of the `MPRIS_PROPERTIES`
+    fn update(&mut self, name: &str, value: &Value) -> Option<Change> {
+        match name {
+            "pause" => {
+                self.paused = value.as_bool().unwrap_or(false);
+                Some(Change::PlaybackStatus)
+            }
+            "time-pos" => {
+                let position = seconds_to_us(value);
+                let expected = self.position;
+                self.position = position;
+
+                ((position - expected).abs() > SEEK_THRESHOLD_US)
+                    .then_some(Change::Seeked(position))
+            }
+            "duration" => {
+                self.duration = seconds_to_us(value);
+                Some(Change::Duration)
+            }
+            "volume" => {
+                self.volume = value.as_f64().unwrap_or(100.0) / 100.0;
+                Some(Change::Volume)
+            }
+            "path" => {
+                self.path = value.as_str().map(str::to_owned);
+                Some(Change::Metadata)
+            }
+            _ => None,
+        }
+    }
+
+    /// The `xesam` and `mpris` metadata of the current track
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut metadata = HashMap::new();
+
+        let track_id = match self.active {
+            true => TRACK_ID,
+            false => NO_TRACK_ID,
+        };
+
+        metadata.insert(
+            "mpris:trackid".to_owned(),
+            OwnedValue::from(ObjectPath::from_static_str_unchecked(track_id)),
+        );
+
+        if !self.active {
+            return metadata;
+        }
+
+        if self.duration > 0 {
+            metadata.insert("mpris:length".to_owned(), OwnedValue::from(self.duration));
+        }
+
+        let title = self.metadata.title.clone().or(self
+            .path
+            .as_ref()
+            .and_then(|path| path.rsplit('/').next().map(str::to_owned)));
+
+        if let Some(title) = title {
+            metadata.insert("xesam:title".to_owned(), OwnedValue::from(Str::from(title)));
+        }
+
+        if let Some(album) = self.metadata.album.clone() {
+            metadata.insert("xesam:album".to_owned(), OwnedValue::from(Str::from(album)));
+        }
+
+        if let Some(subtitle) = self.metadata.subtitle.clone()
+            && let Ok(artist) = OwnedValue::try_from(zbus::zvariant::Value::from(vec![subtitle]))
+        {
+            metadata.insert("xesam:artist".to_owned(), artist);
+        }
+
+        if let Some(art_url) = self.metadata.art_url.clone() {
+            metadata.insert(
+                "mpris:artUrl".to_owned(),
+                OwnedValue::from(Str::from(art_url)),
+            );
+        }
+
+        metadata
+    }
+}
+
+struct MediaPlayer2 {
+    sender: WakingSender<MprisEvent>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl MediaPlayer2 {
+    fn raise(&self) {
+        self.sender.send(MprisEvent::Raise).ok();
+    }
+
+    fn quit(&self) {
+        self.sender.send(MprisEvent::Quit).ok();
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        APP_NAME.to_owned()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        APP_ID.to_owned()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        router::SCHEMES
+            .iter()
+            .map(|scheme| scheme.to_string())
+            .collect()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+struct MediaPlayer2Player {
+    sender: WakingSender<MprisEvent>,
+    state: State,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl MediaPlayer2Player {
+    fn next(&self) {
+        self.sender.send(MprisEvent::Next).ok();
+    }
+
+    fn previous(&self) {
+        self.sender.send(MprisEvent::Previous).ok();
+    }
+
+    fn pause(&self) {
+        self.sender.send(MprisEvent::Pause).ok();
+    }
+
+    fn play_pause(&self) {
+        self.sender.send(MprisEvent::PlayPause).ok();
+    }
+
+    fn stop(&self) {
+        self.sender.send(MprisEvent::Stop).ok();
+    }
+
+    fn play(&self) {
+        self.sender.send(MprisEvent::Play).ok();
+    }
+
+    fn seek(&self, offset: i64) {
+        self.sender.send(MprisEvent::Seek(offset)).ok();
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        // Requests for a track that is no longer playing must be ignored
+        if track_id.as_str() == TRACK_ID && (0..=self.state.duration).contains(&position) {
+            self.sender.send(MprisEvent::SetPosition(position)).ok();
+        }
+    }
+
+    fn open_uri(&self, uri: String) {
+        self.sender.send(MprisEvent::OpenUri(uri)).ok();
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        match (self.state.active, self.state.paused) {
+            (false, _) => "Stopped",
+            (true, true) => "Paused",
+            (true, false) => "Playing",
+        }
+        .to_owned()
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.state.metadata()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.state.volume
+    }
+
+    #[zbus(property)]
+    fn set_volume(&mut self, volume: f64) {
+        self.sender.send(MprisEvent::Volume(volume.max(0.0))).ok();
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> i64 {
+        self.state.position
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.state.active
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.state.active
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.state.active
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.state.active
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.state.active && self.state.duration > 0
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// MPRIS2 D-Bus service exposing playback state to desktop media widgets and media keys
+pub struct Mpris {
+    receiver: Receiver<MprisEvent>,
+    update_sender: Sender<MprisUpdate>,
+}
+
+impl Mpris {
+    pub fn new() -> Self {
+        let (sender, receiver) = waker::channel::<MprisEvent>();
+        let (update_sender, update_receiver) = unbounded::<MprisUpdate>();
+
+        thread::spawn(move || match Self::connect(sender) {
+            Ok(connection) => {
+                info!(target: "mpris", "MPRIS service started");
+
+                update_receiver.iter().for_each(|update| {
+                    if let Err(e) = Self::apply(&connection, update) {
+                        error!(target: "mpris", "Failed to update MPRIS state: {e}");
+                    }
+                });
+            }
+            Err(e) => error!(target: "mpris", "Failed to start MPRIS service: {e}"),
+        });
+
+        Self {
+            receiver,
+            update_sender,
+        }
+    }
+
+    fn connect(sender: WakingSender<MprisEvent>) -> zbus::Result<Connection> {
+        let root = MediaPlayer2 {
+            sender: sender.clone(),
+        };
+
+        let player = MediaPlayer2Player {
+            sender,
+            state: State {
+                volume: 1.0,
+                ..Default::default()
+            },
+        };
+
+        let connection = connection::Builder::session()?
+            .serve_at(OBJECT_PATH, root)?
+            .serve_at(OBJECT_PATH, player)?
+            .build()?;
+
+        // Another instance may already own the name, the spec allows a unique suffix
+        if connection.request_name(BUS_NAME).is_err() {
+            let name = format!("{BUS_NAME}.instance{}", process::id());
+            connection.request_name(name)?;
+        }
+
+        Ok(connection)
+    }
+
+    fn apply(connection: &Connection, update: MprisUpdate) -> zbus::Result<()> {
+        let iface_ref = connection
+            .object_server()
+            .interface::<_, MediaPlayer2Player>(OBJECT_PATH)?;
+        let mut iface = iface_ref.get_mut();
+        let emitter = iface_ref.signal_emitter();
+
+        futures::executor::block_on(async {
+            match update {
+                MprisUpdate::Property(name, value) => match iface.state.update(&name, &value) {
+                    Some(Change::PlaybackStatus) => {
+                        iface.playback_status_changed(emitter).await?;
+                    }
+                    Some(Change::Seeked(position)) => {
+                        MediaPlayer2Player::seeked(emitter, position).await?;
+                    }
+                    Some(Change::Duration) => {
+                        iface.metadata_changed(emitter).await?;
+                        iface.can_seek_changed(emitter).await?;
+                    }
+                    Some(Change::Volume) => {
+                        iface.volume_changed(emitter).await?;
+                    }
+                    Some(Change::Metadata) => {
+                        iface.metadata_changed(emitter).await?;
+                    }
+                    None => {}
+                },
+                MprisUpdate::Metadata(metadata) => {
+                    iface.state.metadata = metadata;
+                    iface.metadata_changed(emitter).await?;
+                }
+                MprisUpdate::Start => {
+                    iface.state.active = true;
+                    iface.state.position = 0;
+                    iface.playback_status_changed(emitter).await?;
+                    iface.can_play_changed(emitter).await?;
+                    iface.can_pause_changed(emitter).await?;
+                    iface.can_go_next_changed(emitter).await?;
+                    iface.can_go_previous_changed(emitter).await?;
+                }
+                MprisUpdate::Stop => {
+                    iface.state = State {
+                        volume: iface.state.volume,
+                        ..Default::default()
+                    };
+                    iface.playback_status_changed(emitter).await?;
+                    iface.metadata_changed(emitter).await?;
+                    iface.can_play_changed(emitter).await?;
+                    iface.can_pause_changed(emitter).await?;
+                    iface.can_seek_changed(emitter).await?;
+                    iface.can_go_next_changed(emitter).await?;
+                    iface.can_go_previous_changed(emitter).await?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    pub fn start(&self) {
+        self.update_sender.send(MprisUpdate::Start).ok();
+    }
+
+    pub fn stop(&self) {
+        self.update_sender.send(MprisUpdate::Stop).ok();
+    }
+
+    pub fn update_property(&self, property: &MpvProperty) {
+        if MPRIS_PROPERTIES.contains(&property.name())
+            && let Some(value) = property.data()
+        {
+            let update = MprisUpdate::Property(property.name().to_owned(), value.to_owned());
+            self.update_sender.send(update).ok();
+        }
+    }
+
+    pub fn update_presence(&self, args: &[String]) {
+        if let Some(metadata) = Metadata::from_presence(args) {
+            self.update_sender
+                .send(MprisUpdate::Metadata(metadata))
+                .ok();
+        }
+    }
+
+    pub fn events<F: FnMut(MprisEvent)>(&self, handler: F) {
+        self.receiver.try_iter().for_each(handler);
+    }
+}
+
+fn seconds_to_us(value: &Value) -> i64 {
+    (value.as_f64().unwrap_or_default() * 1_000_000.0) as i64
+}
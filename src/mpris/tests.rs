@@ -0,0 +1,183 @@
+use serde_json::{Value, json};
+use zbus::zvariant::{self, ObjectPath, OwnedValue, Str};
+
+use super::{Change, Metadata, NO_TRACK_ID, State, TRACK_ID};
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn string(value: &str) -> OwnedValue {
+    OwnedValue::from(Str::from(value.to_owned()))
+}
+
+fn track_id(path: &'static str) -> OwnedValue {
+    OwnedValue::from(ObjectPath::from_static_str_unchecked(path))
+}
+
+#[test]
+fn series_metadata() {
+    let metadata = Metadata::from_presence(&args(&[
+        "watching",
+        "series",
+        "The Show",
+        "1",
+        "2",
+        "Pilot",
+        "https://host/thumbnail.jpg",
+        "https://host/poster.jpg",
+    ]))
+    .unwrap();
+
+    assert_eq!(metadata.title.as_deref(), Some("Pilot"));
+    assert_eq!(metadata.subtitle.as_deref(), Some("S1E2"));
+    assert_eq!(metadata.album.as_deref(), Some("The Show"));
+    assert_eq!(
+        metadata.art_url.as_deref(),
+        Some("https://host/thumbnail.jpg")
+    );
+
+    // Without an episode title or thumbnail, the show's ones are used
+    let metadata = Metadata::from_presence(&args(&[
+        "watching",
+        "series",
+        "The Show",
+        "1",
+        "2",
+        "",
+        "",
+        "https://host/poster.jpg",
+    ]))
+    .unwrap();
+
+    assert_eq!(metadata.title.as_deref(), Some("The Show"));
+    assert_eq!(metadata.art_url.as_deref(), Some("https://host/poster.jpg"));
+}
+
+#[test]
+fn movie_metadata() {
+    let metadata = Metadata::from_presence(&args(&[
+        "watching",
+        "movie",
+        "The Movie",
+        "",
+        "",
+        "",
+        "",
+        "https://host/poster.jpg",
+    ]))
+    .unwrap();
+
+    assert_eq!(metadata.title.as_deref(), Some("The Movie"));
+    assert_eq!(metadata.subtitle, None);
+    assert_eq!(metadata.album, None);
+    assert_eq!(metadata.art_url.as_deref(), Some("https://host/poster.jpg"));
+}
+
+#[test]
+fn other_presence() {
+    assert!(Metadata::from_presence(&args(&["menu", "Board"])).is_none());
+    assert!(Metadata::from_presence(&[]).is_none());
+}
+
+#[test]
+fn property_changes() {
+    let mut state = State::default();
+    let mut update = |name, value: Value| state.update(name, &value);
+
+    assert_eq!(update("pause", json!(true)), Some(Change::PlaybackStatus));
+    assert_eq!(update("duration", json!(90.5)), Some(Change::Duration));
+    assert_eq!(update("volume", json!(50)), Some(Change::Volume));
+    assert_eq!(
+        update("path", json!("/videos/a.mkv")),
+        Some(Change::Metadata)
+    );
+    assert_eq!(update("speed", json!(2)), None);
+
+    assert!(state.paused);
+    assert_eq!(state.duration, 90_500_000);
+    assert_eq!(state.volume, 0.5);
+    assert_eq!(state.path.as_deref(), Some("/videos/a.mkv"));
+
+    // Missing values reset the state
+    let mut update = |name, value: Value| state.update(name, &value);
+    update("pause", Value::Null);
+    update("volume", Value::Null);
+    update("path", Value::Null);
+
+    assert!(!state.paused);
+    assert_eq!(state.volume, 1.0);
+    assert_eq!(state.path, None);
+}
+
+#[test]
+fn seeks() {
+    let mut state = State::default();
+
+    // Regular playback only updates the position
+    assert_eq!(state.update("time-pos", &json!(1.0)), None);
+    assert_eq!(state.update("time-pos", &json!(2.5)), None);
+    assert_eq!(state.position, 2_500_000);
+
+    // Jumps in both directions are reported
+    assert_eq!(
+        state.update("time-pos", &json!(60)),
+        Some(Change::Seeked(60_000_000))
+    );
+    assert_eq!(
+        state.update("time-pos", &json!(10.25)),
+        Some(Change::Seeked(10_250_000))
+    );
+}
+
+#[test]
+fn stopped_metadata() {
+    let state = State {
+        path: Some("/videos/a.mkv".to_owned()),
+        ..Default::default()
+    };
+    let metadata = state.metadata();
+
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata["mpris:trackid"], track_id(NO_TRACK_ID));
+}
+
+#[test]
+fn playing_metadata() {
+    let mut state = State {
+        active: true,
+        ..Default::default()
+    };
+    state.update("path", &json!("http://127.0.0.1:11470/hash/0/a.mkv"));
+
+    // The file name stands in for the title until the presence is known
+    let metadata = state.metadata();
+    assert_eq!(metadata["mpris:trackid"], track_id(TRACK_ID));
+    assert_eq!(metadata["xesam:title"], string("a.mkv"));
+    assert!(!metadata.contains_key("mpris:length"));
+
+    state.update("duration", &json!(2.5));
+    state.metadata = Metadata::from_presence(&args(&[
+        "watching",
+        "series",
+        "The Show",
+        "1",
+        "2",
+        "Pilot",
+        "https://host/thumbnail.jpg",
+    ]))
+    .unwrap();
+
+    let metadata = state.metadata();
+    assert_eq!(metadata["mpris:length"], OwnedValue::from(2_500_000i64));
+    assert_eq!(metadata["xesam:title"], string("Pilot"));
+    assert_eq!(metadata["xesam:album"], string("The Show"));
+    assert_eq!(
+        metadata["xesam:artist"],
+        OwnedValue::try_from(zvariant::Value::from(vec!["S1E2".to_owned()])).unwrap()
+    );
+    assert_eq!(
+        metadata["mpris:artUrl"],
+        string("https://host/thumbnail.jpg")
+    );
+}
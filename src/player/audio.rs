@@ -0,0 +1,44 @@
+use serde_json::Value;
+
+use crate::{config::AudioNormalization, player::languages::language_matches};
+
+// Label of the normalization filter in mpv's `af` chain
+pub const NORMALIZATION_LABEL: &str = "@normalize";
+// mpv properties set by the device settings
+pub const DEVICE_PROPERTIES: &[&str] = &["audio-spdif", "audio-channels"];
+
+/// Filter added to mpv's `af` chain for a normalization mode
+pub fn normalization_filter(normalization: AudioNormalization) -> Option<String> {
+    let filter = match normalization {
+        AudioNormalization::Off => return None,
+        AudioNormalization::Dynamic => "dynaudnorm=f=250:g=31",
+        AudioNormalization::Loudness => "loudnorm=I=-16:TP=-1.5:LRA=11",
+    };
+
+    Some(format!("{NORMALIZATION_LABEL}:lavfi=[{filter}]"))
+}
+
+/// Picks the audio track of the first preferred language with one,
+/// default tracks first, and returns its id and language
+pub fn select_track(track_list: &Value, languages: &[String]) -> Option<(i64, String)> {
+    let tracks = track_list
+        .as_array()?
+        .iter()
+        .filter(|track| track.get("type").and_then(Value::as_str) == Some("audio"))
+        .filter_map(|track| {
+            let id = track.get("id").and_then(Value::as_i64)?;
+            let lang = track.get("lang").and_then(Value::as_str)?;
+            let default = track.get("default").and_then(Value::as_bool) == Some(true);
+
+            Some((id, lang, default))
+        })
+        .collect::<Vec<_>>();
+
+    languages.iter().find_map(|language| {
+        tracks
+            .iter()
+            .filter(|(_, lang, _)| language_matches(lang, language))
+            .min_by_key(|(id, _, default)| (!default, *id))
+            .map(|(id, lang, _)| (*id, lang.to_string()))
+    })
+}
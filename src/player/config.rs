@@ -4,12 +4,17 @@ use std::{
 };
 
 use anyhow::{Context, Result};
+use tracing::info;
+
+use super::shaders::SHADER_PRESETS_FILE;
 
-const MPV_CONFIG_DIR: &str = "mpv-portable";
 const DEFAULT_MPV_CONF: &str = include_str!("../../data/mpv-configs/mpv.conf");
 const DEFAULT_INPUT_CONF: &str = include_str!("../../data/mpv-configs/input.conf");
 const THUMBFAST_LUA: &str = include_str!("../../data/mpv-configs/scripts/thumbfast.lua");
 const THUMBFAST_CONF: &str = include_str!("../../data/mpv-configs/script-opts/thumbfast.conf");
+const OVERLAY_MPV_CONF: &str =
+    "# Options of this profile, loaded after the mpv.conf shared by the profiles\n";
+const SHADER_PRESETS: &str = include_str!("../../data/mpv-configs/shader-presets.json");
 
 pub struct MpvConfig {
     pub config_dir: PathBuf,
@@ -17,8 +22,8 @@ pub struct MpvConfig {
 
 impl MpvConfig {
     /// Creates a new MPV config, initializing the portable config directory
-    pub fn new(data_dir: &Path) -> Result<Self> {
-        let config_dir = data_dir.join(MPV_CONFIG_DIR);
+    pub fn new(config_dir: &Path) -> Result<Self> {
+        let config_dir = config_dir.to_path_buf();
 
         // Create directory structure
         fs::create_dir_all(&config_dir).context("Failed to create MPV config directory")?;
@@ -37,20 +42,20 @@ impl MpvConfig {
         Ok(Self { config_dir })
     }
 
-    /// Installs default mpv.conf, input.conf, and thumbfast files if they don't exist
+    /// Installs default mpv.conf, input.conf, thumbfast and shader preset files if they don't exist
     fn install_default_configs(config_dir: &Path) -> Result<()> {
         let mpv_conf_path = config_dir.join("mpv.conf");
         if !mpv_conf_path.exists() {
             fs::write(&mpv_conf_path, DEFAULT_MPV_CONF)
                 .context("Failed to write default mpv.conf")?;
-            println!("✅ Created default mpv.conf");
+            info!(target: "player", "Created default mpv.conf");
         }
 
         let input_conf_path = config_dir.join("input.conf");
         if !input_conf_path.exists() {
             fs::write(&input_conf_path, DEFAULT_INPUT_CONF)
                 .context("Failed to write default input.conf")?;
-            println!("✅ Created default input.conf");
+            info!(target: "player", "Created default input.conf");
         }
 
         // Install thumbfast script
@@ -58,7 +63,7 @@ impl MpvConfig {
         if !thumbfast_lua_path.exists() {
             fs::write(&thumbfast_lua_path, THUMBFAST_LUA)
                 .context("Failed to write thumbfast.lua")?;
-            println!("✅ Installed thumbfast.lua script");
+            info!(target: "player", "Installed thumbfast.lua script");
         }
 
         // Install thumbfast config
@@ -66,7 +71,15 @@ impl MpvConfig {
         if !thumbfast_conf_path.exists() {
             fs::write(&thumbfast_conf_path, THUMBFAST_CONF)
                 .context("Failed to write thumbfast.conf")?;
-            println!("✅ Installed thumbfast.conf");
+            info!(target: "player", "Installed thumbfast.conf");
+        }
+
+        // Install shader presets
+        let shader_presets_path = config_dir.join(SHADER_PRESETS_FILE);
+        if !shader_presets_path.exists() {
+            fs::write(&shader_presets_path, SHADER_PRESETS)
+                .context("Failed to write shader presets")?;
+            info!(target: "player", "Installed {SHADER_PRESETS_FILE}");
         }
 
         Ok(())
@@ -77,3 +90,13 @@ impl MpvConfig {
         self.config_dir.to_string_lossy().to_string()
     }
 }
+
+/// Creates the mpv.conf of a profile, loaded on top of the shared config
+pub fn create_overlay(file: &Path) -> Result<()> {
+    if !file.exists() {
+        fs::write(file, OVERLAY_MPV_CONF).context("Failed to write profile mpv.conf")?;
+        info!(target: "player", "Created profile mpv.conf");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+// ISO 639-1 codes and their ISO 639-2 equivalents, bibliographic and terminology ones
+const LANGUAGE_CODES: &[(&str, &[&str])] = &[
+    ("ar", &["ara"]),
+    ("bg", &["bul"]),
+    ("cs", &["cze", "ces"]),
+    ("da", &["dan"]),
+    ("de", &["ger", "deu"]),
+    ("el", &["gre", "ell"]),
+    ("en", &["eng"]),
+    ("es", &["spa"]),
+    ("fi", &["fin"]),
+    ("fr", &["fre", "fra"]),
+    ("he", &["heb"]),
+    ("hi", &["hin"]),
+    ("hr", &["hrv"]),
+    ("hu", &["hun"]),
+    ("id", &["ind"]),
+    ("it", &["ita"]),
+    ("ja", &["jpn"]),
+    ("ko", &["kor"]),
+    ("ms", &["may", "msa"]),
+    ("nl", &["dut", "nld"]),
+    ("no", &["nor", "nob", "nno"]),
+    ("pl", &["pol"]),
+    ("pt", &["por"]),
+    ("ro", &["rum", "ron"]),
+    ("ru", &["rus"]),
+    ("sk", &["slo", "slk"]),
+    ("sr", &["srp"]),
+    ("sv", &["swe"]),
+    ("th", &["tha"]),
+    ("tr", &["tur"]),
+    ("uk", &["ukr"]),
+    ("vi", &["vie"]),
+    ("zh", &["chi", "zho"]),
+];
+
+/// Reduces a language tag (ex: `eng`, `en-US`, `pt_BR`) to its ISO 639-1 code when known
+pub fn normalize_language(language: &str) -> String {
+    let language = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    LANGUAGE_CODES
+        .iter()
+        .find(|(short, long)| *short == language || long.contains(&language.as_str()))
+        .map_or(language, |(short, _)| short.to_string())
+}
+
+pub fn language_matches(a: &str, b: &str) -> bool {
+    normalize_language(a) == normalize_language(b)
+}
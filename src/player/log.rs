@@ -0,0 +1,48 @@
+use std::{
+    ffi::CStr,
+    os::raw::{c_char, c_int, c_void},
+};
+
+use libmpv2::Mpv;
+use tracing::{Level, debug, enabled, error, info, trace, warn};
+
+unsafe extern "C" {
+    // Not wrapped by libmpv2, the symbol comes from the libmpv it links
+    fn mpv_request_log_messages(ctx: *mut c_void, min_level: *const c_char) -> c_int;
+}
+
+/// Lowest mpv level logged by the `mpv` target
+fn min_level() -> &'static CStr {
+    if enabled!(target: "mpv", Level::TRACE) {
+        c"debug"
+    } else if enabled!(target: "mpv", Level::DEBUG) {
+        c"v"
+    } else if enabled!(target: "mpv", Level::INFO) {
+        c"info"
+    } else if enabled!(target: "mpv", Level::WARN) {
+        c"warn"
+    } else if enabled!(target: "mpv", Level::ERROR) {
+        c"error"
+    } else {
+        c"no"
+    }
+}
+
+/// Routes the messages of mpv to the `mpv` target, called again when the levels change
+pub fn request_messages(mpv: &Mpv) {
+    unsafe {
+        mpv_request_log_messages(mpv.ctx.as_ptr() as *mut c_void, min_level().as_ptr());
+    }
+}
+
+pub fn forward(prefix: &str, level: &str, text: &str) {
+    let text = text.trim_end();
+
+    match level {
+        "fatal" | "error" => error!(target: "mpv", "[{prefix}] {text}"),
+        "warn" => warn!(target: "mpv", "[{prefix}] {text}"),
+        "info" | "status" => info!(target: "mpv", "[{prefix}] {text}"),
+        "v" => debug!(target: "mpv", "[{prefix}] {text}"),
+        _ => trace!(target: "mpv", "[{prefix}] {text}"),
+    }
+}
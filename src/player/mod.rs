@@ -1,53 +1,79 @@
+mod audio;
 mod config;
-mod constants;
+mod languages;
+mod log;
+mod resume;
+mod shaders;
+mod subtitles;
+#[cfg(test)]
+mod tests;
+mod utils;
 
-use std::{env, ffi::CString, os::raw::c_void, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::CString,
+    iter,
+    os::raw::c_void,
+    rc::Rc,
+};
 
-use crate::config::PlayerConfig;
+use crate::{
+    config::{AudioConfig, AudioDeviceSettings, PlayerConfig, SubtitlesConfig},
+    gpu::{self, GpuProbe},
+    shared::waker::{self, WakingSender},
+};
 use config::MpvConfig;
-use constants::{BOOL_PROPERTIES, FLOAT_PROPERTIES, STRING_PROPERTIES};
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::Receiver;
 use glutin::{display::Display, prelude::GlDisplay};
 use itertools::Itertools;
 use libc::{LC_NUMERIC, setlocale};
 use libmpv2::{
     Format, Mpv,
     events::{Event, EventContext, PropertyData},
+    mpv_node::MpvNode,
     render::{OpenGLInitParams, RenderContext, RenderParam, RenderParamApiType},
 };
+use resume::{ResumeEntry, ResumeStore};
 use rust_i18n::t;
 use serde::{Deserialize, Serialize, Serializer, ser::SerializeStruct};
-use serde_json::{Number, Value};
-use tracing::error;
+use serde_json::{Number, Value, json};
+use shaders::{ShaderPreset, ShaderPresets};
+use subtitles::{STYLE_PROPERTIES, SubtitleSelection, SubtitleTrack, Subtitles};
+use tracing::{error, info};
 
 pub type GLContext = Rc<Display>;
 
+// Properties the player observes itself to keep the resume store up to date
+const RESUME_PROPERTIES: &[&str] = &["path", "duration", "time-pos"];
+// Properties the player observes itself to select shader presets automatically
+const SHADER_PROPERTIES: &[&str] = &["height"];
+// Properties the player observes itself to select tracks and style subtitles
+const TRACK_PROPERTIES: &[&str] = &["track-list"];
+const END_FILE_REASON_EOF: u32 = 0;
+// Id of the OSD overlay of the diagnostics, other ids are free for scripts
+const OVERLAY_ID: u32 = 63;
+
+/// Type of an mpv property, discovered by reading it as a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MpvPropertyType {
+    Flag,
+    Int64,
+    Double,
+    String,
+    Node,
+    Unknown,
+}
+
 #[derive(Debug)]
 pub enum MpvPropertyValue {
-    Float(f64),
-    Bool(bool),
+    Flag(bool),
+    Int64(i64),
+    Double(f64),
     String(String),
 }
 
-impl Serialize for MpvPropertyValue {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match self {
-            MpvPropertyValue::Float(value) => serializer.serialize_f64(*value),
-            MpvPropertyValue::Bool(value) => serializer.serialize_bool(*value),
-            MpvPropertyValue::String(value) => {
-                if let Ok(json_value) = serde_json::from_str::<Value>(value) {
-                    json_value.serialize(serializer)
-                } else {
-                    serializer.serialize_str(value)
-                }
-            }
-        }
-    }
-}
-
 #[derive(Deserialize, Debug)]
 pub struct MpvProperty(pub String, pub Option<Value>);
 
@@ -56,28 +82,65 @@ impl MpvProperty {
         self.0.as_ref()
     }
 
-    pub fn value(&self) -> Result<MpvPropertyValue, &'static str> {
-        if let Some(value) = self.1.clone() {
-            if FLOAT_PROPERTIES.contains(&self.name()) {
-                return serde_json::from_value::<f64>(value)
-                    .map(MpvPropertyValue::Float)
-                    .map_err(|_| "Failed to get f64 from Value");
-            }
+    pub fn data(&self) -> Option<&Value> {
+        self.1.as_ref()
+    }
 
-            if BOOL_PROPERTIES.contains(&self.name()) {
-                return serde_json::from_value::<bool>(value)
-                    .map(MpvPropertyValue::Bool)
-                    .map_err(|_| "Failed to get bool from Value");
-            }
+    /// Converts the JSON value into the given mpv type
+    pub fn value(&self, r#type: MpvPropertyType) -> Result<MpvPropertyValue, String> {
+        let value = self
+            .data()
+            .ok_or(format!("Missing value for property {}", self.name()))?;
+
+        let invalid = || {
+            format!(
+                "Invalid value for {:?} property {}: {value}",
+                r#type,
+                self.name()
+            )
+        };
 
-            if STRING_PROPERTIES.contains(&self.name()) {
-                return serde_json::from_value::<String>(value)
-                    .map(MpvPropertyValue::String)
-                    .map_err(|_| "Failed to get String from Value");
+        match (r#type, value) {
+            (MpvPropertyType::Flag, Value::Bool(value)) => Ok(MpvPropertyValue::Flag(*value)),
+            (MpvPropertyType::Flag, Value::String(value)) => match value.as_str() {
+                "yes" => Ok(MpvPropertyValue::Flag(true)),
+                "no" => Ok(MpvPropertyValue::Flag(false)),
+                _ => Err(invalid()),
+            },
+            (MpvPropertyType::Int64, Value::Number(value)) => value
+                .as_i64()
+                .or(value
+                    .as_f64()
+                    .filter(|value| value.fract() == 0.0)
+                    .map(|value| value as i64))
+                .map(MpvPropertyValue::Int64)
+                .ok_or_else(invalid),
+            (MpvPropertyType::Int64, Value::String(value)) => value
+                .parse::<i64>()
+                .map(MpvPropertyValue::Int64)
+                .map_err(|_| invalid()),
+            (MpvPropertyType::Double, Value::Number(value)) => value
+                .as_f64()
+                .map(MpvPropertyValue::Double)
+                .ok_or_else(invalid),
+            (MpvPropertyType::Double, Value::String(value)) => value
+                .parse::<f64>()
+                .map(MpvPropertyValue::Double)
+                .map_err(|_| invalid()),
+            (MpvPropertyType::Flag | MpvPropertyType::Int64 | MpvPropertyType::Double, _) => {
+                Err(invalid())
             }
+            // Unknown types are converted based on the JSON value itself
+            (MpvPropertyType::Unknown, Value::Bool(value)) => Ok(MpvPropertyValue::Flag(*value)),
+            (MpvPropertyType::Unknown, Value::Number(value)) => Ok(value
+                .as_i64()
+                .map(MpvPropertyValue::Int64)
+                .unwrap_or(MpvPropertyValue::Double(value.as_f64().unwrap_or_default()))),
+            // Strings, lists and maps are handed to mpv's option parser
+            (_, value) => Ok(MpvPropertyValue::String(utils::json_to_option_string(
+                value,
+            ))),
         }
-
-        Err("Failed to get value of MpvProperty")
     }
 }
 
@@ -89,8 +152,8 @@ impl Serialize for MpvProperty {
         let mut state = serializer.serialize_struct("MpvProperty", 2)?;
         state.serialize_field("name", self.name())?;
 
-        if let Ok(value) = self.value() {
-            state.serialize_field("data", &value)?;
+        if let Some(value) = self.data() {
+            state.serialize_field("data", value)?;
         }
 
         state.end()
@@ -103,6 +166,14 @@ pub enum PlayerEvent {
     Stop(Option<String>),
     Update,
     PropertyChange(MpvProperty),
+    ShadersChanged {
+        preset: Option<String>,
+        manual: bool,
+    },
+    SubtitleLoaded {
+        source: String,
+        error: Option<String>,
+    },
 }
 
 impl<'a> TryFrom<Event<'a>> for PlayerEvent {
@@ -121,21 +192,22 @@ impl<'a> TryFrom<Event<'a>> for PlayerEvent {
                 Ok(PlayerEvent::Stop(error.map(String::from)))
             }
             Event::PropertyChange { name, change, .. } => {
-                let property = match change {
-                    PropertyData::Double(value) => MpvProperty(
-                        name.to_owned(),
-                        Some(Value::Number(Number::from_f64(value).unwrap())),
-                    ),
-                    PropertyData::Flag(value) => {
-                        MpvProperty(name.to_owned(), Some(Value::Bool(value)))
-                    }
-                    PropertyData::Str(value) => {
-                        MpvProperty(name.to_owned(), Some(Value::String(value.to_owned())))
+                let value = match change {
+                    PropertyData::Node(node) => utils::node_to_json(node),
+                    PropertyData::Double(value) => Number::from_f64(value)
+                        .map(Value::Number)
+                        .unwrap_or(Value::Null),
+                    PropertyData::Int64(value) => Value::from(value),
+                    PropertyData::Flag(value) => Value::Bool(value),
+                    PropertyData::Str(value) | PropertyData::OsdStr(value) => {
+                        Value::String(value.to_owned())
                     }
-                    _ => return Err("Property not supported"),
                 };
 
-                Ok(PlayerEvent::PropertyChange(property))
+                Ok(PlayerEvent::PropertyChange(MpvProperty(
+                    name.to_owned(),
+                    Some(value),
+                )))
             }
             _ => Err("Event not supported"),
         }
@@ -146,8 +218,21 @@ pub struct Player {
     mpv: Mpv,
     event_context: EventContext,
     render_context: Option<RenderContext>,
-    sender: Sender<PlayerEvent>,
+    sender: WakingSender<PlayerEvent>,
     receiver: Receiver<PlayerEvent>,
+    property_types: RefCell<HashMap<String, MpvPropertyType>>,
+    observed_properties: RefCell<HashSet<String>>,
+    resume: Option<ResumeStore>,
+    auto_seek: bool,
+    resume_pending: bool,
+    shader_presets: RefCell<ShaderPresets>,
+    shaders_auto: bool,
+    subtitles: Subtitles,
+    audio: AudioConfig,
+    /// Values of the device properties from mpv.conf, restored when the settings are unset
+    audio_base: Vec<(&'static str, Value)>,
+    /// Whether the tracks of the current file still have to be selected
+    tracks_pending: bool,
 }
 
 impl Player {
@@ -163,30 +248,45 @@ impl Player {
 
         // Initialize MPV config (creates config directory and installs defaults)
         let mpv_config =
-            MpvConfig::new(&player_config.data_dir).expect("Failed to initialize MPV config");
+            MpvConfig::new(&player_config.mpv_config_dir).expect("Failed to initialize MPV config");
 
-        println!(
-            "🎬 MPV Enhanced - Config loaded from: {}",
+        info!(
+            target: "player",
+            "Config loaded from {}",
             mpv_config.config_dir_str()
         );
 
-        let log = env::var("RUST_LOG");
-        let msg_level = match log {
-            Ok(scope) => &format!("all={},cplayer=v,lua=v", scope.as_str()),
-            _ => "cplayer=v,lua=v",
-        };
-
         let config_dir = mpv_config.config_dir_str();
+        let headless = player_config.headless;
+
+        let overlay = player_config.mpv_overlay.as_deref().and_then(|file| {
+            config::create_overlay(file)
+                .map_err(|e| error!(target: "player", "{e:#}"))
+                .ok()?;
+            Some(file.to_string_lossy().into_owned())
+        });
 
         let mpv = Mpv::with_initializer(move |init| {
+            if headless {
+                init.set_property("vo", "null")?;
+                init.set_property("ao", "null")?;
+                init.set_property("config", "no")?;
+                init.set_property("load-scripts", "no")?;
+                return Ok(());
+            }
+
             init.set_property("vo", "libmpv")?;
             init.set_property("video-timing-offset", "0")?;
-            init.set_property("terminal", "yes")?;
-            init.set_property("msg-level", msg_level)?;
+            // The messages are logged through `log::forward` instead
+            init.set_property("terminal", "no")?;
             // Enable config file loading from custom directory
             init.set_property("config-dir", config_dir.as_str())?;
             init.set_property("config", "yes")?;
             init.set_property("load-scripts", "yes")?;
+            // The profile's options override the shared config
+            if let Some(overlay) = &overlay {
+                init.set_property("include", overlay.as_str())?;
+            }
             // Enable input.conf processing for keyboard shortcuts
             init.set_property("input-default-bindings", "yes")?;
             init.set_property("input-vo-keyboard", "yes")?;
@@ -194,20 +294,107 @@ impl Player {
         })
         .expect("Failed to create mpv");
 
-        let event_context = EventContext::new(mpv.ctx);
+        let mut event_context = EventContext::new(mpv.ctx);
         event_context
             .disable_deprecated_events()
             .expect("Failed to disable deprecated events");
 
-        let (sender, receiver) = unbounded::<PlayerEvent>();
+        // mpv events are only read once the main loop is woken up
+        event_context.set_wakeup_callback(waker::wake);
+
+        log::request_messages(&mpv);
+
+        let (sender, receiver) = waker::channel::<PlayerEvent>();
 
-        Self {
+        let shader_presets = ShaderPresets::new(&mpv_config.config_dir);
+
+        let resume = player_config
+            .resume
+            .enabled
+            .then(|| ResumeStore::new(&player_config.data_dir));
+
+        let subtitles = Subtitles::new(&player_config.data_dir, player_config.subtitles);
+
+        let mut player = Self {
             mpv,
             event_context,
             render_context: None,
             sender,
             receiver,
+            property_types: RefCell::new(HashMap::new()),
+            observed_properties: RefCell::new(HashSet::new()),
+            resume,
+            auto_seek: player_config.resume.auto_seek,
+            resume_pending: false,
+            shader_presets: RefCell::new(shader_presets),
+            shaders_auto: player_config.shaders.auto,
+            subtitles,
+            audio: player_config.audio,
+            audio_base: vec![],
+            tracks_pending: false,
+        };
+
+        if player.resume.is_some() {
+            RESUME_PROPERTIES
+                .iter()
+                .for_each(|name| player.observe_property(name.to_string()));
+        }
+
+        if player.shaders_auto {
+            SHADER_PROPERTIES
+                .iter()
+                .for_each(|name| player.observe_property(name.to_string()));
+        }
+
+        // The decoding settings of the GPU are overridden by the mpv properties of the config
+        if !player_config.headless {
+            gpu::mpv_properties(&player_config.gpu, &GpuProbe::new())
+                .into_iter()
+                .for_each(|(name, value)| {
+                    info!(target: "player", "Setting {name} to {value} for the GPU");
+                    if let Err(e) = player.mpv.set_property(name, value.as_str()) {
+                        error!(target: "player", "Failed to set {name}: {e}");
+                    }
+                });
+        }
+
+        // Properties of the app config override mpv.conf
+        player_config.mpv.into_iter().for_each(|(name, value)| {
+            if let Err(e) = player.set_property(MpvProperty(name, Some(value))) {
+                error!(target: "player", "{e}");
+            }
+        });
+
+        // Styles fall back to the values of mpv.conf and of the app config
+        player.subtitles.base_style = STYLE_PROPERTIES
+            .iter()
+            .filter_map(|name| {
+                let value = player.get_property(name.to_string()).ok()?.1?;
+                Some((*name, value))
+            })
+            .collect();
+
+        player.audio_base = iter::once(&"audio-device")
+            .chain(audio::DEVICE_PROPERTIES)
+            .filter_map(|name| {
+                let value = player.get_property(name.to_string()).ok()?.1?;
+                Some((*name, value))
+            })
+            .collect();
+        player.apply_audio_config();
+
+        TRACK_PROPERTIES
+            .iter()
+            .for_each(|name| player.observe_property(name.to_string()));
+
+        // Restores the last preset picked by the user
+        if let Some(preset) = player_config.shaders.preset.as_deref()
+            && let Err(e) = player.set_shader_preset(Some(preset))
+        {
+            error!(target: "player", "{e}");
         }
+
+        player
     }
 
     pub fn setup(&mut self, context: GLContext) {
@@ -243,9 +430,18 @@ impl Player {
     }
 
     pub fn render(&self, fbo: u32, width: i32, height: i32) {
+        self.render_to(fbo, width, height, false);
+    }
+
+    /// Renders straight to the window of the current surface, upside down compared to an FBO
+    pub fn render_window(&self, width: i32, height: i32) {
+        self.render_to(0, width, height, true);
+    }
+
+    fn render_to(&self, fbo: u32, width: i32, height: i32, flip: bool) {
         if let Some(render_context) = self.render_context.as_ref() {
             render_context
-                .render::<GLContext>(fbo as i32, width, height, false)
+                .render::<GLContext>(fbo as i32, width, height, flip)
                 .expect("Failed to draw on glutin window");
         }
     }
@@ -257,70 +453,594 @@ impl Player {
     }
 
     pub fn events<T: FnMut(PlayerEvent)>(&mut self, handler: T) {
-        self.receiver.try_iter().for_each(handler);
-
         let sender = self.sender.clone();
-        if let Some(result) = self.event_context.wait_event(0.0) {
+
+        // The wakeup callback is called once for all the queued events
+        while let Some(result) = self.event_context.wait_event(0.0) {
             match result {
+                Ok(Event::LogMessage {
+                    prefix,
+                    level,
+                    text,
+                    ..
+                }) => log::forward(prefix, level, text),
                 Ok(event) => {
+                    let ended = matches!(event, Event::EndFile(END_FILE_REASON_EOF));
+
                     if let Ok(player_event) = PlayerEvent::try_from(event) {
+                        self.update_resume(&player_event, ended);
+                        self.update_shaders(&player_event);
+                        self.update_tracks(&player_event);
                         sender.send(player_event).ok();
                     }
                 }
-                Err(e) => {
-                    eprintln!("Mpv error: {e}")
-                }
+                Err(e) => error!(target: "player", "{e}"),
             }
+        }
+
+        self.add_loaded_subtitles();
+        self.receiver.try_iter().for_each(handler);
+    }
+
+    /// Requests the mpv messages of the current log level, called when the levels change
+    pub fn update_log_level(&self) {
+        log::request_messages(&self.mpv);
+    }
+
+    /// Versions of mpv and of the FFmpeg it uses
+    pub fn versions(&self) -> Value {
+        mpv_versions(&self.mpv)
+    }
+
+    /// Shows text over the video through an OSD overlay of mpv, `None` hides it
+    pub fn set_overlay(&self, text: Option<&str>) {
+        let id = OVERLAY_ID.to_string();
+        let args = match text {
+            Some(text) => vec![id, "ass-events".to_owned(), text.to_owned()],
+            None => vec![id, "none".to_owned(), String::new()],
         };
+
+        self.command("osd-overlay".to_owned(), args);
     }
 
     pub fn command(&self, name: String, args: Vec<String>) {
+        if let Err(e) = self.try_command(name, args) {
+            error!(target: "player", "{e}");
+        }
+    }
+
+    /// Runs a command and returns the mpv error to the caller instead of logging it
+    pub fn try_command(&self, name: String, args: Vec<String>) -> Result<(), String> {
         let args = args.iter().map(String::as_ref).collect_vec();
-        if let Err(e) = self.mpv.command(&name, &args) {
-            error!("Failed to use command {name} with args {:?}: {e}", args);
+        self.mpv
+            .command(&name, &args)
+            .map_err(|e| format!("Failed to use command {name} with args {:?}: {e}", args))
+    }
+
+    pub fn get_property(&self, name: String) -> Result<MpvProperty, String> {
+        self.mpv
+            .get_property::<MpvNode>(&name)
+            .map(|node| MpvProperty(name.clone(), Some(utils::node_to_json(&node))))
+            .map_err(|e| format!("Failed to get property {name}: {e}"))
+    }
+
+    /// Discovers the type of a property by reading it as a node, the first known type is cached.
+    /// Choice properties change type with their value (ex: `sid` is a number or `no`), so the
+    /// cached type is only a hint, see `set_property`
+    pub fn property_type(&self, name: &str) -> MpvPropertyType {
+        if let Some(r#type) = self.property_types.borrow().get(name) {
+            return *r#type;
+        }
+
+        let r#type = self
+            .mpv
+            .get_property::<MpvNode>(name)
+            .map(|node| utils::node_type(&node))
+            .unwrap_or(MpvPropertyType::Unknown);
+
+        // Unavailable properties (ex: `duration` before a file is loaded) are retried next time
+        if r#type != MpvPropertyType::Unknown {
+            self.property_types
+                .borrow_mut()
+                .insert(name.to_owned(), r#type);
         }
+
+        r#type
     }
 
+    /// Observes a property once, later calls for the same property are ignored
+    /// so that the web UI and control clients don't receive duplicated changes
     pub fn observe_property(&self, name: String) {
-        let format = match name.as_str() {
-            name if FLOAT_PROPERTIES.contains(&name) => Some(Format::Double),
-            name if BOOL_PROPERTIES.contains(&name) => Some(Format::Flag),
-            name if STRING_PROPERTIES.contains(&name) => Some(Format::String),
-            _ => None,
-        };
+        if self.observed_properties.borrow().contains(&name) {
+            return;
+        }
 
-        if let Some(format) = format
-            && let Err(e) = self.event_context.observe_property(&name, format, 0)
-        {
-            error!("Failed to observe property {name}: {e}");
+        match self.event_context.observe_property(&name, Format::Node, 0) {
+            Ok(_) => {
+                self.observed_properties.borrow_mut().insert(name);
+            }
+            Err(e) => error!(target: "player", "Failed to observe property {name}: {e}"),
         }
     }
 
-    pub fn set_property(&self, property: MpvProperty) {
-        match property.name() {
-            name if FLOAT_PROPERTIES.contains(&name) => {
-                if let Ok(MpvPropertyValue::Float(value)) = property.value()
-                    && let Err(e) = self.mpv.set_property(name, value)
-                {
-                    error!("Failed to set property {name}: {e}");
+    pub fn set_property(&self, property: MpvProperty) -> Result<(), String> {
+        let name = property.name();
+
+        // Values that don't match the cached type go through mpv's option parser,
+        // which knows the choices of the property
+        let value = match (property.value(self.property_type(name)), property.data()) {
+            (Err(_), Some(data)) => MpvPropertyValue::String(utils::json_to_option_string(data)),
+            (value, _) => value?,
+        };
+
+        let result = match value {
+            MpvPropertyValue::Flag(value) => self.mpv.set_property(name, value),
+            MpvPropertyValue::Int64(value) => self.mpv.set_property(name, value),
+            MpvPropertyValue::Double(value) => self.mpv.set_property(name, value),
+            MpvPropertyValue::String(value) => self.mpv.set_property(name, value.as_str()),
+        };
+
+        result.map_err(|e| format!("Failed to set property {name}: {e}"))
+    }
+}
+
+impl Player {
+    /// Returns the saved position of a stream, or of the current one if no path is given
+    pub fn resume_position(&self, path: Option<&str>) -> Option<ResumeEntry> {
+        self.resume.as_ref().and_then(|resume| match path {
+            Some(path) => resume.get(path),
+            None => resume.current(),
+        })
+    }
+
+    pub fn set_auto_seek(&mut self, auto_seek: bool) {
+        self.auto_seek = auto_seek;
+    }
+
+    fn update_resume(&mut self, event: &PlayerEvent, ended: bool) {
+        let Some(resume) = self.resume.as_mut() else {
+            return;
+        };
+
+        match event {
+            PlayerEvent::Start => {
+                self.resume_pending = self.auto_seek;
+            }
+            PlayerEvent::Stop(_) => {
+                self.resume_pending = false;
+                resume.stop(ended);
+            }
+            PlayerEvent::PropertyChange(property) => {
+                let data = property.data();
+
+                match property.name() {
+                    "path" => resume.set_path(data.and_then(Value::as_str)),
+                    "duration" => {
+                        let duration = data.and_then(Value::as_f64).unwrap_or_default();
+                        resume.set_duration(duration);
+
+                        // The duration is known once the file is loaded, which is when seeking works
+                        if self.resume_pending && duration > 0.0 {
+                            self.resume_pending = false;
+
+                            if let Some(entry) = resume.current() {
+                                self.command(
+                                    "seek".to_owned(),
+                                    vec![entry.position.to_string(), "absolute".to_owned()],
+                                );
+                            }
+                        }
+                    }
+                    "time-pos" => {
+                        if let Some(position) = data.and_then(Value::as_f64) {
+                            resume.set_position(position);
+                        }
+                    }
+                    _ => {}
                 }
             }
-            name if BOOL_PROPERTIES.contains(&name) => {
-                if let Ok(MpvPropertyValue::Bool(value)) = property.value()
-                    && let Err(e) = self.mpv.set_property(name, value)
-                {
-                    error!("Failed to set property {name}: {e}");
+            _ => {}
+        }
+    }
+}
+
+impl Player {
+    /// Returns the shader presets, for the web UI to show a picker
+    pub fn shader_presets(&self) -> Value {
+        let shader_presets = self.shader_presets.borrow();
+
+        json!({
+            "active": shader_presets.active,
+            "auto": self.shaders_auto,
+            "content": shader_presets.content,
+            "presets": shader_presets
+                .all()
+                .iter()
+                .map(|preset| json!({
+                    "name": preset.name,
+                    "label": preset.label,
+                    "valid": preset.valid(),
+                    "missing": preset.missing,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Activates a preset picked by the user, `None` clears the shaders
+    pub fn set_shader_preset(&self, name: Option<&str>) -> Result<(), String> {
+        let preset = match name {
+            Some(name) => {
+                let shader_presets = self.shader_presets.borrow();
+                let preset = shader_presets
+                    .get(name)
+                    .ok_or(format!("Unknown shader preset {name}"))?;
+
+                if !preset.valid() {
+                    return Err(format!(
+                        "Shader preset {name} is missing files: {}",
+                        preset.missing.join(", ")
+                    ));
                 }
+
+                Some(preset.clone())
+            }
+            None => None,
+        };
+
+        let mut shader_presets = self.shader_presets.borrow_mut();
+        shader_presets.manual = true;
+        shader_presets.preferred = name.map(str::to_owned);
+        drop(shader_presets);
+
+        self.apply_shader_preset(preset.as_ref(), true)
+    }
+
+    /// Enables the selection of presets from the rules matching the current video
+    pub fn set_shaders_auto(&mut self, auto: bool) {
+        self.shaders_auto = auto;
+
+        if auto {
+            SHADER_PROPERTIES
+                .iter()
+                .for_each(|name| self.observe_property(name.to_string()));
+
+            self.auto_select_shaders();
+        }
+    }
+
+    /// Sets the content tag of the current media (ex: `anime`) used by auto selection
+    pub fn set_shader_content(&self, content: Option<String>) {
+        self.shader_presets.borrow_mut().content = content;
+        self.auto_select_shaders();
+    }
+
+    fn apply_shader_preset(
+        &self,
+        preset: Option<&ShaderPreset>,
+        manual: bool,
+    ) -> Result<(), String> {
+        self.try_command(
+            "change-list".to_owned(),
+            vec!["glsl-shaders".to_owned(), "clr".to_owned(), "".to_owned()],
+        )?;
+
+        if let Some(preset) = preset {
+            for shader in preset.shaders.iter() {
+                self.try_command(
+                    "change-list".to_owned(),
+                    vec![
+                        "glsl-shaders".to_owned(),
+                        "append".to_owned(),
+                        shader.to_owned(),
+                    ],
+                )?;
+            }
+        }
+
+        let label = preset.map_or(t!("shaders_cleared").to_string(), |preset| {
+            preset.label.to_owned()
+        });
+        self.command("show-text".to_owned(), vec![label]);
+
+        let name = preset.map(|preset| preset.name.to_owned());
+        self.shader_presets.borrow_mut().active = name.clone();
+
+        self.sender
+            .send(PlayerEvent::ShadersChanged {
+                preset: name,
+                manual,
+            })
+            .ok();
+
+        Ok(())
+    }
+
+    fn auto_select_shaders(&self) {
+        if !self.shaders_auto || self.shader_presets.borrow().manual {
+            return;
+        }
+
+        let selection = {
+            let shader_presets = self.shader_presets.borrow();
+
+            // Videos no rule matches keep the preset picked by the user
+            let preset = match shader_presets.auto_select() {
+                Some(preset) => Some(preset),
+                None if shader_presets.height.is_some() => shader_presets
+                    .preferred
+                    .as_deref()
+                    .and_then(|name| shader_presets.get(name)),
+                None => return,
+            };
+
+            (preset.map(|preset| &preset.name) != shader_presets.active.as_ref())
+                .then(|| preset.cloned())
+        };
+
+        if let Some(preset) = selection
+            && let Err(e) = self.apply_shader_preset(preset.as_ref(), false)
+        {
+            error!(target: "player", "{e}");
+        }
+    }
+
+    fn update_shaders(&self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::Start => {
+                self.shader_presets.borrow_mut().manual = false;
+            }
+            PlayerEvent::Stop(_) => {
+                let mut shader_presets = self.shader_presets.borrow_mut();
+                shader_presets.height = None;
+                shader_presets.content = None;
+            }
+            PlayerEvent::PropertyChange(property) if property.name() == "height" => {
+                let height = property.data().and_then(Value::as_i64);
+                self.shader_presets.borrow_mut().height = height;
+                self.auto_select_shaders();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Player {
+    /// Subtitle tracks of the current file
+    pub fn subtitle_tracks(&self) -> &[SubtitleTrack] {
+        &self.subtitles.tracks
+    }
+
+    /// Loads a local or HTTP subtitle file, `PlayerEvent::SubtitleLoaded` reports the result
+    pub fn load_subtitle(&self, source: String, lang: Option<String>, title: Option<String>) {
+        self.subtitles.load(source, lang, title);
+    }
+
+    pub fn set_subtitles_config(&mut self, config: SubtitlesConfig) {
+        self.subtitles.config = config;
+        self.subtitles.styled_language = None;
+        self.apply_subtitle_style();
+    }
+
+    fn add_loaded_subtitles(&self) {
+        for load in self.subtitles.loaded() {
+            let result = load
+                .result
+                .map_err(|e| format!("{e:#}"))
+                .and_then(|loaded| {
+                    let mut args = vec![
+                        loaded.path.to_string_lossy().to_string(),
+                        "select".to_owned(),
+                        load.title.clone().unwrap_or_default(),
+                    ];
+                    args.extend(load.lang.clone());
+
+                    self.try_command("sub-add".to_owned(), args)?;
+
+                    info!(
+                        target: "player",
+                        "Loaded subtitles {} as {:?}, decoded from {}",
+                        load.source, loaded.format, loaded.encoding
+                    );
+
+                    Ok(())
+                });
+
+            if let Err(e) = &result {
+                error!(target: "player", "{e}");
             }
-            name if STRING_PROPERTIES.contains(&name) => {
-                if let Ok(MpvPropertyValue::String(value)) = property.value()
-                    && let Err(e) = self.mpv.set_property(name, value)
-                {
-                    error!("Failed to set property {name}: {e}");
+
+            self.sender
+                .send(PlayerEvent::SubtitleLoaded {
+                    source: load.source,
+                    error: result.err(),
+                })
+                .ok();
+        }
+    }
+
+    fn update_tracks(&mut self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::Start => {
+                self.tracks_pending = true;
+            }
+            PlayerEvent::Stop(_) => {
+                self.tracks_pending = false;
+                self.subtitles.update_tracks(&Value::Null);
+            }
+            PlayerEvent::PropertyChange(property) if property.name() == "track-list" => {
+                let track_list = property.data().unwrap_or(&Value::Null);
+                self.subtitles.update_tracks(track_list);
+
+                // The embedded tracks are all known once the first ones are listed
+                let has_tracks = track_list
+                    .as_array()
+                    .is_some_and(|tracks| !tracks.is_empty());
+                if self.tracks_pending && has_tracks {
+                    self.tracks_pending = false;
+
+                    // Subtitles depend on the language of the audio track picked first
+                    if let Some(language) = self.select_audio(track_list) {
+                        self.subtitles.audio_language = Some(language);
+                    }
+
+                    self.select_subtitles();
                 }
+
+                self.apply_subtitle_style();
             }
-            name => error!("Failed to set property {name}: Unsupported"),
+            _ => {}
+        }
+    }
+
+    fn select_subtitles(&self) {
+        let result = match self.subtitles.select() {
+            Some(SubtitleSelection::Track(id)) => self.mpv.set_property("sid", id),
+            Some(SubtitleSelection::Off) => self.mpv.set_property("sid", "no"),
+            None => return,
         };
+
+        if let Err(e) = result {
+            error!(target: "player", "Failed to select subtitles: {e}");
+        }
+    }
+
+    /// Applies the style of the language of the selected subtitles when it changes
+    fn apply_subtitle_style(&mut self) {
+        let Some(track) = self.subtitles.selected() else {
+            return;
+        };
+
+        let language = track.lang.clone();
+        if self.subtitles.styled_language.as_ref() == Some(&language) {
+            return;
+        }
+
+        let style = self.subtitles.style(language.as_deref());
+        for (name, value) in subtitles::style_properties(style, &self.subtitles.base_style) {
+            if let Err(e) = self.set_property(MpvProperty(name.to_owned(), Some(value))) {
+                error!(target: "player", "{e}");
+            }
+        }
+
+        self.subtitles.styled_language = Some(language);
+    }
+}
+
+impl Player {
+    /// Output devices known to mpv, with their saved settings
+    pub fn audio_devices(&self) -> Value {
+        let current = self.audio_device();
+        let devices = self
+            .get_property("audio-device-list".to_owned())
+            .ok()
+            .and_then(|property| property.1)
+            .and_then(|devices| devices.as_array().cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|device| {
+                let name = device
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+
+                json!({
+                    "name": name,
+                    "description": device.get("description"),
+                    "active": name == current,
+                    "settings": self.audio.device_settings(name),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "device": current,
+            "devices": devices,
+        })
+    }
+
+    /// Name of the output device in use, `auto` when mpv picks it
+    pub fn audio_device(&self) -> String {
+        self.mpv
+            .get_property::<String>("audio-device")
+            .unwrap_or_else(|_| "auto".to_owned())
+    }
+
+    pub fn audio_device_settings(&self) -> AudioDeviceSettings {
+        self.audio.device_settings(&self.audio_device())
+    }
+
+    pub fn set_audio_config(&mut self, config: AudioConfig) {
+        self.audio = config;
+        self.apply_audio_config();
+    }
+
+    /// Switches to the configured device and applies its settings
+    fn apply_audio_config(&self) {
+        let device = self
+            .audio
+            .device
+            .clone()
+            .or_else(|| {
+                self.audio_base_value("audio-device")?
+                    .as_str()
+                    .map(str::to_owned)
+            })
+            .unwrap_or_else(|| "auto".to_owned());
+
+        // Setting the device reopens it, even when it doesn't change
+        if device != self.audio_device()
+            && let Err(e) = self.mpv.set_property("audio-device", device.as_str())
+        {
+            error!(target: "player", "Failed to set audio device {device}: {e}");
+        }
+
+        let settings = self.audio_device_settings();
+
+        let values = [
+            settings.spdif.map(|codecs| json!(codecs.join(","))),
+            settings.channels.map(Value::from),
+        ];
+
+        for (name, value) in audio::DEVICE_PROPERTIES.iter().zip(values) {
+            let Some(value) = value.or_else(|| self.audio_base_value(name)) else {
+                continue;
+            };
+
+            if let Err(e) = self.set_property(MpvProperty(name.to_string(), Some(value))) {
+                error!(target: "player", "{e}");
+            }
+        }
+
+        // Removing a filter that isn't there fails, which is expected
+        self.try_command(
+            "af".to_owned(),
+            vec!["remove".to_owned(), audio::NORMALIZATION_LABEL.to_owned()],
+        )
+        .ok();
+
+        if let Some(filter) = audio::normalization_filter(settings.normalization) {
+            self.command("af".to_owned(), vec!["add".to_owned(), filter]);
+        }
+    }
+
+    fn audio_base_value(&self, name: &str) -> Option<Value> {
+        self.audio_base
+            .iter()
+            .find(|(base_name, _)| *base_name == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Selects the audio track of the preferred languages, returns its language
+    fn select_audio(&self, track_list: &Value) -> Option<String> {
+        let (id, language) = audio::select_track(track_list, &self.audio.languages)?;
+
+        match self.mpv.set_property("aid", id) {
+            Ok(()) => Some(language),
+            Err(e) => {
+                error!(target: "player", "Failed to select audio track {id}: {e}");
+                None
+            }
+        }
     }
 }
 
@@ -329,3 +1049,32 @@ impl Drop for Player {
         self.render_context.take();
     }
 }
+
+fn mpv_versions(mpv: &Mpv) -> Value {
+    let version = |name: &str| mpv.get_property::<String>(name).ok();
+
+    json!({
+        "mpv": version("mpv-version"),
+        "ffmpeg": version("ffmpeg-version"),
+    })
+}
+
+/// Versions of mpv and FFmpeg from a temporary instance, for when no player is running
+pub fn probe_versions() -> Value {
+    // Required for libmpv to work alongside gtk
+    unsafe {
+        setlocale(LC_NUMERIC, c"C".as_ptr());
+    }
+
+    let mpv = Mpv::with_initializer(|init| {
+        init.set_property("vo", "null")?;
+        init.set_property("ao", "null")?;
+        init.set_property("config", "no")?;
+        Ok(())
+    });
+
+    match mpv {
+        Ok(mpv) => mpv_versions(&mpv),
+        Err(e) => json!({ "error": e.to_string() }),
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+const RESUME_FILE: &str = "resume.json";
+// Oldest entries are dropped once the store grows past this size
+const MAX_ENTRIES: usize = 1000;
+// Positions are flushed to disk at most this often while playing
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+// Positions too close to the start are not worth resuming
+const MIN_POSITION: f64 = 10.0;
+// Past this fraction of the duration the stream is considered watched
+const WATCHED_RATIO: f64 = 0.95;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ResumeEntry {
+    pub position: f64,
+    pub duration: f64,
+    pub updated: u64,
+}
+
+#[derive(Default)]
+struct Playback {
+    key: Option<String>,
+    position: f64,
+    duration: f64,
+}
+
+/// Local database of playback positions, keyed by stream
+pub struct ResumeStore {
+    file: PathBuf,
+    entries: HashMap<String, ResumeEntry>,
+    playback: Playback,
+    last_save: Instant,
+    dirty: bool,
+}
+
+impl ResumeStore {
+    pub fn new(data_dir: &Path) -> Self {
+        let file = data_dir.join(RESUME_FILE);
+
+        let entries = fs::read_to_string(&file)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            file,
+            entries,
+            playback: Playback::default(),
+            last_save: Instant::now(),
+            dirty: false,
+        }
+    }
+
+    /// Returns the saved entry of a stream path
+    pub fn get(&self, path: &str) -> Option<ResumeEntry> {
+        self.entries.get(&stream_key(path)).copied()
+    }
+
+    /// Returns the saved entry of the stream currently playing
+    pub fn current(&self) -> Option<ResumeEntry> {
+        self.playback
+            .key
+            .as_ref()
+            .and_then(|key| self.entries.get(key))
+            .copied()
+    }
+
+    pub fn set_path(&mut self, path: Option<&str>) {
+        let key = path.map(stream_key);
+
+        if key != self.playback.key {
+            self.playback = Playback {
+                key,
+                ..Default::default()
+            };
+        }
+    }
+
+    pub fn set_duration(&mut self, duration: f64) {
+        self.playback.duration = duration;
+    }
+
+    pub fn set_position(&mut self, position: f64) {
+        self.playback.position = position;
+        self.record();
+
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Records the last known position when playback stops,
+    /// streams played until the end are removed from the store
+    pub fn stop(&mut self, ended: bool) {
+        if let Some(key) = self.playback.key.as_ref() {
+            match ended {
+                true => {
+                    self.entries.remove(key);
+                    self.dirty = true;
+                }
+                false => self.record(),
+            }
+        }
+
+        self.playback = Playback::default();
+        self.save();
+    }
+
+    fn record(&mut self) {
+        let Playback {
+            key: Some(key),
+            position,
+            duration,
+        } = &self.playback
+        else {
+            return;
+        };
+
+        let watched = *duration > 0.0 && *position >= duration * WATCHED_RATIO;
+
+        if watched {
+            self.dirty |= self.entries.remove(key).is_some();
+        } else if *position >= MIN_POSITION {
+            let updated = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or_default();
+
+            self.entries.insert(
+                key.to_owned(),
+                ResumeEntry {
+                    position: *position,
+                    duration: *duration,
+                    updated,
+                },
+            );
+
+            self.dirty = true;
+        }
+    }
+
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+
+        if !self.dirty {
+            return;
+        }
+
+        if self.entries.len() > MAX_ENTRIES {
+            let mut entries = self.entries.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.updated));
+
+            self.entries = entries
+                .into_iter()
+                .take(MAX_ENTRIES)
+                .map(|(key, entry)| (key.to_owned(), *entry))
+                .collect();
+        }
+
+        match self.write() {
+            Ok(_) => {
+                self.dirty = false;
+                debug!(target: "resume", "Saved {} entries", self.entries.len());
+            }
+            Err(e) => error!(target: "resume", "{e}"),
+        }
+    }
+
+    /// Writes to a temporary file first so that a crash never leaves a truncated store
+    fn write(&self) -> Result<()> {
+        let content = serde_json::to_string(&self.entries)?;
+        let temp_file = self.file.with_extension("json.tmp");
+
+        fs::write(&temp_file, content).context("Failed to write resume store")?;
+        fs::rename(&temp_file, &self.file).context("Failed to replace resume store")?;
+
+        Ok(())
+    }
+}
+
+/// Streams served by the streaming server are identified by their infohash and file index,
+/// so that the position survives a change of server address or of trackers in the query
+fn stream_key(path: &str) -> String {
+    let segments = path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(path)
+        .split('/')
+        .collect::<Vec<_>>();
+
+    segments
+        .iter()
+        .position(|segment| segment.len() == 40 && segment.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|index| {
+            let infohash = segments[index].to_lowercase();
+            match segments.get(index + 1) {
+                Some(file) if file.parse::<u64>().is_ok() => format!("{infohash}/{file}"),
+                _ => infohash,
+            }
+        })
+        .unwrap_or_else(|| path.to_owned())
+}
@@ -0,0 +1,155 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use super::{MAX_ENTRIES, RESUME_FILE, ResumeEntry, ResumeStore, stream_key};
+
+const INFOHASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn stream(address: &str, file: &str) -> String {
+    format!(
+        "http://{address}/{}/{file}?tr=udp://tracker",
+        INFOHASH.to_uppercase()
+    )
+}
+
+/// A store playing a stream of 100 seconds
+fn playing(dir: &TempDir, path: &str) -> ResumeStore {
+    let mut store = ResumeStore::new(dir.path());
+    store.set_path(Some(path));
+    store.set_duration(100.0);
+    store
+}
+
+#[test]
+fn stream_keys() {
+    // The server address and the query don't matter, the file index does
+    assert_eq!(
+        stream_key(&stream("127.0.0.1:11470", "2")),
+        format!("{INFOHASH}/2")
+    );
+    assert_eq!(
+        stream_key(&stream("192.168.1.2:11470", "2")),
+        stream_key(&stream("127.0.0.1:11470", "2#t=10"))
+    );
+    assert_ne!(
+        stream_key(&stream("127.0.0.1:11470", "2")),
+        stream_key(&stream("127.0.0.1:11470", "3"))
+    );
+    assert_eq!(stream_key(&stream("127.0.0.1:11470", "")), INFOHASH);
+
+    // Anything else is identified by its full path
+    assert_eq!(stream_key("/videos/a.mkv"), "/videos/a.mkv");
+    assert_eq!(
+        stream_key("https://host/a.mkv?token=1"),
+        "https://host/a.mkv?token=1"
+    );
+    assert_eq!(stream_key("https://host/abc/1"), "https://host/abc/1");
+}
+
+#[test]
+fn record_and_stop() {
+    let dir = TempDir::new().unwrap();
+    let path = stream("127.0.0.1:11470", "1");
+    let mut store = playing(&dir, &path);
+
+    // Too close to the start
+    store.set_position(5.0);
+    assert!(store.current().is_none());
+
+    store.set_position(50.0);
+    assert_eq!(store.current().unwrap().position, 50.0);
+
+    store.set_position(60.0);
+    store.stop(false);
+    assert!(store.current().is_none());
+
+    // The entry is saved and found again from another server address
+    let store = ResumeStore::new(dir.path());
+    let entry = store.get(&stream("10.0.0.1:11470", "1")).unwrap();
+    assert_eq!((entry.position, entry.duration), (60.0, 100.0));
+    assert!(entry.updated > 0);
+}
+
+#[test]
+fn watched() {
+    let dir = TempDir::new().unwrap();
+    let path = "/videos/a.mkv";
+    let mut store = playing(&dir, path);
+
+    store.set_position(90.0);
+    assert!(store.current().is_some());
+
+    // Past the watched ratio the entry is removed
+    store.set_position(95.0);
+    assert!(store.current().is_none());
+    store.stop(false);
+
+    assert!(ResumeStore::new(dir.path()).get(path).is_none());
+}
+
+#[test]
+fn ended() {
+    let dir = TempDir::new().unwrap();
+    let path = "/videos/a.mkv";
+
+    let mut store = playing(&dir, path);
+    store.set_position(50.0);
+    store.stop(false);
+
+    let mut store = playing(&dir, path);
+    assert_eq!(store.current().unwrap().position, 50.0);
+    store.stop(true);
+
+    assert!(ResumeStore::new(dir.path()).get(path).is_none());
+}
+
+#[test]
+fn eviction() {
+    let dir = TempDir::new().unwrap();
+    let mut store = ResumeStore::new(dir.path());
+
+    for updated in 0..=MAX_ENTRIES as u64 {
+        let entry = ResumeEntry {
+            position: 50.0,
+            duration: 100.0,
+            updated,
+        };
+        store
+            .entries
+            .insert(format!("/videos/{updated}.mkv"), entry);
+    }
+    store.dirty = true;
+    store.save();
+
+    // The oldest entry is dropped
+    let store = ResumeStore::new(dir.path());
+    assert_eq!(store.entries.len(), MAX_ENTRIES);
+    assert!(store.get("/videos/0.mkv").is_none());
+    assert!(store.get("/videos/1.mkv").is_some());
+    assert!(store.get(&format!("/videos/{MAX_ENTRIES}.mkv")).is_some());
+}
+
+#[test]
+fn atomic_save() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join(RESUME_FILE);
+    fs::write(&file, "{ truncated").unwrap();
+
+    // A corrupted store is started over
+    let mut store = playing(&dir, "/videos/a.mkv");
+    assert!(store.entries.is_empty());
+
+    store.set_position(50.0);
+    store.stop(false);
+
+    let entries = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, [RESUME_FILE]);
+
+    let content = fs::read_to_string(&file).unwrap();
+    let saved = serde_json::from_str::<serde_json::Value>(&content).unwrap();
+    assert_eq!(saved["/videos/a.mkv"]["position"], 50.0);
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+pub const SHADER_PRESETS_FILE: &str = "shader-presets.json";
+// Prefix mpv expands to its config directory
+const MPV_CONFIG_PREFIX: &str = "~~/";
+
+/// Conditions under which a preset is selected automatically
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AutoRule {
+    #[serde(default)]
+    pub content: Vec<String>,
+    #[serde(default)]
+    pub min_height: Option<i64>,
+    #[serde(default)]
+    pub max_height: Option<i64>,
+}
+
+impl AutoRule {
+    fn matches(&self, height: i64, content: Option<&str>) -> bool {
+        let content_matches = self.content.is_empty()
+            || content.is_some_and(|content| self.content.iter().any(|tag| tag == content));
+
+        content_matches
+            && self.min_height.is_none_or(|min| height >= min)
+            && self.max_height.is_none_or(|max| height <= max)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShaderPreset {
+    pub name: String,
+    pub label: String,
+    pub shaders: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto: Option<AutoRule>,
+    /// Shader files referenced by the preset that could not be found
+    #[serde(skip_deserializing)]
+    pub missing: Vec<String>,
+}
+
+impl ShaderPreset {
+    pub fn valid(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct ShaderPresetsFile {
+    #[serde(default)]
+    presets: Vec<ShaderPreset>,
+}
+
+/// Shader presets loaded from the mpv config directory
+pub struct ShaderPresets {
+    presets: Vec<ShaderPreset>,
+    pub active: Option<String>,
+    /// Content tag of the current media (ex: `anime`), provided by the web UI
+    pub content: Option<String>,
+    /// Height of the current video
+    pub height: Option<i64>,
+    /// Whether the preset was picked by the user for the current video, which disables auto selection
+    pub manual: bool,
+    /// Preset picked by the user or restored from the config, kept when no rule matches a video
+    pub preferred: Option<String>,
+}
+
+impl ShaderPresets {
+    pub fn new(config_dir: &Path) -> Self {
+        let file = config_dir.join(SHADER_PRESETS_FILE);
+
+        let mut presets = fs::read_to_string(&file)
+            .ok()
+            .and_then(
+                |content| match serde_json::from_str::<ShaderPresetsFile>(&content) {
+                    Ok(file) => Some(file.presets),
+                    Err(e) => {
+                        warn!(target: "shaders", "Failed to parse {SHADER_PRESETS_FILE}: {e}");
+                        None
+                    }
+                },
+            )
+            .unwrap_or_default();
+
+        presets.iter_mut().for_each(|preset| {
+            preset.missing = preset
+                .shaders
+                .iter()
+                .filter(|shader| !shader_path(config_dir, shader).is_file())
+                .cloned()
+                .collect();
+
+            if !preset.valid() {
+                warn!(
+                    target: "shaders",
+                    "Preset {} is missing {} shader files: {}",
+                    preset.name,
+                    preset.missing.len(),
+                    preset.missing.join(", ")
+                );
+            }
+        });
+
+        Self {
+            presets,
+            active: None,
+            content: None,
+            height: None,
+            manual: false,
+            preferred: None,
+        }
+    }
+
+    pub fn all(&self) -> &[ShaderPreset] {
+        &self.presets
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ShaderPreset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Returns the first valid preset whose rule matches the current video, if it is known
+    pub fn auto_select(&self) -> Option<&ShaderPreset> {
+        let height = self.height?;
+
+        self.presets.iter().find(|preset| {
+            preset.valid()
+                && preset
+                    .auto
+                    .as_ref()
+                    .is_some_and(|rule| rule.matches(height, self.content.as_deref()))
+        })
+    }
+}
+
+/// Resolves a shader path the way mpv does, `~~/` being the mpv config directory
+fn shader_path(config_dir: &Path, shader: &str) -> PathBuf {
+    match shader.strip_prefix(MPV_CONFIG_PREFIX) {
+        Some(path) => config_dir.join(path),
+        None => PathBuf::from(shader),
+    }
+}
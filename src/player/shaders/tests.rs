@@ -0,0 +1,110 @@
+use std::fs;
+
+use serde_json::json;
+use tempfile::TempDir;
+
+use super::{SHADER_PRESETS_FILE, ShaderPresets};
+
+/// Presets for anime up to 1080p, any video up to 720p, and one with a missing shader file
+fn presets() -> (TempDir, ShaderPresets) {
+    let config_dir = TempDir::new().unwrap();
+    fs::create_dir_all(config_dir.path().join("shaders")).unwrap();
+    fs::write(config_dir.path().join("shaders").join("a.glsl"), "").unwrap();
+
+    let file = json!({
+        "presets": [
+            {
+                "name": "broken",
+                "label": "Broken",
+                "shaders": ["~~/shaders/missing.glsl"],
+                "auto": {}
+            },
+            {
+                "name": "anime",
+                "label": "Anime",
+                "shaders": ["~~/shaders/a.glsl"],
+                "auto": { "content": ["anime"], "max_height": 1080 }
+            },
+            {
+                "name": "low",
+                "label": "Low resolution",
+                "shaders": ["~~/shaders/a.glsl"],
+                "auto": { "max_height": 720 }
+            },
+            {
+                "name": "manual",
+                "label": "Manual",
+                "shaders": ["~~/shaders/a.glsl"]
+            }
+        ]
+    });
+    fs::write(
+        config_dir.path().join(SHADER_PRESETS_FILE),
+        file.to_string(),
+    )
+    .unwrap();
+
+    let presets = ShaderPresets::new(config_dir.path());
+    (config_dir, presets)
+}
+
+fn selected(presets: &ShaderPresets) -> Option<&str> {
+    presets.auto_select().map(|preset| preset.name.as_str())
+}
+
+#[test]
+fn missing_files() {
+    let (_config_dir, presets) = presets();
+
+    assert_eq!(presets.all().len(), 4);
+    assert!(!presets.get("broken").unwrap().valid());
+    assert_eq!(
+        presets.get("broken").unwrap().missing,
+        ["~~/shaders/missing.glsl"]
+    );
+    assert!(presets.get("anime").unwrap().valid());
+}
+
+#[test]
+fn unknown_height() {
+    let (_config_dir, mut presets) = presets();
+    presets.content = Some("anime".to_owned());
+
+    assert_eq!(selected(&presets), None);
+}
+
+#[test]
+fn rules() {
+    let (_config_dir, mut presets) = presets();
+
+    // The broken preset matches everything but is skipped
+    presets.height = Some(480);
+    assert_eq!(selected(&presets), Some("low"));
+
+    presets.content = Some("anime".to_owned());
+    assert_eq!(selected(&presets), Some("anime"));
+
+    presets.height = Some(1080);
+    assert_eq!(selected(&presets), Some("anime"));
+
+    presets.height = Some(2160);
+    assert_eq!(selected(&presets), None);
+}
+
+#[test]
+fn no_match() {
+    let (_config_dir, mut presets) = presets();
+    presets.height = Some(1080);
+    presets.content = Some("movie".to_owned());
+
+    assert_eq!(selected(&presets), None);
+}
+
+#[test]
+fn no_presets_file() {
+    let config_dir = TempDir::new().unwrap();
+    let presets = ShaderPresets::new(config_dir.path());
+
+    assert!(presets.all().is_empty());
+    assert!(presets.auto_select().is_none());
+}
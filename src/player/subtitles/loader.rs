@@ -0,0 +1,260 @@
+use std::{
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use itertools::Itertools;
+use reqwest::blocking::Client;
+use serde::Serialize;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+// Larger files are not subtitles
+pub(super) const MAX_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+    Ass,
+}
+
+impl SubtitleFormat {
+    /// Sniffs the format from the content, the extension of the source decides otherwise
+    pub(super) fn detect(source: &str, text: &str) -> Option<Self> {
+        let head = text.trim_start();
+
+        if head.starts_with("WEBVTT") {
+            return Some(Self::Vtt);
+        }
+
+        if head.starts_with("[Script Info]") {
+            return Some(Self::Ass);
+        }
+
+        if text.contains("-->") {
+            return Some(Self::Srt);
+        }
+
+        let extension = source
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_lowercase());
+
+        match extension.as_deref() {
+            Some("srt") => Some(Self::Srt),
+            Some("vtt") => Some(Self::Vtt),
+            Some("ass" | "ssa") => Some(Self::Ass),
+            _ => None,
+        }
+    }
+
+    /// VTT is converted to SRT, which mpv and the styling options handle the same way
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt | Self::Vtt => "srt",
+            Self::Ass => "ass",
+        }
+    }
+}
+
+/// A subtitle file converted to UTF-8 and written to the cache
+#[derive(Debug)]
+pub struct LoadedSubtitle {
+    pub path: PathBuf,
+    pub format: SubtitleFormat,
+    pub encoding: &'static str,
+}
+
+/// Reads a local or HTTP subtitle file and normalizes it for mpv
+pub fn load(source: &str, cache_dir: &Path) -> anyhow::Result<LoadedSubtitle> {
+    let data = fetch(source)?;
+    let (text, encoding) = decode(&data);
+
+    let format = SubtitleFormat::detect(source, &text)
+        .with_context(|| format!("Unsupported subtitle format of {source}"))?;
+
+    let text = normalize(&text, format);
+    if text.trim().is_empty() {
+        bail!("No subtitles found in {source}");
+    }
+
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    let path = cache_dir.join(format!("{:016x}.{}", hasher.finish(), format.extension()));
+
+    fs::create_dir_all(cache_dir).context("Failed to create subtitle cache")?;
+    fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(LoadedSubtitle {
+        path,
+        format,
+        encoding,
+    })
+}
+
+fn fetch(source: &str) -> anyhow::Result<Vec<u8>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = Client::builder()
+            .timeout(DOWNLOAD_TIMEOUT)
+            .build()?
+            .get(source)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to download {source}"))?;
+
+        if response
+            .content_length()
+            .is_some_and(|length| length > MAX_SIZE)
+        {
+            bail!("{source} is too large for a subtitle file");
+        }
+
+        // Without a length, chunked answers are cut right past the limit
+        let mut data = vec![];
+        response
+            .take(MAX_SIZE + 1)
+            .read_to_end(&mut data)
+            .with_context(|| format!("Failed to download {source}"))?;
+
+        if data.len() as u64 > MAX_SIZE {
+            bail!("{source} is too large for a subtitle file");
+        }
+
+        return Ok(data);
+    }
+
+    let path = Path::new(source.strip_prefix("file://").unwrap_or(source));
+
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() > MAX_SIZE) {
+        bail!("{source} is too large for a subtitle file");
+    }
+
+    fs::read(path).with_context(|| format!("Failed to read {source}"))
+}
+
+/// Decodes the file from its BOM, as UTF-8 when valid, or from the detected charset
+pub(super) fn decode(data: &[u8]) -> (String, &'static str) {
+    let encoding = match Encoding::for_bom(data) {
+        Some((encoding, _)) => encoding,
+        None if std::str::from_utf8(data).is_ok() => UTF_8,
+        None => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(data, true);
+            detector.guess(None, true)
+        }
+    };
+
+    let (text, encoding, _) = encoding.decode(data);
+    (text.into_owned(), encoding.name())
+}
+
+pub(super) fn normalize(text: &str, format: SubtitleFormat) -> String {
+    let text = text
+        .trim_start_matches('\u{feff}')
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+
+    match format {
+        SubtitleFormat::Ass => text,
+        SubtitleFormat::Srt | SubtitleFormat::Vtt => to_srt(&text, format),
+    }
+}
+
+/// Rewrites the cues as SRT: numbered, with `HH:MM:SS,mmm` timings and without VTT metadata
+fn to_srt(text: &str, format: SubtitleFormat) -> String {
+    text.split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines().skip_while(|line| line.trim().is_empty());
+            let mut timing = lines.next()?;
+
+            // SRT numbers and VTT identifiers precede the timing line
+            if !timing.contains("-->") {
+                timing = lines.next()?;
+            }
+
+            let (start, end) = timing.split_once("-->")?;
+            // VTT cue settings follow the end time
+            let end = end.split_whitespace().next()?;
+            let timing = format!("{} --> {}", timestamp(start)?, timestamp(end)?);
+
+            let text = lines
+                .map(|line| match format {
+                    SubtitleFormat::Vtt => strip_vtt_tags(line),
+                    _ => line.to_owned(),
+                })
+                .join("\n");
+
+            Some((timing, text))
+        })
+        .enumerate()
+        .map(|(index, (timing, text))| format!("{}\n{timing}\n{text}\n", index + 1))
+        .join("\n")
+}
+
+/// Formats `[HH:]MM:SS[.,]mmm` as `HH:MM:SS,mmm`
+pub(super) fn timestamp(value: &str) -> Option<String> {
+    let (time, millis) = value.trim().split_once([',', '.'])?;
+    // Shorter fractions are tenths or hundredths: `.5` is 500ms
+    let millis = format!("{:0<3}", millis.get(..3).unwrap_or(millis))
+        .parse::<u32>()
+        .ok()?;
+
+    let parts = time
+        .split(':')
+        .map(|part| part.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes, seconds] => (*hours, *minutes, *seconds),
+        [minutes, seconds] => (0, *minutes, *seconds),
+        _ => return None,
+    };
+
+    Some(format!("{hours:02}:{minutes:02}:{seconds:02},{millis:03}"))
+}
+
+/// Keeps the tags SRT understands (`<i>`, `<b>`, `<u>`), drops voices, classes and timestamps
+pub(super) fn strip_vtt_tags(line: &str) -> String {
+    let mut result = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+
+        let tag = &rest[start..start + end + 1];
+        let name = tag
+            .trim_start_matches(['<', '/'])
+            .split(['.', ' ', '>'])
+            .next()
+            .unwrap_or_default();
+
+        if matches!(name, "i" | "b" | "u") {
+            match tag.starts_with("</") {
+                true => result.push_str(&format!("</{name}>")),
+                false => result.push_str(&format!("<{name}>")),
+            }
+        }
+
+        rest = &rest[start + end + 1..];
+    }
+
+    result.push_str(rest);
+    result
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+}
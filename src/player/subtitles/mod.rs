@@ -0,0 +1,159 @@
+mod loader;
+#[cfg(test)]
+mod tests;
+mod tracks;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+};
+
+use crossbeam_channel::Receiver;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{SubtitleStyle, SubtitlesConfig},
+    player::languages,
+    shared::waker::{self, WakingSender},
+};
+pub use loader::LoadedSubtitle;
+pub use tracks::{SubtitleSelection, SubtitleTrack};
+
+const CACHE_DIR: &str = "subtitles";
+const DEFAULT_STYLE: &str = "default";
+// mpv properties set by the styles
+pub const STYLE_PROPERTIES: &[&str] = &[
+    "sub-font",
+    "sub-font-size",
+    "sub-color",
+    "sub-border-color",
+    "sub-border-size",
+    "sub-bold",
+    "sub-pos",
+    "sub-scale",
+];
+
+/// Subtitle file requested by the web UI, once loaded
+pub struct SubtitleLoad {
+    pub source: String,
+    pub lang: Option<String>,
+    pub title: Option<String>,
+    pub result: anyhow::Result<LoadedSubtitle>,
+}
+
+/// External subtitle files, automatic track selection and styles by language
+pub struct Subtitles {
+    pub config: SubtitlesConfig,
+    cache_dir: PathBuf,
+    sender: WakingSender<SubtitleLoad>,
+    receiver: Receiver<SubtitleLoad>,
+    pub tracks: Vec<SubtitleTrack>,
+    pub audio_language: Option<String>,
+    /// Values of the style properties from mpv.conf, restored for unstyled languages
+    pub base_style: Vec<(&'static str, Value)>,
+    /// Language whose style is applied, `Some(None)` for subtitles without a language
+    pub styled_language: Option<Option<String>>,
+}
+
+impl Subtitles {
+    pub fn new(data_dir: &Path, config: SubtitlesConfig) -> Self {
+        // Converted files are only needed while they are played
+        let cache_dir = data_dir.join(CACHE_DIR);
+        fs::remove_dir_all(&cache_dir).ok();
+
+        let (sender, receiver) = waker::channel::<SubtitleLoad>();
+
+        Self {
+            config,
+            cache_dir,
+            sender,
+            receiver,
+            tracks: vec![],
+            audio_language: None,
+            base_style: vec![],
+            styled_language: None,
+        }
+    }
+
+    /// Downloads and converts a file in the background, the result is returned by `loaded`
+    pub fn load(&self, source: String, lang: Option<String>, title: Option<String>) {
+        let cache_dir = self.cache_dir.clone();
+        let sender = self.sender.clone();
+
+        thread::spawn(move || {
+            let result = loader::load(&source, &cache_dir);
+            sender
+                .send(SubtitleLoad {
+                    source,
+                    lang,
+                    title,
+                    result,
+                })
+                .ok();
+        });
+    }
+
+    pub fn loaded(&self) -> Vec<SubtitleLoad> {
+        self.receiver.try_iter().collect()
+    }
+
+    pub fn update_tracks(&mut self, track_list: &Value) {
+        self.tracks = tracks::parse_tracks(track_list);
+        self.audio_language = tracks::audio_language(track_list);
+    }
+
+    pub fn select(&self) -> Option<SubtitleSelection> {
+        tracks::select(&self.tracks, self.audio_language.as_deref(), &self.config)
+    }
+
+    pub fn selected(&self) -> Option<&SubtitleTrack> {
+        self.tracks.iter().find(|track| track.selected)
+    }
+
+    /// Style of a language, the default style applies to the languages without one
+    pub fn style(&self, language: Option<&str>) -> Option<&SubtitleStyle> {
+        language
+            .and_then(|language| {
+                self.config
+                    .styles
+                    .iter()
+                    .find(|(name, _)| languages::language_matches(name, language))
+            })
+            .or_else(|| self.config.styles.get_key_value(DEFAULT_STYLE))
+            .map(|(_, style)| style)
+    }
+}
+
+/// Values of the style properties, unset ones are taken from `base`
+pub fn style_properties(
+    style: Option<&SubtitleStyle>,
+    base: &[(&'static str, Value)],
+) -> Vec<(&'static str, Value)> {
+    let style = style.cloned().unwrap_or_default();
+
+    let values = [
+        style.font.map(Value::from),
+        style.font_size.map(Value::from),
+        style.color.map(Value::from),
+        style.border_color.map(Value::from),
+        style.border_size.map(|size| json!(size)),
+        style.bold.map(Value::from),
+        style.position.map(Value::from),
+        style.scale.map(|scale| json!(scale)),
+    ];
+
+    STYLE_PROPERTIES
+        .iter()
+        .zip(values)
+        .filter_map(|(name, value)| {
+            value
+                .or_else(|| {
+                    base.iter()
+                        .find(|(base_name, _)| base_name == name)
+                        .map(|(_, value)| value.clone())
+                })
+                .map(|value| (*name, value))
+        })
+        .collect()
+}
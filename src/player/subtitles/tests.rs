@@ -0,0 +1,260 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+};
+
+use encoding_rs::{WINDOWS_1251, WINDOWS_1252};
+use serde_json::json;
+use tempfile::TempDir;
+
+use super::{
+    loader::{MAX_SIZE, SubtitleFormat, decode, load, normalize, strip_vtt_tags, timestamp},
+    tracks::{SubtitleSelection, audio_language, parse_tracks, select},
+};
+use crate::config::{ForcedSubtitles, SubtitlesConfig};
+
+const FRENCH: &str = "1\n00:00:01,000 --> 00:00:02,000\nÇa a été un été très chaud, \
+                      déjà à la fenêtre on voyait le garçon.\n";
+const RUSSIAN: &str = "1\n00:00:01,000 --> 00:00:02,000\nПривет, как дела? Всё хорошо, \
+                       спасибо. Сегодня прекрасная погода.\n";
+
+const VTT: &str = "WEBVTT - Episode 1\r\nKind: captions\r\n\r\nNOTE written by hand\r\n\r\n\
+                   intro\r\n00:01.500 --> 00:03.250 align:start position:10%\r\n\
+                   <v Narrator>Once upon a <i>time</i></v>\r\n<c.yellow>Tom &amp; Jerry</c>\r\n\r\n\
+                   01:02:03.004 --> 01:02:05.000\r\n<b>Bold</b> <00:01:02.500>later\r\n";
+
+fn config(languages: &[&str], forced: ForcedSubtitles, sdh: bool) -> SubtitlesConfig {
+    SubtitlesConfig {
+        languages: languages
+            .iter()
+            .map(|language| language.to_string())
+            .collect(),
+        forced,
+        sdh,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn charsets() {
+    let (latin, _, _) = WINDOWS_1252.encode(FRENCH);
+    assert_eq!(decode(&latin), (FRENCH.to_owned(), "windows-1252"));
+
+    let (cyrillic, _, _) = WINDOWS_1251.encode(RUSSIAN);
+    assert_eq!(decode(&cyrillic), (RUSSIAN.to_owned(), "windows-1251"));
+
+    assert_eq!(decode(RUSSIAN.as_bytes()), (RUSSIAN.to_owned(), "UTF-8"));
+
+    // The BOM decides over the detection
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend(FRENCH.encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(decode(&utf16), (FRENCH.to_owned(), "UTF-16LE"));
+}
+
+#[test]
+fn vtt_to_srt() {
+    assert_eq!(
+        normalize(VTT, SubtitleFormat::Vtt),
+        "1\n00:00:01,500 --> 00:00:03,250\nOnce upon a <i>time</i>\nTom & Jerry\n\n\
+         2\n01:02:03,004 --> 01:02:05,000\n<b>Bold</b> later\n"
+    );
+}
+
+#[test]
+fn srt_renumbered() {
+    let srt = "\u{feff}5\r\n00:00:01,000 --> 00:00:02,000\r\nFirst\r\n\r\n\r\n\
+               9\r\n00:00:03,000 --> 00:00:04,000\r\nSecond\r\nline\r\n";
+
+    assert_eq!(
+        normalize(srt, SubtitleFormat::Srt),
+        "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n\
+         2\n00:00:03,000 --> 00:00:04,000\nSecond\nline\n"
+    );
+}
+
+#[test]
+fn timestamps() {
+    assert_eq!(timestamp("01:02:03,456").as_deref(), Some("01:02:03,456"));
+    assert_eq!(timestamp(" 02:03.456 ").as_deref(), Some("00:02:03,456"));
+    assert_eq!(timestamp("1:2:3.4567").as_deref(), Some("01:02:03,456"));
+    assert_eq!(timestamp("00:01.5").as_deref(), Some("00:00:01,500"));
+    assert_eq!(timestamp("00:01.05").as_deref(), Some("00:00:01,050"));
+    assert_eq!(timestamp("03"), None);
+    assert_eq!(timestamp("03.000"), None);
+    assert_eq!(timestamp("aa:bb.ccc"), None);
+}
+
+#[test]
+fn vtt_tags() {
+    assert_eq!(
+        strip_vtt_tags("<v.loud Bob>Hi</v> <I>there</I>"),
+        "Hi there"
+    );
+    assert_eq!(strip_vtt_tags("<i.a>a</i> <u>b</u>"), "<i>a</i> <u>b</u>");
+    assert_eq!(strip_vtt_tags("1 &lt; 2 &gt; 0"), "1 < 2 > 0");
+    assert_eq!(strip_vtt_tags("unclosed <tag"), "unclosed <tag");
+}
+
+#[test]
+fn formats() {
+    let detect = SubtitleFormat::detect;
+
+    assert_eq!(detect("a.srt", "\n\nWEBVTT\n"), Some(SubtitleFormat::Vtt));
+    assert_eq!(detect("a", "[Script Info]\n"), Some(SubtitleFormat::Ass));
+    assert_eq!(detect("a", "00:01 --> 00:02"), Some(SubtitleFormat::Srt));
+    assert_eq!(
+        detect("https://host/a.SSA?token=1", ""),
+        Some(SubtitleFormat::Ass)
+    );
+    assert_eq!(detect("a.txt", "text"), None);
+}
+
+#[test]
+fn load_local_file() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("episode.vtt");
+    fs::write(&file, WINDOWS_1252.encode(VTT).0).unwrap();
+
+    let cache_dir = dir.path().join("cache");
+    let loaded = load(&format!("file://{}", file.display()), &cache_dir).unwrap();
+
+    assert_eq!(loaded.format, SubtitleFormat::Vtt);
+    assert_eq!(loaded.path.extension().unwrap(), "srt");
+    assert!(loaded.path.starts_with(&cache_dir));
+    assert!(
+        fs::read_to_string(&loaded.path)
+            .unwrap()
+            .starts_with("1\n00:00:01,500")
+    );
+
+    fs::write(&file, "WEBVTT\n\n").unwrap();
+    assert!(load(&file.to_string_lossy(), &cache_dir).is_err());
+    assert!(
+        load(
+            &dir.path().join("missing.srt").to_string_lossy(),
+            &cache_dir
+        )
+        .is_err()
+    );
+}
+
+/// Serves `chunk` `count` times as a chunked answer, without a length
+fn chunked_server(chunk: &'static str, count: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/episode.srt", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        let Ok((mut stream, _)) = listener.accept() else {
+            return;
+        };
+
+        // A GET request ends with an empty line
+        BufReader::new(&stream)
+            .lines()
+            .map_while(Result::ok)
+            .take_while(|line| !line.is_empty())
+            .for_each(drop);
+
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n"
+        )
+        .ok();
+        for _ in 0..count {
+            // The client stops reading past the limit
+            if write!(stream, "{:x}\r\n{chunk}\r\n", chunk.len()).is_err() {
+                return;
+            }
+        }
+        write!(stream, "0\r\n\r\n").ok();
+    });
+
+    url
+}
+
+#[test]
+fn chunked_download() {
+    let dir = TempDir::new().unwrap();
+
+    let loaded = load(&chunked_server(FRENCH, 1), dir.path()).unwrap();
+    assert_eq!(loaded.format, SubtitleFormat::Srt);
+
+    // Answers without a length are still limited
+    let chunk = "a".repeat(1024 * 1024).leak();
+    let count = MAX_SIZE as usize / chunk.len() + 1;
+    let error = load(&chunked_server(chunk, count), dir.path()).unwrap_err();
+    assert!(error.to_string().contains("too large"), "{error:#}");
+}
+
+fn tracks() -> serde_json::Value {
+    json!([
+        { "id": 1, "type": "audio", "lang": "jpn", "selected": true },
+        { "id": 1, "type": "sub", "lang": "eng", "title": "Signs & Songs", "forced": true },
+        { "id": 2, "type": "sub", "lang": "eng", "title": "English [SDH]" },
+        { "id": 3, "type": "sub", "lang": "en", "title": "Full", "default": true },
+        { "id": 4, "type": "sub", "lang": "fre", "hearing-impaired": true },
+        { "id": 5, "type": "sub", "lang": "jpn", "forced": true },
+        { "id": 6, "type": "sub", "lang": "ger", "image": true, "external": true },
+    ])
+}
+
+fn selection(
+    languages: &[&str],
+    audio: Option<&str>,
+    forced: ForcedSubtitles,
+    sdh: bool,
+) -> Option<SubtitleSelection> {
+    select(
+        &parse_tracks(&tracks()),
+        audio,
+        &config(languages, forced, sdh),
+    )
+}
+
+#[test]
+fn parse() {
+    let tracks = parse_tracks(&tracks());
+    assert_eq!(tracks.len(), 6);
+
+    let sdh = tracks.iter().map(|track| track.sdh).collect::<Vec<_>>();
+    assert_eq!(sdh, [false, true, false, true, false, false]);
+
+    assert!(tracks[5].image && tracks[5].external);
+    assert_eq!(audio_language(&self::tracks()).as_deref(), Some("jpn"));
+    assert!(parse_tracks(&json!(null)).is_empty());
+}
+
+#[test]
+fn selection_rules() {
+    use ForcedSubtitles::{Always, Auto, Never};
+    use SubtitleSelection::{Off, Track};
+
+    // Without preferred languages, mpv and the web UI decide
+    assert_eq!(selection(&[], Some("jpn"), Auto, false), None);
+
+    // Full subtitles first, the default one among them, SDH ones when asked for
+    assert_eq!(selection(&["en"], Some("jpn"), Auto, false), Some(Track(3)));
+    assert_eq!(selection(&["en"], Some("jpn"), Auto, true), Some(Track(2)));
+    assert_eq!(
+        selection(&["en"], Some("jpn"), Always, false),
+        Some(Track(1))
+    );
+    assert_eq!(selection(&["en"], None, Never, false), Some(Track(3)));
+
+    // Languages are tried in order, unknown ones are skipped
+    assert_eq!(
+        selection(&["it", "fr", "en"], Some("jpn"), Auto, false),
+        Some(Track(4))
+    );
+    assert_eq!(selection(&["it"], Some("jpn"), Auto, false), None);
+
+    // Audio in a preferred language only gets its forced subtitles
+    assert_eq!(
+        selection(&["ja", "en"], Some("jpn"), Auto, false),
+        Some(Track(5))
+    );
+    assert_eq!(selection(&["ja"], Some("ja"), Never, false), Some(Off));
+    assert_eq!(selection(&["fr"], Some("fre"), Auto, false), Some(Off));
+}
@@ -0,0 +1,143 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    config::{ForcedSubtitles, SubtitlesConfig},
+    player::languages::language_matches,
+};
+
+// Words of track titles marking subtitles for the deaf and hard of hearing
+const SDH_MARKERS: &[&str] = &["sdh", "cc", "hi"];
+
+/// A subtitle track of mpv's `track-list`
+#[derive(Serialize, Debug, Clone)]
+pub struct SubtitleTrack {
+    pub id: i64,
+    pub lang: Option<String>,
+    pub title: Option<String>,
+    pub codec: Option<String>,
+    /// Bitmap subtitles (PGS, VobSub) can't be styled
+    pub image: bool,
+    pub external: bool,
+    pub external_filename: Option<String>,
+    pub forced: bool,
+    pub default: bool,
+    pub sdh: bool,
+    pub selected: bool,
+}
+
+impl SubtitleTrack {
+    fn parse(track: &Value) -> Option<Self> {
+        if track.get("type").and_then(Value::as_str) != Some("sub") {
+            return None;
+        }
+
+        let string = |key: &str| track.get(key).and_then(Value::as_str).map(str::to_owned);
+        let flag = |key: &str| track.get(key).and_then(Value::as_bool).unwrap_or_default();
+
+        let title = string("title");
+        let sdh = flag("hearing-impaired")
+            || title.as_deref().is_some_and(|title| {
+                let title = title.to_lowercase();
+                title.contains("hearing impaired")
+                    || title
+                        .split(|c: char| !c.is_alphanumeric())
+                        .any(|word| SDH_MARKERS.contains(&word))
+            });
+
+        Some(Self {
+            id: track.get("id").and_then(Value::as_i64)?,
+            lang: string("lang"),
+            title,
+            codec: string("codec"),
+            image: flag("image"),
+            external: flag("external"),
+            external_filename: string("external-filename"),
+            forced: flag("forced"),
+            default: flag("default"),
+            sdh,
+            selected: flag("selected"),
+        })
+    }
+
+    fn lang_matches(&self, language: &str) -> bool {
+        self.lang
+            .as_deref()
+            .is_some_and(|lang| language_matches(lang, language))
+    }
+}
+
+pub fn parse_tracks(track_list: &Value) -> Vec<SubtitleTrack> {
+    track_list
+        .as_array()
+        .map(|tracks| tracks.iter().filter_map(SubtitleTrack::parse).collect())
+        .unwrap_or_default()
+}
+
+/// Language of the selected audio track
+pub fn audio_language(track_list: &Value) -> Option<String> {
+    track_list.as_array()?.iter().find_map(|track| {
+        let selected = track.get("selected").and_then(Value::as_bool) == Some(true);
+        let audio = track.get("type").and_then(Value::as_str) == Some("audio");
+
+        (selected && audio)
+            .then(|| track.get("lang").and_then(Value::as_str).map(str::to_owned))
+            .flatten()
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleSelection {
+    Track(i64),
+    Off,
+}
+
+/// Picks the subtitles of a file from the preferred languages,
+/// `None` leaves the choice of mpv and of the web UI untouched
+pub fn select(
+    tracks: &[SubtitleTrack],
+    audio_language: Option<&str>,
+    config: &SubtitlesConfig,
+) -> Option<SubtitleSelection> {
+    if config.languages.is_empty() {
+        return None;
+    }
+
+    // Audio in a preferred language only needs the forced subtitles of that language
+    if let Some(audio_language) = audio_language
+        && config
+            .languages
+            .iter()
+            .any(|language| language_matches(language, audio_language))
+    {
+        let forced = tracks
+            .iter()
+            .find(|track| track.forced && track.lang_matches(audio_language))
+            .filter(|_| config.forced != ForcedSubtitles::Never);
+
+        return Some(forced.map_or(SubtitleSelection::Off, |track| {
+            SubtitleSelection::Track(track.id)
+        }));
+    }
+
+    config.languages.iter().find_map(|language| {
+        tracks
+            .iter()
+            .filter(|track| track.lang_matches(language))
+            .filter(|track| !track.forced || config.forced != ForcedSubtitles::Never)
+            .min_by_key(|track| {
+                let forced_rank = match config.forced {
+                    ForcedSubtitles::Always => !track.forced,
+                    _ => track.forced,
+                };
+
+                (
+                    forced_rank,
+                    track.sdh != config.sdh,
+                    !track.default,
+                    track.id,
+                )
+            })
+            .map(|track| SubtitleSelection::Track(track.id))
+    })
+}
@@ -0,0 +1,178 @@
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+use tempfile::TempDir;
+
+use super::{MpvProperty, MpvPropertyType, MpvPropertyValue, Player, PlayerEvent};
+use crate::config::{AppConfig, DEFAULT_PROFILE, PlayerConfig, Profile};
+
+// Generated by ffmpeg's lavfi, no media file is needed
+const TEST_CLIP: &str = "av://lavfi:testsrc=duration=1:size=64x64:rate=10";
+const LONG_TEST_CLIP: &str = "av://lavfi:testsrc=duration=30:size=64x64:rate=10";
+const TEST_SUBTITLES: &str = "1\n00:00:00,000 --> 00:00:30,000\nHello\n";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn property(name: &str, value: Value) -> MpvProperty {
+    MpvProperty(name.to_owned(), Some(value))
+}
+
+#[test]
+fn property_values() {
+    let flag = property("pause", json!("yes")).value(MpvPropertyType::Flag);
+    assert!(matches!(flag, Ok(MpvPropertyValue::Flag(true))));
+
+    let int = property("volume", json!(50.0)).value(MpvPropertyType::Int64);
+    assert!(matches!(int, Ok(MpvPropertyValue::Int64(50))));
+
+    let double = property("speed", json!("1.5")).value(MpvPropertyType::Double);
+    assert!(matches!(double, Ok(MpvPropertyValue::Double(1.5))));
+
+    let list = property("sub-auto", json!(["fuzzy"])).value(MpvPropertyType::Node);
+    assert!(matches!(list, Ok(MpvPropertyValue::String(_))));
+
+    assert!(
+        property("pause", json!(1))
+            .value(MpvPropertyType::Flag)
+            .is_err()
+    );
+    assert!(
+        property("volume", json!(50.5))
+            .value(MpvPropertyType::Int64)
+            .is_err()
+    );
+    assert!(
+        MpvProperty("pause".to_owned(), None)
+            .value(MpvPropertyType::Flag)
+            .is_err()
+    );
+}
+
+#[test]
+fn property_serialization() {
+    assert_eq!(
+        json!(property("time-pos", json!(1.5))),
+        json!({ "name": "time-pos", "data": 1.5 })
+    );
+    assert_eq!(
+        json!(MpvProperty("duration".to_owned(), None)),
+        json!({ "name": "duration" })
+    );
+}
+
+/// Player without a window, video or audio output
+fn headless_player(data_dir: &TempDir) -> Player {
+    let profile = Profile::new(DEFAULT_PROFILE, data_dir.path(), data_dir.path());
+    let mut config = PlayerConfig::new(&profile, &AppConfig::default());
+    config.headless = true;
+
+    Player::new(config)
+}
+
+/// Polls the events of the player until `predicate` matches one of them
+fn wait_for(player: &mut Player, mut predicate: impl FnMut(&PlayerEvent) -> bool) -> bool {
+    let start = Instant::now();
+    let mut found = false;
+
+    while !found && start.elapsed() < TIMEOUT {
+        player.events(|event| found |= predicate(&event));
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    found
+}
+
+#[test]
+fn headless_playback() {
+    let data_dir = TempDir::new().unwrap();
+    let mut player = headless_player(&data_dir);
+
+    player.observe_property("pause".to_owned());
+    player
+        .try_command("loadfile".to_owned(), vec![TEST_CLIP.to_owned()])
+        .unwrap();
+
+    assert!(wait_for(&mut player, |event| matches!(
+        event,
+        PlayerEvent::Start
+    )));
+
+    player.set_property(property("pause", json!(true))).unwrap();
+    assert_eq!(
+        player.get_property("pause".to_owned()).unwrap().1,
+        Some(json!(true))
+    );
+    assert!(wait_for(&mut player, |event| matches!(
+        event,
+        PlayerEvent::PropertyChange(MpvProperty(name, Some(Value::Bool(true)))) if name == "pause"
+    )));
+
+    player.set_property(property("pause", json!("no"))).unwrap();
+    assert!(wait_for(&mut player, |event| matches!(
+        event,
+        PlayerEvent::Stop(None)
+    )));
+}
+
+#[test]
+fn headless_errors() {
+    let data_dir = TempDir::new().unwrap();
+    let player = headless_player(&data_dir);
+
+    assert!(
+        player
+            .try_command("not-a-command".to_owned(), vec![])
+            .is_err()
+    );
+    assert!(
+        player
+            .set_property(property("volume", json!("loud")))
+            .is_err()
+    );
+    assert!(player.get_property("not-a-property".to_owned()).is_err());
+}
+
+#[test]
+fn headless_choice_properties() {
+    let data_dir = TempDir::new().unwrap();
+    let mut player = headless_player(&data_dir);
+
+    player
+        .try_command("loadfile".to_owned(), vec![LONG_TEST_CLIP.to_owned()])
+        .unwrap();
+    assert!(wait_for(&mut player, |event| matches!(
+        event,
+        PlayerEvent::Start
+    )));
+    player.set_property(property("pause", json!(true))).unwrap();
+
+    let subtitles = data_dir.path().join("test.srt");
+    fs::write(&subtitles, TEST_SUBTITLES).unwrap();
+    player
+        .try_command(
+            "sub-add".to_owned(),
+            vec![
+                subtitles.to_string_lossy().into_owned(),
+                "select".to_owned(),
+            ],
+        )
+        .unwrap();
+
+    let sid = |player: &Player| player.get_property("sid".to_owned()).unwrap().1;
+
+    // The type is cached while a track is selected, `no` must still be accepted
+    assert_eq!(sid(&player), Some(json!(1)));
+    player.set_property(property("sid", json!("no"))).unwrap();
+    assert_ne!(sid(&player), Some(json!(1)));
+
+    player.set_property(property("sid", json!(1))).unwrap();
+    assert_eq!(sid(&player), Some(json!(1)));
+
+    player.set_property(property("sid", json!(false))).unwrap();
+    assert_ne!(sid(&player), Some(json!(1)));
+
+    player.set_property(property("sid", json!("1"))).unwrap();
+    assert_eq!(sid(&player), Some(json!(1)));
+}
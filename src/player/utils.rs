@@ -0,0 +1,60 @@
+use libmpv2::mpv_node::{MpvNode, MpvNodeValue};
+use serde_json::{Map, Number, Value};
+
+use super::MpvPropertyType;
+
+/// Converts an mpv node (and all of its children) into a JSON value
+pub fn node_to_json(node: &MpvNode) -> Value {
+    match node.value() {
+        Ok(MpvNodeValue::String(value)) => Value::String(value.to_owned()),
+        Ok(MpvNodeValue::Flag(value)) => Value::Bool(value),
+        Ok(MpvNodeValue::Int64(value)) => Value::Number(value.into()),
+        Ok(MpvNodeValue::Double(value)) => Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        Ok(MpvNodeValue::Array(array)) => {
+            Value::Array(array.map(|node| node_to_json(&node)).collect())
+        }
+        Ok(MpvNodeValue::Map(map)) => Value::Object(
+            map.map(|(key, node)| (key.to_owned(), node_to_json(&node)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Ok(MpvNodeValue::None) | Err(_) => Value::Null,
+    }
+}
+
+/// Returns the type of the value held by an mpv node
+pub fn node_type(node: &MpvNode) -> MpvPropertyType {
+    match node.value() {
+        Ok(MpvNodeValue::String(_)) => MpvPropertyType::String,
+        Ok(MpvNodeValue::Flag(_)) => MpvPropertyType::Flag,
+        Ok(MpvNodeValue::Int64(_)) => MpvPropertyType::Int64,
+        Ok(MpvNodeValue::Double(_)) => MpvPropertyType::Double,
+        Ok(MpvNodeValue::Array(_)) | Ok(MpvNodeValue::Map(_)) => MpvPropertyType::Node,
+        Ok(MpvNodeValue::None) | Err(_) => MpvPropertyType::Unknown,
+    }
+}
+
+/// Formats a JSON value using mpv's option syntax,
+/// lists are comma separated and maps are written as `key=value` pairs
+pub fn json_to_option_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(value) => match value {
+            true => "yes".to_owned(),
+            false => "no".to_owned(),
+        },
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => value.to_owned(),
+        Value::Array(values) => values
+            .iter()
+            .map(json_to_option_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| format!("{key}={}", json_to_option_string(value)))
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod tests;
+mod torrent;
+
+use std::path::Path;
+
+use url::Url;
+
+/// Extensions of the local files played directly, playlists included
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "avi", "webm", "mov", "m4v", "ts", "m2ts", "mpg", "mpeg", "wmv", "flv", "ogv",
+    "3gp", "mp3", "flac", "m4a", "aac", "ogg", "opus", "wav", "m3u", "m3u8",
+];
+
+const TORRENT_EXTENSION: &str = "torrent";
+
+/// Schemes of the links `route` accepts
+pub const SCHEMES: &[&str] = &["stremio", "magnet", "http", "https", "file"];
+
+/// What an opened link or file turns into
+#[derive(Debug, PartialEq)]
+pub enum Route {
+    /// Sent to the web UI as `open-media`: `stremio://` deeplinks and magnet links
+    OpenMedia(String),
+    /// Loaded by the player right away: local files and directories, stream urls
+    Play(String),
+}
+
+/// Makes a relative path absolute, the running instance may have another working directory
+pub fn resolve(input: &str, current_dir: &Path) -> String {
+    let path = Path::new(input);
+    match path.is_relative() && current_dir.join(path).exists() {
+        true => current_dir.join(path).to_string_lossy().into_owned(),
+        false => input.to_owned(),
+    }
+}
+
+/// Routes a deeplink, a url or a path given to `--open`
+pub fn route(input: &str) -> Result<Route, String> {
+    let input = input.trim();
+
+    // Paths first, `C:` or `a:b.mkv` would parse as urls
+    let path = Path::new(input);
+    if path.is_absolute() && path.exists() {
+        return route_path(path);
+    }
+
+    let url = Url::parse(input).map_err(|_| format!("Cannot open '{input}'"))?;
+    match url.scheme() {
+        "stremio" | "magnet" => Ok(Route::OpenMedia(input.to_owned())),
+        // The server fetches remote torrent files
+        "http" | "https" if has_extension(Path::new(url.path()), &[TORRENT_EXTENSION]) => {
+            Ok(Route::OpenMedia(input.to_owned()))
+        }
+        "http" | "https" => Ok(Route::Play(input.to_owned())),
+        "file" => {
+            let path = url
+                .to_file_path()
+                .map_err(|_| format!("Invalid file url '{input}'"))?;
+            match path.exists() {
+                true => route_path(&path),
+                false => Err(format!("File not found: {}", path.display())),
+            }
+        }
+        scheme => Err(format!("Unsupported link scheme '{scheme}'")),
+    }
+}
+
+fn route_path(path: &Path) -> Result<Route, String> {
+    let display = || path.to_string_lossy().into_owned();
+
+    // mpv plays the files of a directory as a playlist
+    if path.is_dir() {
+        return Ok(Route::Play(display()));
+    }
+
+    // The web UI can't read local files, the torrent is opened from its magnet link
+    if has_extension(path, &[TORRENT_EXTENSION]) {
+        return torrent::magnet(path)
+            .map(Route::OpenMedia)
+            .map_err(|e| format!("{e:#}: {}", path.display()));
+    }
+
+    match has_extension(path, MEDIA_EXTENSIONS) {
+        true => Ok(Route::Play(display())),
+        false => Err(format!("Unsupported file type: {}", path.display())),
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extensions
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(extension))
+        })
+}
@@ -0,0 +1,129 @@
+use std::fs;
+
+use tempfile::TempDir;
+
+use super::{Route, resolve, route, torrent::magnet_from_bytes};
+
+const INFO: &str = "d6:lengthi1024e4:name9:Movie.mkv12:piece lengthi16384e6:pieces20:\
+                    aaaaaaaaaaaaaaaaaaaae";
+const INFO_HASH: &str = "6a774f331d46d3a5165a5322eee4c92f54a88f14";
+
+/// A single file torrent with a tracker listed twice
+fn torrent() -> String {
+    format!(
+        "d8:announce22:udp://tracker.test:80/13:announce-listll22:udp://tracker.test:80/e\
+         l20:http://other.test/anee4:info{INFO}e"
+    )
+}
+
+#[test]
+fn deeplinks() {
+    for link in [
+        "stremio:///detail/movie/tt0111161",
+        "magnet:?xt=urn:btih:6a774f331d46d3a5165a5322eee4c92f54a88f14",
+        "https://example.com/files/Movie.torrent",
+    ] {
+        assert_eq!(route(link), Ok(Route::OpenMedia(link.to_owned())), "{link}");
+    }
+}
+
+#[test]
+fn stream_urls() {
+    for url in [
+        "http://example.com/stream",
+        "https://example.com/Movie.mkv?token=1",
+    ] {
+        assert_eq!(route(url), Ok(Route::Play(url.to_owned())), "{url}");
+    }
+}
+
+#[test]
+fn local_files() {
+    let dir = TempDir::new().unwrap();
+    let movie = dir.path().join("Movie.MKV");
+    fs::write(&movie, "").unwrap();
+    let movie = movie.to_string_lossy().into_owned();
+
+    assert_eq!(route(&movie), Ok(Route::Play(movie.clone())));
+    assert_eq!(
+        route(&format!("file://{movie}")),
+        Ok(Route::Play(movie.clone()))
+    );
+
+    let directory = dir.path().to_string_lossy().into_owned();
+    assert_eq!(route(&directory), Ok(Route::Play(directory.clone())));
+
+    let text = dir.path().join("notes.txt");
+    fs::write(&text, "").unwrap();
+    assert!(route(&text.to_string_lossy()).is_err());
+
+    assert!(route(&dir.path().join("missing.mkv").to_string_lossy()).is_err());
+    assert!(route(&format!("file://{directory}/missing.mkv")).is_err());
+}
+
+#[test]
+fn torrent_files() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("Movie.torrent");
+    fs::write(&file, torrent()).unwrap();
+
+    let expected = format!(
+        "magnet:?xt=urn:btih:{INFO_HASH}&dn=Movie.mkv\
+         &tr=udp%3A%2F%2Ftracker.test%3A80%2F&tr=http%3A%2F%2Fother.test%2Fan"
+    );
+    assert_eq!(
+        route(&file.to_string_lossy()),
+        Ok(Route::OpenMedia(expected))
+    );
+
+    fs::write(&file, "not a torrent").unwrap();
+    assert!(route(&file.to_string_lossy()).is_err());
+}
+
+#[test]
+fn magnet_links() {
+    let link = magnet_from_bytes(format!("d4:info{INFO}e").as_bytes()).unwrap();
+    assert_eq!(
+        link,
+        format!("magnet:?xt=urn:btih:{INFO_HASH}&dn=Movie.mkv")
+    );
+
+    assert!(magnet_from_bytes(b"d8:announce3:abce").is_err());
+    assert!(magnet_from_bytes(b"d4:infod").is_err());
+    assert!(magnet_from_bytes(b"").is_err());
+
+    // Huge lengths and deep nesting are rejected instead of panicking
+    let length = format!("d4:info{}:ae", usize::MAX);
+    assert!(magnet_from_bytes(length.as_bytes()).is_err());
+
+    let nested = |depth| format!("d4:info{}{}e", "l".repeat(depth), "e".repeat(depth));
+    assert!(magnet_from_bytes(nested(63).as_bytes()).is_ok());
+    assert!(magnet_from_bytes(nested(64).as_bytes()).is_err());
+    assert!(magnet_from_bytes(nested(1_000_000).as_bytes()).is_err());
+}
+
+#[test]
+fn unsupported() {
+    for input in [
+        "",
+        "Movie.mkv",
+        "ftp://example.com/Movie.mkv",
+        "javascript:alert(1)",
+    ] {
+        assert!(route(input).is_err(), "{input}");
+    }
+}
+
+#[test]
+fn relative_paths() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("Movie.mkv"), "").unwrap();
+
+    let absolute = dir.path().join("Movie.mkv").to_string_lossy().into_owned();
+    assert_eq!(resolve("Movie.mkv", dir.path()), absolute);
+    assert_eq!(resolve("Other.mkv", dir.path()), "Other.mkv");
+    assert_eq!(
+        resolve("stremio:///detail", dir.path()),
+        "stremio:///detail"
+    );
+}
@@ -0,0 +1,172 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, bail};
+use itertools::Itertools;
+use sha1_smol::Sha1;
+use url::form_urlencoded::byte_serialize;
+
+// Torrent files are a few hundred KiB at most, anything bigger isn't one
+const MAX_SIZE: u64 = 10 * 1024 * 1024;
+// Torrent files nest a few levels deep, deeper lists and dicts would overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// A bencoded value, along with the bytes it was read from
+struct Node<'a> {
+    value: Value<'a>,
+    raw: &'a [u8],
+}
+
+enum Value<'a> {
+    Integer,
+    Bytes(&'a [u8]),
+    List(Vec<Node<'a>>),
+    Dict(Vec<(&'a [u8], Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    fn get(&self, key: &str) -> Option<&Node<'a>> {
+        match &self.value {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(name, _)| *name == key.as_bytes())
+                .map(|(_, node)| node),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&'a str> {
+        match self.value {
+            Value::Bytes(bytes) => std::str::from_utf8(bytes).ok(),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> &[Node<'a>] {
+        match &self.value {
+            Value::List(items) => items,
+            _ => &[],
+        }
+    }
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn node(&mut self, depth: usize) -> Option<Node<'a>> {
+        let start = self.position;
+
+        let value = match *self.data.get(self.position)? {
+            b'i' => {
+                self.position += 1;
+                self.until(b'e')?;
+                Value::Integer
+            }
+            b'l' if depth < MAX_DEPTH => {
+                self.position += 1;
+                let mut items = vec![];
+                while self.peek()? != b'e' {
+                    items.push(self.node(depth + 1)?);
+                }
+                self.position += 1;
+                Value::List(items)
+            }
+            b'd' if depth < MAX_DEPTH => {
+                self.position += 1;
+                let mut entries = vec![];
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    entries.push((key, self.node(depth + 1)?));
+                }
+                self.position += 1;
+                Value::Dict(entries)
+            }
+            b'l' | b'd' => return None,
+            _ => Value::Bytes(self.bytes()?),
+        };
+
+        Some(Node {
+            value,
+            raw: &self.data[start..self.position],
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).copied()
+    }
+
+    /// Reads up to `end` and skips it
+    fn until(&mut self, end: u8) -> Option<&'a [u8]> {
+        let length = self.data[self.position..]
+            .iter()
+            .position(|byte| *byte == end)?;
+        let value = &self.data[self.position..self.position + length];
+        self.position += length + 1;
+
+        Some(value)
+    }
+
+    /// Reads a string written `<length>:<bytes>`
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let length = std::str::from_utf8(self.until(b':')?)
+            .ok()?
+            .parse::<usize>()
+            .ok()?;
+        let value = self
+            .data
+            .get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+
+        Some(value)
+    }
+}
+
+/// Magnet link of a torrent file, with its name and trackers
+pub fn magnet(file: &Path) -> anyhow::Result<String> {
+    let size = fs::metadata(file)
+        .context("Failed to read torrent file")?
+        .len();
+    if size > MAX_SIZE {
+        bail!("Torrent file is too big");
+    }
+
+    let data = fs::read(file).context("Failed to read torrent file")?;
+    magnet_from_bytes(&data)
+}
+
+pub fn magnet_from_bytes(data: &[u8]) -> anyhow::Result<String> {
+    let mut parser = Parser { data, position: 0 };
+    let torrent = parser.node(0).context("Invalid torrent file")?;
+    let info = torrent.get("info").context("Torrent file without info")?;
+
+    // The info hash identifies the torrent, it's computed on the bytes as written in the file
+    let info_hash = Sha1::from(info.raw).digest().to_string();
+    let mut link = format!("magnet:?xt=urn:btih:{info_hash}");
+
+    if let Some(name) = info.get("name").and_then(Node::as_str) {
+        link.push_str(&format!("&dn={}", encode(name)));
+    }
+
+    let announce = torrent.get("announce").and_then(Node::as_str);
+    let announce_list = torrent
+        .get("announce-list")
+        .map(Node::as_list)
+        .unwrap_or_default()
+        .iter()
+        .flat_map(Node::as_list)
+        .filter_map(Node::as_str);
+
+    announce
+        .into_iter()
+        .chain(announce_list)
+        .unique()
+        .for_each(|tracker| link.push_str(&format!("&tr={}", encode(tracker))));
+
+    Ok(link)
+}
+
+fn encode(value: &str) -> String {
+    byte_serialize(value.as_bytes()).collect()
+}
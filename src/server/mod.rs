@@ -0,0 +1,144 @@
+mod supervisor;
+#[cfg(test)]
+mod tests;
+
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::anyhow;
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use serde::{Deserialize, Serialize};
+use supervisor::Supervisor;
+use tracing::error;
+
+use crate::{
+    config::ServerConfig,
+    shared::waker::{self, WakingSender},
+};
+
+// The streaming server always listens on this port, the web UI connects to it
+const SERVER_PORT: u16 = 11470;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+pub enum ServerStatus {
+    Stopped,
+    Starting {
+        attempt: u32,
+    },
+    Running {
+        pid: u32,
+    },
+    Restarting {
+        attempt: u32,
+        delay: u64,
+        reason: String,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+pub enum ServerEvent {
+    Status(ServerStatus),
+}
+
+pub struct Server {
+    config: ServerConfig,
+    port: u16,
+    sender: WakingSender<ServerEvent>,
+    receiver: Receiver<ServerEvent>,
+    status: Arc<Mutex<ServerStatus>>,
+    supervisor: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl Server {
+    pub fn new(config: ServerConfig) -> Self {
+        Self::with_port(config, SERVER_PORT)
+    }
+
+    /// Probes another port than the one of the streaming server
+    fn with_port(config: ServerConfig, port: u16) -> Self {
+        let (sender, receiver) = waker::channel::<ServerEvent>();
+
+        Self {
+            config,
+            port,
+            sender,
+            receiver,
+            status: Arc::new(Mutex::new(ServerStatus::Stopped)),
+            supervisor: None,
+        }
+    }
+
+    /// Starts the server and the thread supervising it, an error to spawn
+    /// the first process is returned and reported as a failed status
+    pub fn start(&mut self, dev: bool) -> anyhow::Result<()> {
+        let (stop_sender, stop_receiver) = unbounded::<()>();
+
+        let supervisor = Supervisor::new(
+            self.config.clone(),
+            self.port,
+            dev,
+            self.sender.clone(),
+            self.status.clone(),
+            stop_receiver,
+        );
+
+        let child = match supervisor.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.set_status(ServerStatus::Failed {
+                    reason: format!("{e:#}"),
+                });
+                return Err(e);
+            }
+        };
+        let handle = thread::spawn(move || supervisor.run(child));
+
+        self.supervisor = Some((stop_sender, handle));
+
+        Ok(())
+    }
+
+    /// Stops the server gracefully and waits for the supervisor to exit
+    pub fn stop(&mut self) -> anyhow::Result<()> {
+        if let Some((stop_sender, handle)) = self.supervisor.take() {
+            drop(stop_sender);
+            handle
+                .join()
+                .map_err(|_| anyhow!("Server supervisor panicked"))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or(ServerStatus::Stopped)
+    }
+
+    pub fn events<F: FnMut(ServerEvent)>(&self, handler: F) {
+        self.receiver.try_iter().for_each(handler);
+    }
+
+    fn set_status(&self, status: ServerStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status.clone();
+        }
+
+        self.sender.send(ServerEvent::Status(status)).ok();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        if let Err(e) = self.stop() {
+            error!(target: "server", "Failed to stop server: {e}");
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use reqwest::blocking::Client;
+use tracing::{debug, error, info, warn};
+
+use super::{ServerEvent, ServerStatus};
+use crate::{config::ServerConfig, logging::RotatingLog, shared::waker::WakingSender};
+
+// How often the process is checked for exit
+const TICK: Duration = Duration::from_millis(250);
+// How often the server is probed while it is starting and once it is running
+const STARTUP_PROBE_INTERVAL: Duration = Duration::from_millis(500);
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+// A server that never answers within this delay is restarted
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+// Consecutive failed probes after which a running server is restarted
+const MAX_PROBE_FAILURES: u32 = 3;
+// Time between SIGTERM and SIGKILL
+const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(5);
+// Restart delays grow exponentially from the initial delay up to the maximum
+const INITIAL_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+// A server running for that long is considered stable and resets the backoff
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+// The log is rotated once it grows past this size
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+// Number of rotated logs kept next to the current one (server.log.1, server.log.2, ...)
+const MAX_ROTATED_LOGS: usize = 3;
+
+enum Exit {
+    Stopped,
+    Failed(String, Duration),
+}
+
+pub struct Supervisor {
+    config: ServerConfig,
+    port: u16,
+    dev: bool,
+    log: Arc<RotatingLog>,
+    client: Option<Client>,
+    sender: WakingSender<ServerEvent>,
+    status: Arc<Mutex<ServerStatus>>,
+    stop: Receiver<()>,
+}
+
+impl Supervisor {
+    pub fn new(
+        config: ServerConfig,
+        port: u16,
+        dev: bool,
+        sender: WakingSender<ServerEvent>,
+        status: Arc<Mutex<ServerStatus>>,
+        stop: Receiver<()>,
+    ) -> Self {
+        let log = Arc::new(RotatingLog::new(
+            &config.log_file,
+            MAX_LOG_SIZE,
+            MAX_ROTATED_LOGS,
+        ));
+
+        let client = Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .inspect_err(|e| error!(target: "server", "Failed to create health probe client: {e}"))
+            .ok();
+
+        Self {
+            config,
+            port,
+            dev,
+            log,
+            client,
+            sender,
+            status,
+            stop,
+        }
+    }
+
+    pub fn spawn(&self) -> anyhow::Result<Child> {
+        let mut child = Command::new(&self.config.node)
+            .envs(&self.config.env)
+            .env("NO_CORS", (self.dev as i32).to_string())
+            .arg(self.config.file.as_os_str())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start server")?;
+
+        if let Some(stdout) = child.stdout.take() {
+            self.capture(stdout, "stdout");
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            self.capture(stderr, "stderr");
+        }
+
+        info!(target: "server", "Started server with pid {}", child.id());
+
+        Ok(child)
+    }
+
+    fn capture<T: Read + Send + 'static>(&self, output: T, stream: &'static str) {
+        let log = self.log.clone();
+
+        thread::spawn(move || {
+            for line in BufReader::new(output).lines().map_while(Result::ok) {
+                debug!(target: "server", "{}", line);
+                log.write(stream, &line);
+            }
+        });
+    }
+
+    /// Watches the server and restarts it until a stop is requested
+    pub fn run(self, mut child: Child) {
+        let mut attempt = 0;
+
+        loop {
+            self.set_status(ServerStatus::Starting { attempt });
+
+            let (reason, uptime) = match self.watch(&mut child) {
+                Exit::Stopped => {
+                    terminate(&mut child);
+                    self.set_status(ServerStatus::Stopped);
+                    return;
+                }
+                Exit::Failed(reason, uptime) => (reason, uptime),
+            };
+
+            terminate(&mut child);
+
+            attempt = match uptime >= STABLE_UPTIME {
+                true => 1,
+                false => attempt + 1,
+            };
+
+            let delay = INITIAL_RESTART_DELAY
+                .saturating_mul(2u32.saturating_pow(attempt - 1))
+                .min(MAX_RESTART_DELAY);
+
+            warn!(target: "server", "{reason}, restarting in {}s", delay.as_secs());
+            self.log.write("supervisor", &reason);
+
+            self.set_status(ServerStatus::Restarting {
+                attempt,
+                delay: delay.as_secs(),
+                reason,
+            });
+
+            if self.sleep(delay) {
+                self.set_status(ServerStatus::Stopped);
+                return;
+            }
+
+            child = match self.spawn() {
+                Ok(child) => child,
+                Err(e) => {
+                    error!(target: "server", "{e}");
+                    self.set_status(ServerStatus::Failed {
+                        reason: e.to_string(),
+                    });
+                    return;
+                }
+            };
+        }
+    }
+
+    fn watch(&self, child: &mut Child) -> Exit {
+        let started = Instant::now();
+        let mut healthy = false;
+        let mut failures = 0;
+        let mut last_probe: Option<Instant> = None;
+
+        loop {
+            if self.sleep(TICK) {
+                return Exit::Stopped;
+            }
+
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    return Exit::Failed(format!("Server exited with {status}"), started.elapsed());
+                }
+                Err(e) => {
+                    return Exit::Failed(format!("Failed to check server: {e}"), started.elapsed());
+                }
+                Ok(None) => {}
+            }
+
+            let interval = match healthy {
+                true => PROBE_INTERVAL,
+                false => STARTUP_PROBE_INTERVAL,
+            };
+
+            if last_probe.is_some_and(|last_probe| last_probe.elapsed() < interval) {
+                continue;
+            }
+
+            last_probe = Some(Instant::now());
+
+            match (self.probe(), healthy) {
+                (true, false) => {
+                    healthy = true;
+                    info!(target: "server", "Server is up on port {}", self.port);
+                    self.set_status(ServerStatus::Running { pid: child.id() });
+                }
+                (true, true) => failures = 0,
+                (false, true) => {
+                    failures += 1;
+
+                    if failures >= MAX_PROBE_FAILURES {
+                        return Exit::Failed(
+                            format!("Server did not answer {failures} health probes"),
+                            started.elapsed(),
+                        );
+                    }
+                }
+                (false, false) => {
+                    if started.elapsed() >= STARTUP_TIMEOUT {
+                        return Exit::Failed(
+                            format!(
+                                "Server did not come up within {}s",
+                                STARTUP_TIMEOUT.as_secs()
+                            ),
+                            started.elapsed(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Any HTTP response means the server is up, whatever its status
+    fn probe(&self) -> bool {
+        let url = format!("http://127.0.0.1:{}/", self.port);
+
+        self.client
+            .as_ref()
+            .is_some_and(|client| client.get(url).send().is_ok())
+    }
+
+    /// Sleeps for `duration`, returns `true` if a stop was requested in the meantime
+    fn sleep(&self, duration: Duration) -> bool {
+        !matches!(
+            self.stop.recv_timeout(duration),
+            Err(RecvTimeoutError::Timeout)
+        )
+    }
+
+    fn set_status(&self, status: ServerStatus) {
+        if let Ok(mut current) = self.status.lock() {
+            *current = status.clone();
+        }
+
+        self.sender.send(ServerEvent::Status(status)).ok();
+    }
+}
+
+/// Asks the server to exit with SIGTERM, and kills it if it is still running after the grace period
+fn terminate(child: &mut Child) {
+    if let Ok(Some(_)) = child.try_wait() {
+        return;
+    }
+
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < TERMINATE_GRACE_PERIOD {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+
+        thread::sleep(TICK);
+    }
+
+    warn!(target: "server", "Server did not exit after SIGTERM, killing it");
+
+    if let Err(e) = child.kill() {
+        error!(target: "server", "Failed to kill server process: {e}");
+    }
+
+    child.wait().ok();
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use tempfile::TempDir;
+
+use super::{Server, ServerEvent, ServerStatus};
+use crate::config::ServerConfig;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Server running a stub script with `sh` instead of node
+fn server(dir: &TempDir, script: &str, port: u16) -> Server {
+    let file = dir.path().join("server.sh");
+    fs::write(&file, script).unwrap();
+
+    let config = ServerConfig {
+        file,
+        log_file: dir.path().join("server.log"),
+        node: "sh".to_owned(),
+        env: HashMap::new(),
+    };
+
+    Server::with_port(config, port)
+}
+
+/// A port nothing listens on, the health probes fail
+fn closed_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Answers every request on a local port, like the streaming server would
+fn http_listener() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().map_while(Result::ok) {
+            // A GET request ends with an empty line
+            BufReader::new(&stream)
+                .lines()
+                .map_while(Result::ok)
+                .take_while(|line| !line.is_empty())
+                .for_each(drop);
+
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .ok();
+        }
+    });
+
+    port
+}
+
+/// Polls the statuses of the server until `predicate` matches one of them,
+/// returns the statuses received so far with the time they were received at
+fn wait_for(
+    server: &Server,
+    mut predicate: impl FnMut(&ServerStatus) -> bool,
+) -> Vec<(ServerStatus, Instant)> {
+    let start = Instant::now();
+    let mut statuses = vec![];
+
+    while start.elapsed() < TIMEOUT {
+        server.events(|ServerEvent::Status(status)| statuses.push((status, Instant::now())));
+
+        if statuses.iter().any(|(status, _)| predicate(status)) {
+            return statuses;
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    panic!("Timed out, received {statuses:?}");
+}
+
+fn wait_for_file(path: &Path) {
+    let start = Instant::now();
+    while !path.exists() && start.elapsed() < TIMEOUT {
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn restart_on_crash() {
+    let dir = TempDir::new().unwrap();
+    let mut server = server(&dir, "echo started\nexit 3\n", closed_port());
+    server.start(false).unwrap();
+
+    let statuses = wait_for(&server, |status| {
+        matches!(status, ServerStatus::Starting { attempt: 2 })
+    });
+    server.stop().unwrap();
+
+    let restarts = statuses
+        .iter()
+        .filter_map(|(status, time)| match status {
+            ServerStatus::Restarting {
+                attempt,
+                delay,
+                reason,
+            } => Some((*attempt, *delay, reason.as_str(), *time)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // The delay doubles after each crash
+    assert_eq!(restarts.len(), 2);
+    assert_eq!((restarts[0].0, restarts[0].1), (1, 1));
+    assert_eq!((restarts[1].0, restarts[1].1), (2, 2));
+    assert!(
+        restarts[0].2.contains("exit status: 3"),
+        "{}",
+        restarts[0].2
+    );
+
+    // The next process is only started once the delay is over
+    let started = |attempt| {
+        statuses
+            .iter()
+            .find(|(status, _)| *status == ServerStatus::Starting { attempt })
+            .map(|(_, time)| *time)
+            .unwrap()
+    };
+    assert!(started(1) - restarts[0].3 >= Duration::from_millis(900));
+    assert!(started(2) - restarts[1].3 >= Duration::from_millis(1900));
+
+    assert_eq!(server.status(), ServerStatus::Stopped);
+
+    let log = fs::read_to_string(dir.path().join("server.log")).unwrap();
+    assert!(log.contains("[stdout] started"), "{log}");
+    assert!(log.contains("[supervisor] Server exited"), "{log}");
+}
+
+#[test]
+fn running_once_healthy() {
+    let dir = TempDir::new().unwrap();
+    let mut server = server(&dir, "exec sleep 30\n", http_listener());
+    server.start(false).unwrap();
+
+    let statuses = wait_for(&server, |status| {
+        matches!(status, ServerStatus::Running { .. })
+    });
+    assert_eq!(statuses[0].0, ServerStatus::Starting { attempt: 0 });
+
+    // SIGTERM is enough for this one, the grace period isn't waited for
+    let stopping = Instant::now();
+    server.stop().unwrap();
+
+    assert!(stopping.elapsed() < Duration::from_secs(2));
+    assert_eq!(server.status(), ServerStatus::Stopped);
+}
+
+#[test]
+fn missing_node() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("server.js");
+    fs::write(&file, "").unwrap();
+
+    let mut server = Server::new(ServerConfig {
+        file,
+        log_file: dir.path().join("server.log"),
+        node: dir.path().join("missing-node").display().to_string(),
+        env: HashMap::new(),
+    });
+    let error = server.start(false).unwrap_err();
+
+    let mut statuses = vec![];
+    server.events(|ServerEvent::Status(status)| statuses.push(status));
+
+    let failed = ServerStatus::Failed {
+        reason: format!("{error:#}"),
+    };
+    assert_eq!(server.status(), failed);
+    assert_eq!(statuses, [failed]);
+    server.stop().unwrap();
+}
+
+#[test]
+fn terminate_then_kill() {
+    let dir = TempDir::new().unwrap();
+    let started = dir.path().join("started");
+    let terminated = dir.path().join("terminated");
+
+    // Ignores SIGTERM, only SIGKILL stops it
+    let script = format!(
+        "trap 'touch {}' TERM\ntouch {}\nwhile true; do sleep 0.1; done\n",
+        terminated.display(),
+        started.display(),
+    );
+    let mut server = server(&dir, &script, closed_port());
+    server.start(false).unwrap();
+    wait_for_file(&started);
+
+    let stopping = Instant::now();
+    server.stop().unwrap();
+
+    assert!(terminated.exists());
+    assert!(stopping.elapsed() >= Duration::from_secs(5));
+    assert_eq!(server.status(), ServerStatus::Stopped);
+}
@@ -1,5 +1,6 @@
 mod renderer;
 pub mod types;
+pub mod waker;
 
 use std::{
     num::NonZeroU32,
@@ -11,14 +12,15 @@ use glutin::{
     prelude::{NotCurrentGlContext, PossiblyCurrentGlContext},
     surface::{GlSurface, Surface, SwapInterval, WindowSurface},
 };
+pub use renderer::FrameStats;
 use renderer::Renderer;
 use tracing::warn;
 
 pub static RENDERER: RwLock<Option<Renderer>> = RwLock::new(None);
 
-pub fn create_renderer(default_size: (i32, i32), refresh_rate: u32) {
+pub fn create_renderer(default_size: (i32, i32), refresh_rate: u32, scale_factor: f64) {
     if let Ok(mut guard) = RENDERER.write() {
-        *guard = Some(Renderer::new(default_size, refresh_rate));
+        *guard = Some(Renderer::new(default_size, refresh_rate, scale_factor));
     }
 }
 
@@ -55,7 +57,7 @@ pub fn create_gl(surface: Surface<WindowSurface>, context: NotCurrentContext) {
     let swap_interval = SwapInterval::Wait(NonZeroU32::new(1).unwrap());
     surface
         .set_swap_interval(&current_context, swap_interval)
-        .map_err(|e| warn!("Failed to enable VSync: {e}"))
+        .map_err(|e| warn!(target: "renderer", "Failed to enable VSync: {e}"))
         .ok();
 
     let not_current_context = current_context
@@ -71,8 +73,15 @@ pub fn create_gl(surface: Surface<WindowSurface>, context: NotCurrentContext) {
     }
 }
 
-pub fn with_gl<T: FnMut(&Surface<WindowSurface>, &PossiblyCurrentContext)>(mut handler: T) {
-    if let Ok(surface) = GL_SURFACE.lock()
+pub fn with_gl<T: FnMut(&Surface<WindowSurface>, &PossiblyCurrentContext)>(handler: T) {
+    with_surface(&GL_SURFACE, handler);
+}
+
+fn with_surface<T: FnMut(&Surface<WindowSurface>, &PossiblyCurrentContext)>(
+    surface: &Mutex<Option<Surface<WindowSurface>>>,
+    mut handler: T,
+) {
+    if let Ok(surface) = surface.lock()
         && let Some(surface) = surface.as_ref()
         && let Ok(mut guard) = GL_CONTEXT.lock()
         && let Some(context) = guard.take()
@@ -100,3 +109,30 @@ pub fn drop_gl() {
         context.take();
     }
 }
+
+/// Surface of the picture-in-picture window, drawn with the context of the main window
+pub static PIP_SURFACE: Mutex<Option<Surface<WindowSurface>>> = Mutex::new(None);
+
+pub fn create_pip_gl(surface: Surface<WindowSurface>) {
+    if let Ok(mut guard) = PIP_SURFACE.lock() {
+        *guard = Some(surface);
+    }
+
+    // The main window keeps the VSync, waiting for both surfaces would halve the frame rate
+    with_pip_gl(|surface, context| {
+        surface
+            .set_swap_interval(context, SwapInterval::DontWait)
+            .map_err(|e| warn!(target: "renderer", "Failed to disable VSync: {e}"))
+            .ok();
+    });
+}
+
+pub fn with_pip_gl<T: FnMut(&Surface<WindowSurface>, &PossiblyCurrentContext)>(handler: T) {
+    with_surface(&PIP_SURFACE, handler);
+}
+
+pub fn drop_pip_gl() {
+    if let Ok(mut surface) = PIP_SURFACE.lock() {
+        surface.take();
+    }
+}
@@ -1,3 +1,5 @@
 pub const FRAGMENT_SRC: &str = include_str!("shader.frag");
 pub const VERTEX_SRC: &str = include_str!("shader.vert");
 pub const BYTES_PER_PIXEL: i32 = 4;
+// Pixel buffers used in turn to upload the paints of the web UI
+pub const PBO_COUNT: usize = 3;
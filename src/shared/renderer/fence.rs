@@ -0,0 +1,40 @@
+use gl::types::GLsync;
+
+// Longest wait for the GPU to release a pixel buffer, in nanoseconds
+const TIMEOUT: u64 = 100_000_000;
+
+/// GL sync object signaled once the GPU executed the commands issued before it
+#[derive(Debug)]
+pub struct Fence(GLsync);
+
+// Sync objects are only used with the shared GL context current
+unsafe impl Send for Fence {}
+unsafe impl Sync for Fence {}
+
+impl Fence {
+    pub fn new() -> Option<Self> {
+        let sync = unsafe { gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        (!sync.is_null()).then_some(Self(sync))
+    }
+
+    /// Blocks until the fence is signaled, returns whether it had to wait
+    pub fn wait(&self) -> bool {
+        unsafe {
+            let status = gl::ClientWaitSync(self.0, gl::SYNC_FLUSH_COMMANDS_BIT, 0);
+            if status == gl::ALREADY_SIGNALED || status == gl::CONDITION_SATISFIED {
+                return false;
+            }
+
+            gl::ClientWaitSync(self.0, gl::SYNC_FLUSH_COMMANDS_BIT, TIMEOUT);
+            true
+        }
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteSync(self.0);
+        }
+    }
+}
@@ -1,10 +1,16 @@
 mod constants;
+mod fence;
+mod stats;
 mod utils;
 
-use std::ptr;
+use std::{array, ptr, time::Instant};
 
-use constants::{BYTES_PER_PIXEL, FRAGMENT_SRC, VERTEX_SRC};
-use gl::types::{GLint, GLuint};
+use constants::{BYTES_PER_PIXEL, FRAGMENT_SRC, PBO_COUNT, VERTEX_SRC};
+use fence::Fence;
+use gl::types::{GLint, GLsizeiptr, GLuint};
+pub use stats::FrameStats;
+
+use crate::shared::types::DirtyRect;
 
 #[derive(Debug)]
 pub struct Renderer {
@@ -16,14 +22,22 @@ pub struct Renderer {
     pub vao: GLuint,
     pub vbo: GLuint,
     pub fbo: GLuint,
-    pub pbo: GLuint,
+    pub pbos: [GLuint; PBO_COUNT],
+    /// Signaled once the GPU is done reading each PBO
+    fences: [Option<Fence>; PBO_COUNT],
+    pbo_index: usize,
+    /// Size of the front texture, the size of the last paint
+    front_size: (i32, i32),
     pub width: i32,
     pub height: i32,
     pub refresh_rate: u32,
+    /// Device scale factor of the window, CEF lays out the web UI in pixels divided by it
+    pub scale_factor: f64,
+    pub stats: FrameStats,
 }
 
 impl Renderer {
-    pub fn new((width, height): (i32, i32), refresh_rate: u32) -> Self {
+    pub fn new((width, height): (i32, i32), refresh_rate: u32, scale_factor: f64) -> Self {
         unsafe {
             let vertex_shader = utils::compile_shader(gl::VERTEX_SHADER, VERTEX_SRC);
             let fragment_shader = utils::compile_shader(gl::FRAGMENT_SHADER, FRAGMENT_SRC);
@@ -47,7 +61,7 @@ impl Renderer {
             let (vao, vbo) = utils::create_geometry(program);
             let fbo = utils::create_fbo(back_texture);
 
-            let pbo = utils::create_pbo(width, height);
+            let pbos = array::from_fn(|_| utils::create_pbo(width, height));
 
             let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
             if status != gl::FRAMEBUFFER_COMPLETE {
@@ -63,10 +77,15 @@ impl Renderer {
                 vao,
                 vbo,
                 fbo,
-                pbo,
+                pbos,
+                fences: Default::default(),
+                pbo_index: 0,
+                front_size: (width, height),
                 width,
                 height,
                 refresh_rate,
+                scale_factor,
+                stats: FrameStats::default(),
             }
         }
     }
@@ -82,57 +101,124 @@ impl Renderer {
         }
     }
 
-    // A Pixel Buffer Object (PBO) is used to upload the buffer directly to the GPU,
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    /// Size of the web UI in device independent pixels, CEF paints it scaled back up
+    pub fn view_size(&self) -> (i32, i32) {
+        let scale = |size: i32| ((size as f64 / self.scale_factor).floor() as i32).max(1);
+        (scale(self.width), scale(self.height))
+    }
+
+    /// Whether a paint of the given size matches the renderer, scaled view sizes are rounded
+    /// by CEF and may be a few pixels off the window, the texture is stretched over them
+    pub fn fits(&self, width: i32, height: i32) -> bool {
+        let tolerance = self.scale_factor.ceil() as i32;
+        (self.width - width).abs() <= tolerance && (self.height - height).abs() <= tolerance
+    }
+
+    // Pixel Buffer Objects (PBO) are used to upload the buffer directly to the GPU,
     // offering better performance than direct texture uploads.
-    // This helps reduce the time the current GL context remains locked.
+    // They are filled in turn and guarded by fences, so that an upload doesn't wait
+    // for the GPU to be done with the previous ones.
+    // The dirty rects are packed in the same PBO and copied to the texture one by one.
     pub fn paint(
-        &self,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
+        &mut self,
+        rects: &[DirtyRect],
         buffer: *const u8,
-        full_width: i32,
+        (full_width, full_height): (i32, i32),
     ) {
-        utils::resize_pbo(self.pbo, self.width, self.height);
-        utils::resize_texture(self.front_texture, self.width, self.height);
+        let start = Instant::now();
+
+        // A resized texture loses its content, the whole buffer is uploaded again
+        let rects = match self.front_size == (full_width, full_height) {
+            true => rects
+                .iter()
+                .filter_map(|rect| rect.clip(full_width, full_height))
+                .collect::<Vec<_>>(),
+            false => {
+                utils::resize_texture(self.front_texture, full_width, full_height);
+                self.front_size = (full_width, full_height);
+                vec![DirtyRect::full(full_width, full_height)]
+            }
+        };
+
+        let size = rects
+            .iter()
+            .map(|rect| rect.width * rect.height * BYTES_PER_PIXEL)
+            .sum::<i32>();
+
+        if size == 0 {
+            return;
+        }
+
+        self.pbo_index = (self.pbo_index + 1) % PBO_COUNT;
+        let pbo = self.pbos[self.pbo_index];
+
+        if let Some(fence) = self.fences[self.pbo_index].take()
+            && fence.wait()
+        {
+            self.stats.upload_stalls += 1;
+        }
+
+        utils::resize_pbo(pbo, size);
 
         unsafe {
-            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, self.pbo);
+            gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, pbo);
 
-            let row_bytes = width * BYTES_PER_PIXEL;
-            let stride = full_width * BYTES_PER_PIXEL;
+            // The fence already guarantees that the GPU doesn't read the PBO anymore
+            let access =
+                gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_RANGE_BIT | gl::MAP_UNSYNCHRONIZED_BIT;
+            let ptr = gl::MapBufferRange(gl::PIXEL_UNPACK_BUFFER, 0, size as GLsizeiptr, access)
+                as *mut u8;
 
-            let ptr = gl::MapBuffer(gl::PIXEL_UNPACK_BUFFER, gl::WRITE_ONLY) as *mut u8;
             if !ptr.is_null() {
-                for row in 0..height {
-                    let src_offset = (y + row) * stride + (x * BYTES_PER_PIXEL);
-                    let dst_offset = row * row_bytes;
+                let stride = full_width * BYTES_PER_PIXEL;
+                let mut offsets = Vec::with_capacity(rects.len());
+                let mut offset = 0;
+
+                for rect in &rects {
+                    let row_bytes = rect.width * BYTES_PER_PIXEL;
+                    offsets.push(offset);
+
+                    for row in 0..rect.height {
+                        let src_offset = (rect.y + row) * stride + (rect.x * BYTES_PER_PIXEL);
 
-                    let src_ptr = buffer.add(src_offset as usize);
-                    let dst_ptr = ptr.add(dst_offset as usize);
+                        let src_ptr = buffer.add(src_offset as usize);
+                        let dst_ptr = ptr.add(offset as usize);
 
-                    ptr::copy_nonoverlapping(src_ptr, dst_ptr, row_bytes as usize);
+                        ptr::copy_nonoverlapping(src_ptr, dst_ptr, row_bytes as usize);
+                        offset += row_bytes;
+                    }
                 }
 
                 gl::UnmapBuffer(gl::PIXEL_UNPACK_BUFFER);
-            }
 
-            gl::BindTexture(gl::TEXTURE_2D, self.front_texture);
-            gl::TexSubImage2D(
-                gl::TEXTURE_2D,
-                0,
-                x,
-                y,
-                width,
-                height,
-                gl::BGRA,
-                gl::UNSIGNED_BYTE,
-                std::ptr::null(),
-            );
+                gl::BindTexture(gl::TEXTURE_2D, self.front_texture);
+
+                for (rect, offset) in rects.iter().zip(offsets) {
+                    gl::TexSubImage2D(
+                        gl::TEXTURE_2D,
+                        0,
+                        rect.x,
+                        rect.y,
+                        rect.width,
+                        rect.height,
+                        gl::BGRA,
+                        gl::UNSIGNED_BYTE,
+                        offset as usize as *const _,
+                    );
+                }
+
+                self.fences[self.pbo_index] = Fence::new();
+            }
 
             gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
         }
+
+        self.stats
+            .record_paint(rects.len(), size as u64, start.elapsed());
     }
 
     pub fn draw(&self) {
@@ -167,7 +253,7 @@ impl Drop for Renderer {
             gl::DeleteTextures(1, &self.back_texture);
             gl::DeleteBuffers(1, &self.vbo);
             gl::DeleteVertexArrays(1, &self.vao);
-            gl::DeleteBuffers(1, &self.pbo);
+            gl::DeleteBuffers(PBO_COUNT as i32, self.pbos.as_ptr());
             gl::DeleteBuffers(1, &self.fbo);
         }
     }
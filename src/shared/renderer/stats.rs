@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+// Weight of the last sample in the averages
+const SMOOTHING: f64 = 0.1;
+
+/// Counters of the rendering pipeline, for diagnostics
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct FrameStats {
+    /// Frames composed on the main window
+    pub frames: u64,
+    /// Frames where the video was rendered again, the others only updated the web UI
+    pub video_frames: u64,
+    /// Paints of the web UI uploaded to the GPU
+    pub paints: u64,
+    pub dirty_rects: u64,
+    pub uploaded_bytes: u64,
+    /// Uploads that waited for the GPU to release a pixel buffer
+    pub upload_stalls: u64,
+    /// Average time to compose a frame, in milliseconds
+    pub frame_time: f64,
+    /// Average time to upload a paint, in milliseconds
+    pub upload_time: f64,
+}
+
+impl FrameStats {
+    pub fn record_frame(&mut self, video: bool, time: Duration) {
+        self.frames += 1;
+        self.video_frames += video as u64;
+        self.frame_time = average(self.frame_time, time);
+    }
+
+    pub fn record_paint(&mut self, dirty_rects: usize, bytes: u64, time: Duration) {
+        self.paints += 1;
+        self.dirty_rects += dirty_rects as u64;
+        self.uploaded_bytes += bytes;
+        self.upload_time = average(self.upload_time, time);
+    }
+}
+
+fn average(average: f64, sample: Duration) -> f64 {
+    let sample = sample.as_secs_f64() * 1000.0;
+
+    if average == 0.0 {
+        return sample;
+    }
+
+    average + (sample - average) * SMOOTHING
+}
@@ -151,14 +151,13 @@ pub fn compile_shader(kind: GLenum, src: &str) -> GLuint {
     }
 }
 
-pub fn resize_pbo(pbo: GLuint, width: i32, height: i32) {
+pub fn resize_pbo(pbo: GLuint, new_size: i32) {
     unsafe {
         gl::BindBuffer(gl::PIXEL_UNPACK_BUFFER, pbo);
 
         let mut pbo_size = 0;
         gl::GetBufferParameteriv(gl::PIXEL_UNPACK_BUFFER, gl::BUFFER_SIZE, &mut pbo_size);
 
-        let new_size = width * height * BYTES_PER_PIXEL;
         if new_size > pbo_size {
             gl::BufferData(
                 gl::PIXEL_UNPACK_BUFFER,
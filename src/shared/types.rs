@@ -1,6 +1,6 @@
 use winit::event::MouseButton;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Cursor {
     Default,
     Pointer,
@@ -10,6 +10,51 @@ pub enum Cursor {
     ZoomOut,
     Wait,
     None,
+    Custom(CustomCursorImage),
+}
+
+/// Cursor image drawn by the web UI, in physical pixels
+#[derive(Debug, Clone)]
+pub struct CustomCursorImage {
+    pub rgba: Vec<u8>,
+    pub width: u16,
+    pub height: u16,
+    pub hotspot: (u16, u16),
+}
+
+/// Area of the web UI painted again by CEF, in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirtyRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl DirtyRect {
+    pub fn full(width: i32, height: i32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    /// Part of the rect within a buffer of the given size, if any
+    pub fn clip(&self, width: i32, height: i32) -> Option<Self> {
+        let x = self.x.clamp(0, width);
+        let y = self.y.clamp(0, height);
+        let right = (self.x + self.width).clamp(x, width);
+        let bottom = (self.y + self.height).clamp(y, height);
+
+        (right > x && bottom > y).then_some(Self {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -46,5 +91,11 @@ pub enum UserEvent {
     Raise,
     Show,
     Hide,
+    /// Opens or closes the picture-in-picture window, `None` toggles it
+    PictureInPicture(Option<bool>),
+    /// Restarts the shell with another profile
+    SwitchProfile(String),
     Quit,
+    /// A subsystem has events for the main loop
+    Wake,
 }
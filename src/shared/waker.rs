@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossbeam_channel::{Receiver, SendError, Sender, unbounded};
+use once_cell::sync::OnceCell;
+use winit::event_loop::EventLoopProxy;
+
+use super::types::UserEvent;
+
+static PROXY: OnceCell<EventLoopProxy<UserEvent>> = OnceCell::new();
+// Whether a wake event is already queued, the ones sent before the loop runs are merged
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// The main loop sleeps until an event of the window or of a subsystem arrives,
+/// the subsystems running on other threads wake it through the event loop proxy
+pub fn init(proxy: EventLoopProxy<UserEvent>) {
+    PROXY.get_or_init(|| proxy);
+}
+
+pub fn wake() {
+    if !PENDING.swap(true, Ordering::AcqRel)
+        && let Some(proxy) = PROXY.get()
+    {
+        proxy.send_event(UserEvent::Wake).ok();
+    }
+}
+
+/// Called by the main loop before handling the events of the subsystems
+pub fn woken() {
+    PENDING.store(false, Ordering::Release);
+}
+
+/// Channel whose messages wake the main loop
+pub fn channel<T>() -> (WakingSender<T>, Receiver<T>) {
+    let (sender, receiver) = unbounded::<T>();
+    (WakingSender(sender), receiver)
+}
+
+#[derive(Debug)]
+pub struct WakingSender<T>(Sender<T>);
+
+impl<T> WakingSender<T> {
+    pub fn send(&self, message: T) -> Result<(), SendError<T>> {
+        self.0.send(message)?;
+        wake();
+
+        Ok(())
+    }
+}
+
+impl<T> Clone for WakingSender<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
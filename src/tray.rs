@@ -1,33 +1,42 @@
-use std::{thread, time::Duration};
+use std::{cell::RefCell, thread};
 
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use crossbeam_channel::Receiver;
 use gtk::glib;
 use rust_i18n::t;
 use tray_icon::{
     Icon, TrayIcon, TrayIconBuilder,
-    menu::{Menu, MenuEvent, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, Submenu},
 };
 
-use crate::{config::TrayConfig, shared::types::UserEvent};
+use crate::{
+    config::TrayConfig,
+    shared::{types::UserEvent, waker},
+};
 
+// Ids of the profile items are the profile names with this prefix
+const PROFILE_ITEM_PREFIX: &str = "profile:";
 const ICON: &[u8] = include_bytes!(concat!(
     env!("CARGO_MANIFEST_DIR"),
     "/data/icons/symbolic.png"
 ));
 
-enum TrayEvent {
-    Visibility(bool),
+thread_local! {
+    // The tray icon lives on the gtk thread, updates are run there once gtk is idle
+    static TRAY_ICON: RefCell<Option<TrayIcon>> = const { RefCell::new(None) };
 }
 
 pub struct Tray {
     receiver: Receiver<UserEvent>,
-    tray_sender: Sender<TrayEvent>,
+    profile: String,
+    profiles: Vec<String>,
 }
 
 impl Tray {
     pub fn new(config: TrayConfig) -> Self {
-        let (sender, receiver) = unbounded::<UserEvent>();
-        let (tray_sender, tray_receiver) = unbounded::<TrayEvent>();
+        let (sender, receiver) = waker::channel::<UserEvent>();
+
+        let profile = config.profile.clone();
+        let profiles = config.profiles.clone();
 
         thread::spawn(|| {
             gtk::init().expect("Failed to initialize gtk");
@@ -37,29 +46,10 @@ impl Tray {
                 rust_i18n::set_locale(&language.to_str());
             }
 
-            let menu = Self::create_menu();
+            let menu = Self::create_menu(&config.profile, &config.profiles);
             let tray = Self::create(menu, config);
 
-            glib::timeout_add_local(Duration::from_millis(16), move || {
-                tray_receiver.try_iter().for_each(|event| match event {
-                    TrayEvent::Visibility(state) => {
-                        let menu = Self::create_menu();
-                        menu.remove_at(0);
-
-                        if state {
-                            let hide_item = Self::create_menu_item("hide");
-                            menu.prepend(&hide_item).ok();
-                        } else {
-                            let show_item = Self::create_menu_item("show");
-                            menu.prepend(&show_item).ok();
-                        }
-
-                        tray.set_menu(Some(menu));
-                    }
-                });
-
-                glib::ControlFlow::Continue
-            });
+            TRAY_ICON.with_borrow_mut(|tray_icon| *tray_icon = Some(tray));
 
             gtk::main();
         });
@@ -73,6 +63,16 @@ impl Tray {
                 sender.send(UserEvent::Show).ok();
             }
 
+            if event.id == "pip" {
+                sender.send(UserEvent::PictureInPicture(None)).ok();
+            }
+
+            if let Some(profile) = event.id.0.strip_prefix(PROFILE_ITEM_PREFIX) {
+                sender
+                    .send(UserEvent::SwitchProfile(profile.to_owned()))
+                    .ok();
+            }
+
             if event.id == "quit" {
                 sender.send(UserEvent::Quit).ok();
             }
@@ -80,7 +80,8 @@ impl Tray {
 
         Self {
             receiver,
-            tray_sender,
+            profile,
+            profiles,
         }
     }
 
@@ -88,15 +89,41 @@ impl Tray {
         MenuItem::with_id(id, t!(id), true, None)
     }
 
-    fn create_menu() -> Box<Menu> {
+    /// Lists the profiles once there are several, the current one is checked
+    fn create_profile_menu(profile: &str, profiles: &[String]) -> Option<Submenu> {
+        if profiles.len() < 2 {
+            return None;
+        }
+
+        let submenu = Submenu::new(t!("profile"), true);
+        profiles.iter().for_each(|name| {
+            let id = format!("{PROFILE_ITEM_PREFIX}{name}");
+            let current = name == profile;
+            let item = CheckMenuItem::with_id(id, name, !current, current, None);
+            submenu.append(&item).ok();
+        });
+
+        Some(submenu)
+    }
+
+    fn create_menu(profile: &str, profiles: &[String]) -> Box<Menu> {
         let empty_item = Self::create_menu_item("");
+        let pip_item = Self::create_menu_item("pip");
         let quit_item = Self::create_menu_item("quit");
 
         let version_label = format!("v{}", env!("CARGO_PKG_VERSION"));
         let version_item = MenuItem::new(version_label.as_str(), false, None);
 
         let menu = Menu::new();
-        menu.append_items(&[&empty_item, &quit_item, &version_item])
+        menu.append_items(&[&empty_item, &pip_item])
+            .expect("Failed to append menu items");
+
+        if let Some(profile_menu) = Self::create_profile_menu(profile, profiles) {
+            menu.append(&profile_menu)
+                .expect("Failed to append menu items");
+        }
+
+        menu.append_items(&[&quit_item, &version_item])
             .expect("Failed to append menu items");
 
         Box::new(menu)
@@ -114,9 +141,25 @@ impl Tray {
     }
 
     pub fn update(&self, visibility: bool) {
-        self.tray_sender
-            .send(TrayEvent::Visibility(visibility))
-            .ok();
+        let profile = self.profile.clone();
+        let profiles = self.profiles.clone();
+
+        glib::idle_add_once(move || {
+            TRAY_ICON.with_borrow(|tray_icon| {
+                if let Some(tray_icon) = tray_icon {
+                    let menu = Self::create_menu(&profile, &profiles);
+                    menu.remove_at(0);
+
+                    let item = match visibility {
+                        true => Self::create_menu_item("hide"),
+                        false => Self::create_menu_item("show"),
+                    };
+
+                    menu.prepend(&item).ok();
+                    tray_icon.set_menu(Some(menu));
+                }
+            });
+        });
     }
 
     pub fn events<F: FnMut(UserEvent)>(&self, handler: F) {
@@ -1,7 +1,7 @@
 use cef_dll_sys::cef_cursor_type_t;
 use winit::{event::MouseButton, keyboard::KeyCode};
 
-use crate::shared::types::{Cursor, MouseState};
+use crate::shared::types::{Cursor, CustomCursorImage, MouseState};
 
 impl From<MouseState> for cef::MouseEvent {
     fn from(state: MouseState) -> Self {
@@ -36,6 +36,40 @@ impl From<cef::CursorType> for Cursor {
     }
 }
 
+impl TryFrom<&cef::CursorInfo> for Cursor {
+    type Error = &'static str;
+
+    fn try_from(info: &cef::CursorInfo) -> Result<Self, Self::Error> {
+        let width = u16::try_from(info.size.width).map_err(|_| "Invalid cursor width")?;
+        let height = u16::try_from(info.size.height).map_err(|_| "Invalid cursor height")?;
+        let length = width as usize * height as usize * 4;
+
+        if info.buffer.is_null() || length == 0 {
+            return Err("Empty cursor image");
+        }
+
+        // CEF gives BGRA pixels
+        let buffer = unsafe { std::slice::from_raw_parts(info.buffer as *const u8, length) };
+        let rgba = buffer
+            .chunks_exact(4)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect();
+
+        // The hotspot must be within the image or the cursor is rejected
+        let hotspot = (
+            info.hotspot.x.clamp(0, width as i32 - 1) as u16,
+            info.hotspot.y.clamp(0, height as i32 - 1) as u16,
+        );
+
+        Ok(Cursor::Custom(CustomCursorImage {
+            rgba,
+            width,
+            height,
+            hotspot,
+        }))
+    }
+}
+
 pub struct WindowsKeyCode(pub i32);
 
 impl TryFrom<KeyCode> for WindowsKeyCode {
@@ -1,7 +1,11 @@
 use crate::{
     cef_impl,
     shared::with_renderer_read,
-    webview::{BROWSER, app::client::WebViewClient},
+    webview::{
+        BROWSER,
+        app::{client::WebViewClient, scheme_handler::WebViewSchemeHandlerFactory},
+        constants::BUNDLE_SCHEME,
+    },
 };
 
 cef_impl!(
@@ -10,6 +14,11 @@ cef_impl!(
     sys_type = cef_dll_sys::cef_browser_process_handler_t,
     {
         fn on_context_initialized(&self) {
+            // Every domain of the scheme, the factory serves the bundle and the shell pages
+            let scheme = CefString::from(BUNDLE_SCHEME);
+            let mut factory = WebViewSchemeHandlerFactory::new();
+            register_scheme_handler_factory(Some(&scheme), None, Some(&mut factory));
+
             with_renderer_read(|renderer| {
                 let mut client = WebViewClient::new();
 
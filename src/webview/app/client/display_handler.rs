@@ -1,7 +1,10 @@
 use std::os::raw::{c_int, c_ulong};
 
+use cef_dll_sys::cef_cursor_type_t;
+
 use crate::{
     cef_impl,
+    shared::types::Cursor,
     webview::{SENDER, WebViewEvent},
 };
 
@@ -15,10 +18,17 @@ cef_impl!(
             _browser: Option<&mut Browser>,
             _cursor: c_ulong,
             type_: CursorType,
-            _custom_cursor_info: Option<&CursorInfo>,
+            custom_cursor_info: Option<&CursorInfo>,
         ) -> c_int {
+            let cursor = match (type_.as_ref(), custom_cursor_info) {
+                (cef_cursor_type_t::CT_CUSTOM, Some(info)) => {
+                    Cursor::try_from(info).unwrap_or(Cursor::Default)
+                }
+                _ => type_.into(),
+            };
+
             if let Some(sender) = SENDER.get() {
-                sender.send(WebViewEvent::Cursor(type_.into())).ok();
+                sender.send(WebViewEvent::Cursor(cursor)).ok();
                 return 1;
             }
 
@@ -1,5 +1,7 @@
 use std::os::raw::c_int;
 
+use cef_dll_sys::cef_errorcode_t;
+
 use crate::{
     cef_impl,
     webview::{
@@ -38,10 +40,43 @@ cef_impl!(
         ) {
             if let Some(frame) = frame
                 && frame.is_main() == 1
-                && http_status_code == 200
                 && let Some(sender) = SENDER.get()
             {
-                sender.send(WebViewEvent::Loaded).ok();
+                let url = CefString::from(&frame.url()).to_string();
+
+                match http_status_code {
+                    200 => {
+                        sender.send(WebViewEvent::Loaded(url)).ok();
+                    }
+                    // Network errors have no status and are reported by `on_load_error`
+                    400.. if url.starts_with("http") => {
+                        let reason = format!("HTTP {http_status_code}");
+                        sender.send(WebViewEvent::LoadFailed(url, reason)).ok();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        fn on_load_error(
+            &self,
+            _browser: Option<&mut Browser>,
+            frame: Option<&mut Frame>,
+            error_code: Errorcode,
+            error_text: Option<&CefString>,
+            failed_url: Option<&CefString>,
+        ) {
+            // Aborted loads are navigations replaced by another one, not failures
+            if let Some(frame) = frame
+                && frame.is_main() == 1
+                && cef_errorcode_t::from(error_code) != cef_errorcode_t::ERR_ABORTED
+                && let Some(failed_url) = failed_url
+                && let Some(sender) = SENDER.get()
+            {
+                let reason = error_text.map(CefString::to_string).unwrap_or_default();
+                sender
+                    .send(WebViewEvent::LoadFailed(failed_url.to_string(), reason))
+                    .ok();
             }
         }
     }
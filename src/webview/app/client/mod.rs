@@ -1,5 +1,4 @@
 mod display_handler;
-mod keyboard_handler;
 mod lifespan_handler;
 mod load_handler;
 mod render_handler;
@@ -15,7 +14,6 @@ use crate::{
     WebViewEvent, cef_impl,
     webview::{
         SENDER,
-        app::client::keyboard_handler::WebViewKeyboardHandler,
         constants::{IPC_MESSAGE, READY_MESSAGE},
     },
 };
@@ -41,10 +39,6 @@ cef_impl!(
             Some(WebViewLoadHandler::new())
         }
 
-        fn keyboard_handler(&self) -> Option<KeyboardHandler> {
-            Some(WebViewKeyboardHandler::new())
-        }
-
         fn on_process_message_received(
             &self,
             _browser: Option<&mut Browser>,
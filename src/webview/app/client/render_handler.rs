@@ -1,8 +1,8 @@
-use std::os::raw::c_int;
+use std::{os::raw::c_int, slice};
 
 use crate::{
     WebViewEvent, cef_impl,
-    shared::{with_gl, with_renderer_read},
+    shared::{types::DirtyRect, with_gl, with_renderer_read, with_renderer_write},
     webview::SENDER,
 };
 
@@ -11,47 +11,86 @@ cef_impl!(
     name = RenderHandler,
     sys_type = cef_dll_sys::cef_render_handler_t,
     {
+        // The view is in device independent pixels, CEF paints it at the scale of `screen_info`
         fn view_rect(&self, _browser: Option<&mut Browser>, rect: Option<&mut Rect>) {
             with_renderer_read(|renderer| {
                 if let Some(rect) = rect {
+                    let (width, height) = renderer.view_size();
                     *rect = Rect {
                         x: 0,
                         y: 0,
-                        width: renderer.width,
-                        height: renderer.height,
+                        width,
+                        height,
                     };
                 }
             });
         }
 
+        fn screen_info(
+            &self,
+            _browser: Option<&mut Browser>,
+            screen_info: Option<&mut ScreenInfo>,
+        ) -> c_int {
+            let mut handled = 0;
+
+            with_renderer_read(|renderer| {
+                if let Some(screen_info) = screen_info {
+                    let (width, height) = renderer.view_size();
+                    let rect = Rect {
+                        x: 0,
+                        y: 0,
+                        width,
+                        height,
+                    };
+
+                    screen_info.device_scale_factor = renderer.scale_factor as f32;
+                    screen_info.rect = rect.clone();
+                    screen_info.available_rect = rect;
+                    handled = 1;
+                }
+            });
+
+            handled
+        }
+
         // The `width` and `height` parameters may be outdated due to asynchronous updates from `on_paint` and `view_rect`.
-        // We compare them against the current renderer dimensions before painting.
+        // We compare them against the current renderer dimensions, in physical pixels, before painting.
         // If they don't match, send a Resized event to ask for a repaint.
         fn on_paint(
             &self,
             _browser: Option<&mut Browser>,
             _type_: PaintElementType,
-            _dirty_rects_count: usize,
+            dirty_rects_count: usize,
             dirty_rects: Option<&Rect>,
             buffer: *const u8,
             width: c_int,
             height: c_int,
         ) {
+            // The bindings only expose the first element of CEF's array of dirty rects,
+            // the whole view is uploaded when there is none
+            let rects = match dirty_rects {
+                Some(first) if dirty_rects_count > 0 => {
+                    // SAFETY: `first` is the start of the `dirty_rects_count` rects CEF passes,
+                    // they are valid for the duration of the call
+                    let rects =
+                        unsafe { slice::from_raw_parts(first as *const Rect, dirty_rects_count) };
+                    rects
+                        .iter()
+                        .map(|rect| DirtyRect {
+                            x: rect.x,
+                            y: rect.y,
+                            width: rect.width,
+                            height: rect.height,
+                        })
+                        .collect()
+                }
+                _ => vec![DirtyRect::full(width, height)],
+            };
+
             with_gl(|_, _| {
-                with_renderer_read(|renderer| {
-                    if renderer.width == width && renderer.height == height {
-                        if let Some(dirty) = dirty_rects {
-                            renderer.paint(
-                                dirty.x,
-                                dirty.y,
-                                dirty.width,
-                                dirty.height,
-                                buffer,
-                                width,
-                            );
-                        } else {
-                            renderer.paint(0, 0, width, height, buffer, width);
-                        }
+                with_renderer_write(|renderer| {
+                    if renderer.fits(width, height) {
+                        renderer.paint(&rects, buffer, (width, height));
 
                         if let Some(sender) = SENDER.get() {
                             sender.send(WebViewEvent::Paint).ok();
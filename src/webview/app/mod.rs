@@ -1,13 +1,21 @@
 mod browser_process_handler;
 mod client;
 mod render_process_handler;
+mod resource_handler;
+mod scheme_handler;
 mod utils;
 mod v8_handler;
 
+use std::os::raw::c_int;
+
 use browser_process_handler::WebViewBrowserProcessHandler;
+use cef_dll_sys::cef_scheme_options_t;
 use render_process_handler::WebViewRenderProcessHandler;
 
-use crate::{cef_impl, constants::CMD_SWITCHES};
+use crate::{
+    cef_impl, gpu,
+    webview::{GPU_CONFIG, constants::BUNDLE_SCHEME},
+};
 
 cef_impl!(
     prefix = "WebView",
@@ -19,13 +27,27 @@ cef_impl!(
             _process_type: Option<&CefString>,
             command_line: Option<&mut CommandLine>,
         ) {
-            if let Some(line) = command_line {
-                CMD_SWITCHES.iter().for_each(|switch| {
-                    line.append_switch(Some(&CefString::from(switch.to_owned())));
+            if let Some(line) = command_line
+                && let Some(gpu_config) = GPU_CONFIG.get()
+            {
+                gpu::get_switches(gpu_config).iter().for_each(|switch| {
+                    line.append_switch(Some(&CefString::from(switch.as_str())));
                 });
             }
         }
 
+        fn on_register_custom_schemes(&self, registrar: Option<&mut SchemeRegistrar>) {
+            // Standard and secure so the bundled web UI behaves like the remote one
+            let options = cef_scheme_options_t::CEF_SCHEME_OPTION_STANDARD as c_int
+                | cef_scheme_options_t::CEF_SCHEME_OPTION_SECURE as c_int
+                | cef_scheme_options_t::CEF_SCHEME_OPTION_CORS_ENABLED as c_int
+                | cef_scheme_options_t::CEF_SCHEME_OPTION_FETCH_ENABLED as c_int;
+
+            if let Some(registrar) = registrar {
+                registrar.add_custom_scheme(Some(&CefString::from(BUNDLE_SCHEME)), options);
+            }
+        }
+
         fn browser_process_handler(&self) -> Option<BrowserProcessHandler> {
             Some(WebViewBrowserProcessHandler::new())
         }
@@ -0,0 +1,98 @@
+use std::{
+    os::raw::c_int,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use crate::{cef_impl, webview::bundle::BundleFile};
+
+cef_impl!(
+    prefix = "WebView",
+    name = ResourceHandler,
+    sys_type = cef_dll_sys::cef_resource_handler_t,
+    fields = {
+        file: Option<Arc<BundleFile>>,
+        offset: Arc<AtomicUsize>,
+    },
+    {
+        fn open(
+            &self,
+            _request: Option<&mut Request>,
+            handle_request: Option<&mut c_int>,
+            _callback: Option<&mut Callback>,
+        ) -> c_int {
+            // The file is already in memory, the request is handled right away
+            if let Some(handle_request) = handle_request {
+                *handle_request = 1;
+            }
+
+            1
+        }
+
+        fn response_headers(
+            &self,
+            response: Option<&mut Response>,
+            response_length: Option<&mut i64>,
+            _redirect_url: Option<&mut CefString>,
+        ) {
+            if let Some(response) = response {
+                match &self.file {
+                    Some(file) => {
+                        response.set_status(200);
+                        response.set_mime_type(Some(&CefString::from(file.mime_type)));
+                    }
+                    None => {
+                        response.set_status(404);
+                        response.set_status_text(Some(&CefString::from("Not Found")));
+                    }
+                }
+            }
+
+            if let Some(response_length) = response_length {
+                *response_length = self.file.as_ref().map_or(0, |file| file.data.len() as i64);
+            }
+        }
+
+        fn read(
+            &self,
+            data_out: *mut u8,
+            bytes_to_read: c_int,
+            bytes_read: Option<&mut c_int>,
+            _callback: Option<&mut ResourceReadCallback>,
+        ) -> c_int {
+            let Some(file) = &self.file else {
+                return 0;
+            };
+
+            let offset = self.offset.load(Ordering::Relaxed);
+            let count = file
+                .data
+                .len()
+                .saturating_sub(offset)
+                .min(bytes_to_read.max(0) as usize);
+
+            if let Some(bytes_read) = bytes_read {
+                *bytes_read = count as c_int;
+            }
+
+            if count == 0 {
+                return 0;
+            }
+
+            unsafe {
+                std::ptr::copy_nonoverlapping(file.data[offset..].as_ptr(), data_out, count);
+            }
+            self.offset.store(offset + count, Ordering::Relaxed);
+
+            1
+        }
+
+        fn cancel(&self) {
+            if let Some(file) = &self.file {
+                self.offset.store(file.data.len(), Ordering::Relaxed);
+            }
+        }
+    }
+);
@@ -0,0 +1,47 @@
+use std::sync::{Arc, atomic::AtomicUsize};
+
+use url::Url;
+
+use super::resource_handler::WebViewResourceHandler;
+use crate::{
+    cef_impl,
+    webview::{
+        BUNDLE,
+        bundle::BundleFile,
+        constants::{BUNDLE_DOMAIN, ERROR_PAGE, ERROR_PAGE_PATH, SHELL_DOMAIN},
+    },
+};
+
+cef_impl!(
+    prefix = "WebView",
+    name = SchemeHandlerFactory,
+    sys_type = cef_dll_sys::cef_scheme_handler_factory_t,
+    {
+        fn create(
+            &self,
+            _browser: Option<&mut Browser>,
+            _frame: Option<&mut Frame>,
+            _scheme_name: Option<&CefString>,
+            request: Option<&mut Request>,
+        ) -> Option<ResourceHandler> {
+            let url = CefString::from(&request?.url()).to_string();
+            let url = Url::parse(&url).ok()?;
+
+            // Unknown files still get a handler, answering with a 404
+            let file = match url.host_str() {
+                Some(BUNDLE_DOMAIN) => BUNDLE.get().and_then(|bundle| bundle.get(url.path())),
+                Some(SHELL_DOMAIN) if url.path() == ERROR_PAGE_PATH => Some(BundleFile {
+                    data: ERROR_PAGE.as_bytes().to_vec(),
+                    mime_type: "text/html",
+                }),
+                _ => None,
+            }
+            .map(Arc::new);
+
+            Some(WebViewResourceHandler::new(
+                file,
+                Arc::new(AtomicUsize::new(0)),
+            ))
+        }
+    }
+);
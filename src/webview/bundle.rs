@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::Context;
+use once_cell::sync::OnceCell;
+use tracing::{info, warn};
+
+use super::constants::{BUNDLE_INDEX, BUNDLE_VERSION_FILE, DEFAULT_MIME_TYPE, MIME_TYPES};
+
+// Stremio Web build embedded at compile time, see the `bundled-ui` feature
+#[cfg(feature = "bundled-ui")]
+const EMBEDDED_ARCHIVE: Option<&[u8]> = Some(include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/data/stremio-web.tar"
+)));
+#[cfg(not(feature = "bundled-ui"))]
+const EMBEDDED_ARCHIVE: Option<&[u8]> = None;
+
+enum Source {
+    Directory(PathBuf),
+    Archive(HashMap<String, Vec<u8>>),
+}
+
+impl Source {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        match path.is_dir() {
+            true => Ok(Self::Directory(path.to_path_buf())),
+            false => {
+                let file = File::open(path)?;
+                Self::unpack(file)
+            }
+        }
+    }
+
+    /// Reads every file of a tar archive in memory
+    fn unpack<R: Read>(reader: R) -> anyhow::Result<Self> {
+        let mut files = HashMap::new();
+
+        for entry in tar::Archive::new(reader).entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path()?.to_string_lossy().to_string();
+            let path = path.trim_start_matches("./").to_owned();
+
+            let mut data = vec![];
+            entry.read_to_end(&mut data)?;
+            files.insert(path, data);
+        }
+
+        // Archives often wrap the build in a single top level directory
+        if !files.contains_key(BUNDLE_INDEX)
+            && let Some(prefix) = files
+                .keys()
+                .find_map(|path| path.strip_suffix(BUNDLE_INDEX))
+                .filter(|prefix| prefix.ends_with('/') && !prefix[..prefix.len() - 1].contains('/'))
+                .map(str::to_owned)
+        {
+            files = files
+                .into_iter()
+                .filter_map(|(path, data)| {
+                    path.strip_prefix(&prefix)
+                        .map(|path| (path.to_owned(), data))
+                })
+                .collect();
+        }
+
+        Ok(Self::Archive(files))
+    }
+
+    fn read(&self, path: &str) -> Option<Vec<u8>> {
+        match self {
+            Self::Directory(dir) => {
+                // Only plain components, the bundle directory can't be escaped
+                let relative = Path::new(path);
+                if !relative
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return None;
+                }
+
+                fs::read(dir.join(relative)).ok()
+            }
+            Self::Archive(files) => files.get(path).cloned(),
+        }
+    }
+
+    fn version(&self) -> Option<String> {
+        self.read(BUNDLE_VERSION_FILE)
+            .map(|version| String::from_utf8_lossy(&version).trim().to_owned())
+    }
+}
+
+pub struct BundleFile {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+}
+
+/// A local copy of the web UI, loaded from the first usable location on first use
+pub struct Bundle {
+    paths: Vec<PathBuf>,
+    pinned_version: Option<String>,
+    source: OnceCell<Option<Source>>,
+}
+
+impl Bundle {
+    pub fn new(paths: Vec<PathBuf>, pinned_version: Option<String>) -> Self {
+        Self {
+            paths,
+            pinned_version,
+            source: OnceCell::new(),
+        }
+    }
+
+    fn source(&self) -> Option<&Source> {
+        self.source.get_or_init(|| self.load()).as_ref()
+    }
+
+    fn load(&self) -> Option<Source> {
+        let sources = self
+            .paths
+            .iter()
+            .filter(|path| path.exists())
+            .map(|path| {
+                let source = Source::open(path)
+                    .with_context(|| format!("Failed to open web UI bundle {}", path.display()));
+                (path.display().to_string(), source)
+            })
+            .chain(EMBEDDED_ARCHIVE.into_iter().map(|archive| {
+                let source = Source::unpack(archive).context("Failed to unpack embedded web UI");
+                ("embedded archive".to_owned(), source)
+            }));
+
+        for (location, source) in sources {
+            let source = match source {
+                Ok(source) => source,
+                Err(e) => {
+                    warn!(target: "webview", "{e:#}");
+                    continue;
+                }
+            };
+
+            let version = source.version();
+
+            if let Some(pinned_version) = &self.pinned_version
+                && version.as_ref() != Some(pinned_version)
+            {
+                warn!(
+                    target: "webview",
+                    "Skipping web UI bundle {location}: version {} is not the pinned {pinned_version}",
+                    version.as_deref().unwrap_or("unknown")
+                );
+                continue;
+            }
+
+            info!(
+                target: "webview",
+                "Using web UI bundle {location}, version {}",
+                version.as_deref().unwrap_or("unknown")
+            );
+
+            return Some(source);
+        }
+
+        None
+    }
+
+    pub fn available(&self) -> bool {
+        self.source().is_some()
+    }
+
+    /// Returns a file of the bundle, directories resolve to their index
+    pub fn get(&self, path: &str) -> Option<BundleFile> {
+        let path = path.trim_start_matches('/');
+        let path = match path.is_empty() || path.ends_with('/') {
+            true => format!("{path}{BUNDLE_INDEX}"),
+            false => path.to_owned(),
+        };
+
+        let data = self.source()?.read(&path)?;
+
+        let mime_type = Path::new(&path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                MIME_TYPES
+                    .iter()
+                    .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+            })
+            .map(|(_, mime_type)| *mime_type)
+            .unwrap_or(DEFAULT_MIME_TYPE);
+
+        Some(BundleFile { data, mime_type })
+    }
+}
@@ -5,19 +5,36 @@ macro_rules! cef_impl {
         name = $name:ident,
         sys_type = $sys:ty,
         { $($body:tt)* }
+    ) => {
+        $crate::cef_impl!(
+            prefix = $prefix,
+            name = $name,
+            sys_type = $sys,
+            fields = {},
+            { $($body)* }
+        );
+    };
+    (
+        prefix = $prefix:literal,
+        name = $name:ident,
+        sys_type = $sys:ty,
+        fields = { $($field:ident: $field_type:ty),* $(,)? },
+        { $($body:tt)* }
     ) => {
         paste::paste! {
             use cef::{rc::*, *};
 
             pub struct [<$prefix $name>] {
                 object: *mut RcImpl<$sys, Self>,
+                $($field: $field_type,)*
             }
 
             impl [<$prefix $name>] {
                 #[allow(clippy::new_ret_no_self)]
-                pub fn new() -> $name {
+                pub fn new($($field: $field_type),*) -> $name {
                     $name::new(Self {
                         object: std::ptr::null_mut(),
+                        $($field,)*
                     })
                 }
             }
@@ -36,7 +53,10 @@ macro_rules! cef_impl {
                         rc_impl
                     };
 
-                    Self { object }
+                    Self {
+                        object,
+                        $($field: self.$field.clone(),)*
+                    }
                 }
             }
 
@@ -1,4 +1,5 @@
 pub const PRELOAD_SCRIPT: &str = include_str!("preload.js");
+pub const ERROR_PAGE: &str = include_str!("error.html");
 pub const IPC_SENDER: &str = "__postMessage";
 pub const IPC_RECEIVER: &str = "__onMessage";
 
@@ -6,4 +7,36 @@ pub const IPC_RECEIVER: &str = "__onMessage";
 pub const IPC_MESSAGE: &str = "IPC";
 pub const READY_MESSAGE: &str = "READY";
 
-pub const ZOOM_AMOUNT: f64 = 0.2;
+// Bundled web UI, served by the scheme handler
+pub const BUNDLE_SCHEME: &str = "stremio-app";
+pub const BUNDLE_DOMAIN: &str = "ui";
+pub const BUNDLE_URL: &str = "stremio-app://ui/";
+pub const BUNDLE_INDEX: &str = "index.html";
+pub const BUNDLE_VERSION_FILE: &str = "version";
+
+// Pages of the shell itself, served by the same scheme handler
+pub const SHELL_DOMAIN: &str = "shell";
+pub const ERROR_PAGE_PATH: &str = "/error";
+pub const ERROR_PAGE_URL: &str = "stremio-app://shell/error";
+
+pub const MIME_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("css", "text/css"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("wasm", "application/wasm"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("txt", "text/plain"),
+];
+pub const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
@@ -1,5 +1,6 @@
 mod adapters;
 mod app;
+mod bundle;
 mod cef_impl;
 mod constants;
 
@@ -7,6 +8,7 @@ use std::path::PathBuf;
 
 use adapters::{NativeKeyCode, WindowsKeyCode};
 use app::WebViewApp;
+use bundle::Bundle;
 use cef::{
     App, Browser, BrowserHost, BrowserSettings, CefString, Client, Frame, ImplBrowser,
     ImplBrowserHost, ImplCommandLine, ImplDragData, ImplFrame, LogSeverity, Settings, api_hash,
@@ -16,8 +18,8 @@ use cef_dll_sys::{
     cef_drag_operations_mask_t, cef_event_flags_t, cef_key_event_type_t, cef_log_severity_t,
     cef_mouse_button_type_t, cef_paint_element_type_t, cef_pointer_type_t, cef_touch_event_type_t,
 };
-use constants::IPC_SENDER;
-use crossbeam_channel::{Receiver, Sender, unbounded};
+use constants::{BUNDLE_SCHEME, BUNDLE_URL, ERROR_PAGE_URL, IPC_SENDER, SHELL_DOMAIN};
+use crossbeam_channel::Receiver;
 use once_cell::sync::OnceCell;
 use url::Url;
 use winit::{
@@ -26,19 +28,26 @@ use winit::{
 };
 
 use crate::{
-    config::WebViewConfig,
-    shared::types::{Cursor, MouseState},
+    config::{GpuConfig, WebViewConfig},
+    shared::{
+        types::{Cursor, MouseState},
+        waker::{self, WakingSender},
+        with_renderer_read,
+    },
 };
 
 use arboard::Clipboard;
 use std::process::Command;
 
-static SENDER: OnceCell<Sender<WebViewEvent>> = OnceCell::new();
+static SENDER: OnceCell<WakingSender<WebViewEvent>> = OnceCell::new();
 static BROWSER: OnceCell<Browser> = OnceCell::new();
+static GPU_CONFIG: OnceCell<GpuConfig> = OnceCell::new();
+static BUNDLE: OnceCell<Bundle> = OnceCell::new();
 
 pub enum WebViewEvent {
     Ready,
-    Loaded,
+    Loaded(String),
+    LoadFailed(String, String),
     Paint,
     Resized,
     Cursor(Cursor),
@@ -51,6 +60,7 @@ pub struct WebView {
     settings: Settings,
     app: App,
     receiver: Receiver<WebViewEvent>,
+    zoom_step: f64,
 }
 
 impl WebView {
@@ -59,8 +69,12 @@ impl WebView {
 
         let args = Args::new();
 
-        let (sender, receiver) = unbounded::<WebViewEvent>();
+        let (sender, receiver) = waker::channel::<WebViewEvent>();
         SENDER.get_or_init(|| sender);
+        GPU_CONFIG.get_or_init(|| config.gpu.clone());
+        BUNDLE.get_or_init(|| {
+            Bundle::new(config.bundle_paths.clone(), config.bundle_version.clone())
+        });
 
         let app = WebViewApp::new();
 
@@ -73,7 +87,7 @@ impl WebView {
             cache_path: config.cache_dir.to_str().unwrap().into(),
             log_file: config.log_file.to_str().unwrap().into(),
             log_severity: LogSeverity::from(cef_log_severity_t::LOGSEVERITY_VERBOSE),
-            user_agent: "StremioShell/1.0.0".into(), // Custom user agent like community v5
+            user_agent: config.user_agent.as_str().into(),
             ..Default::default()
         };
 
@@ -82,6 +96,7 @@ impl WebView {
             settings,
             app,
             receiver,
+            zoom_step: config.zoom_step,
         }
     }
 
@@ -151,6 +166,36 @@ impl WebView {
         }
     }
 
+    /// Loads the bundled web UI, returns false if no bundle is available
+    pub fn load_bundle(&self) -> bool {
+        let available = BUNDLE.get().is_some_and(Bundle::available);
+        if available {
+            self.navigate(BUNDLE_URL);
+        }
+
+        available
+    }
+
+    /// Whether the page is served by the shell, the bundled web UI or a page of the shell
+    pub fn is_local_url(url: &str) -> bool {
+        Url::parse(url).is_ok_and(|url| url.scheme() == BUNDLE_SCHEME)
+    }
+
+    /// Whether the page is one of the shell, such as the error page, rather than a web UI
+    pub fn is_shell_page(url: &str) -> bool {
+        Url::parse(url)
+            .is_ok_and(|url| url.scheme() == BUNDLE_SCHEME && url.host_str() == Some(SHELL_DOMAIN))
+    }
+
+    /// Shows the error page of the shell for a page that failed to load
+    pub fn show_error_page(&self, url: &str, reason: &str) {
+        if let Ok(error_page) =
+            Url::parse_with_params(ERROR_PAGE_URL, &[("url", url), ("reason", reason)])
+        {
+            self.navigate(error_page.as_str());
+        }
+    }
+
     pub fn dev_tools(&self, state: bool) {
         if let Some(host) = self.browser_host() {
             if state {
@@ -186,15 +231,53 @@ impl WebView {
         }
     }
 
+    /// Tells the webview that the device scale factor changed, it's read again from `screen_info`
+    pub fn scale_changed(&self) {
+        if let Some(host) = self.browser_host() {
+            host.notify_screen_info_changed();
+        }
+
+        self.update();
+    }
+
     pub fn focused(&mut self, state: bool) {
         if let Some(host) = self.browser_host() {
             host.set_focus(state.into());
         }
     }
 
+    pub fn zoom_in(&self) {
+        if let Some(host) = self.browser_host() {
+            host.set_zoom_level(host.zoom_level() + self.zoom_step);
+        }
+    }
+
+    pub fn zoom_out(&self) {
+        if let Some(host) = self.browser_host() {
+            host.set_zoom_level(host.zoom_level() - self.zoom_step);
+        }
+    }
+
+    /// Matches the frame rate of the offscreen rendering to the monitor of the window
+    pub fn set_frame_rate(&self, frame_rate: u32) {
+        if let Some(host) = self.browser_host() {
+            host.set_windowless_frame_rate(frame_rate as i32);
+        }
+    }
+
+    pub fn set_zoom_step(&mut self, zoom_step: f64) {
+        self.zoom_step = zoom_step;
+    }
+
+    pub fn zoom_reset(&self) {
+        if let Some(host) = self.browser_host() {
+            host.set_zoom_level(0.0);
+        }
+    }
+
     pub fn mouse_moved(&mut self, state: MouseState) {
         if let Some(host) = self.browser_host() {
-            let event = state.into();
+            let event = mouse_event(state);
             let mouse_leave = (!state.over).into();
             host.send_mouse_move_event(Some(&event), mouse_leave);
         }
@@ -202,7 +285,7 @@ impl WebView {
 
     pub fn mouse_wheel(&self, state: MouseState) {
         if let Some(host) = self.browser_host() {
-            let event = state.into();
+            let event = mouse_event(state);
             host.send_mouse_wheel_event(Some(&event), state.delta.0, state.delta.1);
         }
     }
@@ -229,7 +312,7 @@ impl WebView {
             if let Some(button_type) = button_type
                 && let Some(host) = browser.host()
             {
-                let event = state.into();
+                let event = mouse_event(state);
 
                 host.send_mouse_click_event(Some(&event), button_type.into(), mouse_up.into(), 1);
             }
@@ -248,8 +331,8 @@ impl WebView {
             let event = cef::TouchEvent {
                 type_: event_type.into(),
                 pointer_type: cef_pointer_type_t::CEF_POINTER_TYPE_TOUCH.into(),
-                x: touch.location.x as f32,
-                y: touch.location.y as f32,
+                x: (touch.location.x / scale_factor()) as f32,
+                y: (touch.location.y / scale_factor()) as f32,
                 ..Default::default()
             };
 
@@ -399,7 +482,7 @@ impl WebView {
 
     pub fn file_hover(&self, path: PathBuf, state: MouseState) {
         if let Some(host) = self.browser_host() {
-            let event = state.into();
+            let event = mouse_event(state);
 
             let file_path = path.to_str().map(CefString::from);
             let file_name = path
@@ -426,7 +509,7 @@ impl WebView {
 
     pub fn file_drop(&self, state: MouseState) {
         if let Some(host) = self.browser_host() {
-            let event = state.into();
+            let event = mouse_event(state);
             host.drag_target_drop(Some(&event));
         }
     }
@@ -437,3 +520,21 @@ impl WebView {
         }
     }
 }
+
+/// Device scale factor of the window, positions sent to CEF are in device independent pixels
+fn scale_factor() -> f64 {
+    let mut scale_factor = 1.0;
+    with_renderer_read(|renderer| scale_factor = renderer.scale_factor);
+
+    scale_factor
+}
+
+fn mouse_event(state: MouseState) -> cef::MouseEvent {
+    let scale_factor = scale_factor();
+    let mut event = cef::MouseEvent::from(state);
+
+    event.x = (event.x as f64 / scale_factor).round() as i32;
+    event.y = (event.y as f64 / scale_factor).round() as i32;
+
+    event
+}